@@ -0,0 +1,49 @@
+// benches/encoding_size.rs
+//
+// Compares encoded document sizes against to_compact_json
+//
+// Run with `cargo bench --bench encoding_size`.
+
+use open_menu_standard::{create_template, generate_order, FieldDictionary, OmsDocument, OmsResult};
+
+/// NTAG215 user memory in bytes
+const NTAG215_BYTES: usize = 504;
+
+fn encodings(doc: &OmsDocument) -> OmsResult<Vec<(&'static str, usize)>> {
+    let dictionary = FieldDictionary::standard();
+    Ok(vec![
+        ("compact json", doc.to_compact_json()?.len()),
+        ("cbor", doc.to_cbor()?.len()),
+        ("cbor + dict", doc.to_cbor_with(dictionary)?.len()),
+        ("msgpack", doc.to_msgpack()?.len()),
+        ("msgpack + dict", doc.to_msgpack_with(dictionary)?.len()),
+    ])
+}
+
+fn report(label: &str, doc: &OmsDocument) -> OmsResult<()> {
+    let sizes = encodings(doc)?;
+    let baseline = sizes[0].1 as f64;
+    println!("{}", label);
+    for (name, size) in sizes {
+        let fits = if size <= NTAG215_BYTES { "fits NTAG215" } else { "" };
+        println!("  {:<16} {:>7} bytes {:>6.1}%  {}", name, size, size as f64 / baseline * 100.0, fits);
+    }
+    Ok(())
+}
+
+fn main() -> OmsResult<()> {
+    for template in ["restaurant", "cafe", "fast-food", "coffee-shop", "pizzeria"] {
+        let mut doc = create_template(template)?;
+        report(&format!("{} menu", template), &doc)?;
+
+        generate_order(&mut doc, Some("customer-1"))?;
+        report(&format!("{} order", template), &doc)?;
+    }
+
+    // A single-item document, as written to an NFC tag
+    let mut doc = create_template("coffee-shop")?;
+    doc.items.truncate(1);
+    doc.items[0].customizations = None;
+    report("single item", &doc)?;
+    Ok(())
+}
@@ -0,0 +1,350 @@
+// src/binary.rs
+//
+// CBOR and MessagePack encodings of OMS documents
+
+use crate::types::*;
+use crate::{OmsError, OmsResult};
+use once_cell::sync::Lazy;
+use serde_json::{Map, Number, Value};
+use std::collections::HashMap;
+
+/// Field names of the standard dictionary, in code order
+///
+/// Codes are positions in this list, so names may only ever be appended.
+/// The most common fields come first because CBOR stores codes below 24
+/// in a single byte.
+const STANDARD_FIELDS: &[&str] = &[
+    "id", "name", "description", "type", "options", "customizations", "price_adjustment", "base_price",
+    "currency", "category", "subcategory", "default", "required", "items", "quantity", "selection",
+    "customization_id", "option_id", "option_ids", "selected_customizations", "value", "unit", "oms_version",
+    "metadata", "vendor", "created", "source", "locale", "vendor_id", "location_id", "location_name",
+    "order", "status", "customer", "customer_notes", "payment", "subtotal", "tax", "tip", "total", "method",
+    "item_note", "item_price", "calculated", "components", "min", "max", "min_selections", "max_selections",
+    "step", "visible_when", "incompatible_with", "sub_customizations", "sub_selections", "nutrition",
+    "nutrition_adjustments", "unit_nutrition_adjustments", "unit_price_adjustment", "calories", "fat",
+    "carbohydrates", "protein", "sodium", "cholesterol", "details", "vitamins", "minerals", "serving_size",
+    "serving_size_description", "nutrition_per_100g", "adjusted_nutrition", "daily_value_percent",
+    "allergens", "dietary_flags", "health_claims", "ingredients", "ingredient_id", "nutrition_standards",
+    "us_fda", "eu_regulation", "daily_value_year", "reference_intake_description", "availability", "days_of_week",
+    "times_of_day", "start_date", "end_date", "open", "close", "day", "hours", "ranges", "popularity", "rank",
+    "stock", "restock_time", "tags", "image_url", "instructions", "contact", "email", "phone", "website",
+    "location", "street", "city", "region", "postal_code", "country", "cuisine", "services", "delivery",
+    "delivery_time", "pickup_time", "exchange_rates", "from", "to", "rate", "as_of", "applied_at", "extensions",
+    "line_id",
+];
+
+static STANDARD_DICTIONARY: Lazy<FieldDictionary> = Lazy::new(|| FieldDictionary::new(STANDARD_FIELDS.iter().copied()));
+
+/// Maps field names to small integer codes to shrink binary encodings
+///
+/// Object keys found in the dictionary are written as integers, which JSON
+/// cannot produce, so encoded documents never confuse codes with real keys.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldDictionary {
+    names: Vec<String>,
+    codes: HashMap<String, u64>,
+}
+
+impl FieldDictionary {
+    /// Create a dictionary where each name is coded by its position
+    pub fn new<I, S>(names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let names: Vec<String> = names.into_iter().map(Into::into).collect();
+        let codes = names.iter().enumerate().map(|(code, name)| (name.clone(), code as u64)).collect();
+        Self { names, codes }
+    }
+
+    /// Dictionary covering the fields of the OMS specification
+    pub fn standard() -> &'static FieldDictionary {
+        &STANDARD_DICTIONARY
+    }
+
+    /// Code for a field name
+    pub fn code(&self, name: &str) -> Option<u64> {
+        self.codes.get(name).copied()
+    }
+
+    /// Field name for a code
+    pub fn name(&self, code: u64) -> Option<&str> {
+        usize::try_from(code).ok().and_then(|index| self.names.get(index)).map(String::as_str)
+    }
+}
+
+impl OmsDocument {
+    /// Serialize the OMS document to CBOR
+    pub fn to_cbor(&self) -> OmsResult<Vec<u8>> {
+        encode_cbor(self, None)
+    }
+
+    /// Serialize the OMS document to CBOR with field names replaced by dictionary codes
+    pub fn to_cbor_with(&self, dictionary: &FieldDictionary) -> OmsResult<Vec<u8>> {
+        encode_cbor(self, Some(dictionary))
+    }
+
+    /// Deserialize an OMS document from CBOR
+    pub fn from_cbor(bytes: &[u8]) -> OmsResult<Self> {
+        decode_cbor(bytes, None)
+    }
+
+    /// Deserialize an OMS document from CBOR written with a field dictionary
+    pub fn from_cbor_with(bytes: &[u8], dictionary: &FieldDictionary) -> OmsResult<Self> {
+        decode_cbor(bytes, Some(dictionary))
+    }
+
+    /// Serialize the OMS document to MessagePack
+    pub fn to_msgpack(&self) -> OmsResult<Vec<u8>> {
+        encode_msgpack(self, None)
+    }
+
+    /// Serialize the OMS document to MessagePack with field names replaced by dictionary codes
+    pub fn to_msgpack_with(&self, dictionary: &FieldDictionary) -> OmsResult<Vec<u8>> {
+        encode_msgpack(self, Some(dictionary))
+    }
+
+    /// Deserialize an OMS document from MessagePack
+    pub fn from_msgpack(bytes: &[u8]) -> OmsResult<Self> {
+        decode_msgpack(bytes, None)
+    }
+
+    /// Deserialize an OMS document from MessagePack written with a field dictionary
+    pub fn from_msgpack_with(bytes: &[u8], dictionary: &FieldDictionary) -> OmsResult<Self> {
+        decode_msgpack(bytes, Some(dictionary))
+    }
+}
+
+/// Build a document from decoded JSON and validate it, like `OmsDocument::from_json`
+fn document_from_value(value: Value) -> OmsResult<OmsDocument> {
+    let document: OmsDocument = serde_json::from_value(value)?;
+    document.validate()?;
+    Ok(document)
+}
+
+fn binary_error(format: &str, error: impl std::fmt::Display) -> OmsError {
+    OmsError::BinaryEncodingError(format!("{}: {}", format, error))
+}
+
+/// Look up the field name for an integer key
+fn field_name(code: Option<u64>, dictionary: Option<&FieldDictionary>) -> OmsResult<String> {
+    code.and_then(|code| dictionary?.name(code))
+        .map(str::to_string)
+        .ok_or_else(|| OmsError::BinaryEncodingError(format!("unknown field code {:?}", code)))
+}
+
+fn encode_cbor(document: &OmsDocument, dictionary: Option<&FieldDictionary>) -> OmsResult<Vec<u8>> {
+    let value = json_to_cbor(serde_json::to_value(document)?, dictionary);
+    let mut bytes = Vec::new();
+    ciborium::into_writer(&value, &mut bytes).map_err(|e| binary_error("CBOR", e))?;
+    Ok(bytes)
+}
+
+fn decode_cbor(bytes: &[u8], dictionary: Option<&FieldDictionary>) -> OmsResult<OmsDocument> {
+    let value: ciborium::Value = ciborium::from_reader(bytes).map_err(|e| binary_error("CBOR", e))?;
+    document_from_value(cbor_to_json(value, dictionary)?)
+}
+
+fn json_to_cbor(value: Value, dictionary: Option<&FieldDictionary>) -> ciborium::Value {
+    use ciborium::Value as Cbor;
+
+    match value {
+        Value::Null => Cbor::Null,
+        Value::Bool(b) => Cbor::Bool(b),
+        Value::Number(n) => match (n.as_u64(), n.as_i64()) {
+            (Some(u), _) => Cbor::Integer(u.into()),
+            (None, Some(i)) => Cbor::Integer(i.into()),
+            _ => Cbor::Float(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => Cbor::Text(s),
+        Value::Array(values) => Cbor::Array(values.into_iter().map(|v| json_to_cbor(v, dictionary)).collect()),
+        Value::Object(map) => Cbor::Map(map.into_iter().map(|(key, v)| {
+            let key = match dictionary.and_then(|d| d.code(&key)) {
+                Some(code) => Cbor::Integer(code.into()),
+                None => Cbor::Text(key),
+            };
+            (key, json_to_cbor(v, dictionary))
+        }).collect()),
+    }
+}
+
+fn cbor_to_json(value: ciborium::Value, dictionary: Option<&FieldDictionary>) -> OmsResult<Value> {
+    use ciborium::Value as Cbor;
+
+    Ok(match value {
+        Cbor::Null => Value::Null,
+        Cbor::Bool(b) => Value::Bool(b),
+        Cbor::Integer(i) => {
+            let i = i128::from(i);
+            match (u64::try_from(i), i64::try_from(i)) {
+                (Ok(u), _) => Value::from(u),
+                (_, Ok(i)) => Value::from(i),
+                _ => return Err(binary_error("CBOR", format!("integer {} out of range", i))),
+            }
+        },
+        Cbor::Float(f) => Number::from_f64(f)
+            .map(Value::Number)
+            .ok_or_else(|| binary_error("CBOR", format!("{} is not a JSON number", f)))?,
+        Cbor::Text(s) => Value::String(s),
+        Cbor::Array(values) => Value::Array(values.into_iter()
+            .map(|v| cbor_to_json(v, dictionary))
+            .collect::<OmsResult<_>>()?),
+        Cbor::Map(entries) => {
+            let mut map = Map::new();
+            for (key, v) in entries {
+                let key = match key {
+                    Cbor::Text(key) => key,
+                    Cbor::Integer(code) => field_name(u64::try_from(i128::from(code)).ok(), dictionary)?,
+                    other => return Err(binary_error("CBOR", format!("unsupported map key {:?}", other))),
+                };
+                map.insert(key, cbor_to_json(v, dictionary)?);
+            }
+            Value::Object(map)
+        },
+        other => return Err(binary_error("CBOR", format!("unsupported value {:?}", other))),
+    })
+}
+
+fn encode_msgpack(document: &OmsDocument, dictionary: Option<&FieldDictionary>) -> OmsResult<Vec<u8>> {
+    let value = json_to_msgpack(serde_json::to_value(document)?, dictionary);
+    let mut bytes = Vec::new();
+    rmpv::encode::write_value(&mut bytes, &value).map_err(|e| binary_error("MessagePack", e))?;
+    Ok(bytes)
+}
+
+fn decode_msgpack(bytes: &[u8], dictionary: Option<&FieldDictionary>) -> OmsResult<OmsDocument> {
+    let value = rmpv::decode::read_value(&mut &bytes[..]).map_err(|e| binary_error("MessagePack", e))?;
+    document_from_value(msgpack_to_json(value, dictionary)?)
+}
+
+fn json_to_msgpack(value: Value, dictionary: Option<&FieldDictionary>) -> rmpv::Value {
+    use rmpv::Value as MsgPack;
+
+    match value {
+        Value::Null => MsgPack::Nil,
+        Value::Bool(b) => MsgPack::Boolean(b),
+        Value::Number(n) => match (n.as_u64(), n.as_i64(), n.as_f64()) {
+            (Some(u), _, _) => MsgPack::from(u),
+            (None, Some(i), _) => MsgPack::from(i),
+            // Use single precision whenever it holds the value exactly
+            (_, _, Some(f)) if f64::from(f as f32) == f => MsgPack::F32(f as f32),
+            (_, _, f) => MsgPack::F64(f.unwrap_or_default()),
+        },
+        Value::String(s) => MsgPack::from(s),
+        Value::Array(values) => MsgPack::Array(values.into_iter().map(|v| json_to_msgpack(v, dictionary)).collect()),
+        Value::Object(map) => MsgPack::Map(map.into_iter().map(|(key, v)| {
+            let key = match dictionary.and_then(|d| d.code(&key)) {
+                Some(code) => MsgPack::from(code),
+                None => MsgPack::from(key),
+            };
+            (key, json_to_msgpack(v, dictionary))
+        }).collect()),
+    }
+}
+
+fn msgpack_to_json(value: rmpv::Value, dictionary: Option<&FieldDictionary>) -> OmsResult<Value> {
+    use rmpv::Value as MsgPack;
+
+    let float = |f: f64| Number::from_f64(f)
+        .map(Value::Number)
+        .ok_or_else(|| binary_error("MessagePack", format!("{} is not a JSON number", f)));
+
+    Ok(match value {
+        MsgPack::Nil => Value::Null,
+        MsgPack::Boolean(b) => Value::Bool(b),
+        MsgPack::Integer(i) => match (i.as_u64(), i.as_i64()) {
+            (Some(u), _) => Value::from(u),
+            (None, Some(i)) => Value::from(i),
+            _ => return Err(binary_error("MessagePack", format!("integer {} out of range", i))),
+        },
+        MsgPack::F32(f) => float(f64::from(f))?,
+        MsgPack::F64(f) => float(f)?,
+        MsgPack::String(s) => match s.into_str() {
+            Some(s) => Value::String(s),
+            None => return Err(binary_error("MessagePack", "string is not valid UTF-8")),
+        },
+        MsgPack::Array(values) => Value::Array(values.into_iter()
+            .map(|v| msgpack_to_json(v, dictionary))
+            .collect::<OmsResult<_>>()?),
+        MsgPack::Map(entries) => {
+            let mut map = Map::new();
+            for (key, v) in entries {
+                let key = match key {
+                    MsgPack::String(key) => key.into_str()
+                        .ok_or_else(|| binary_error("MessagePack", "map key is not valid UTF-8"))?,
+                    MsgPack::Integer(code) => field_name(code.as_u64(), dictionary)?,
+                    other => return Err(binary_error("MessagePack", format!("unsupported map key {}", other))),
+                };
+                map.insert(key, msgpack_to_json(v, dictionary)?);
+            }
+            Value::Object(map)
+        },
+        other => return Err(binary_error("MessagePack", format!("unsupported value {}", other))),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{create_template, generate_order};
+
+    fn documents() -> Vec<OmsDocument> {
+        ["restaurant", "cafe", "fast-food", "coffee-shop", "pizzeria"].iter().map(|template| {
+            let mut doc = create_template(template).unwrap();
+            doc.items[0].description = Some(LocalizedText::from_translations([("en", "Fresh"), ("fr", "Frais")]));
+            generate_order(&mut doc, Some("customer-1")).unwrap();
+            doc.add_extension("loyalty", serde_json::json!({"points": 10, "ratio": 0.1, "balance": -3}));
+            doc
+        }).collect()
+    }
+
+    #[test]
+    fn test_binary_round_trip_matches_json() {
+        let dictionary = FieldDictionary::standard();
+        for doc in documents() {
+            let json = serde_json::to_value(&doc).unwrap();
+            let decoded = [
+                OmsDocument::from_cbor(&doc.to_cbor().unwrap()).unwrap(),
+                OmsDocument::from_cbor_with(&doc.to_cbor_with(dictionary).unwrap(), dictionary).unwrap(),
+                OmsDocument::from_msgpack(&doc.to_msgpack().unwrap()).unwrap(),
+                OmsDocument::from_msgpack_with(&doc.to_msgpack_with(dictionary).unwrap(), dictionary).unwrap(),
+            ];
+            for decoded in decoded {
+                assert_eq!(decoded, doc);
+                assert_eq!(serde_json::to_value(&decoded).unwrap(), json);
+            }
+        }
+    }
+
+    #[test]
+    fn test_dictionary_shrinks_encoding() {
+        let dictionary = FieldDictionary::standard();
+        for doc in documents() {
+            let json = doc.to_compact_json().unwrap().len();
+            let cbor = doc.to_cbor().unwrap().len();
+            let cbor_dict = doc.to_cbor_with(dictionary).unwrap().len();
+            let msgpack_dict = doc.to_msgpack_with(dictionary).unwrap().len();
+            assert!(cbor < json, "{} >= {}", cbor, json);
+            assert!(cbor_dict < cbor * 3 / 4, "{} vs {}", cbor_dict, cbor);
+            assert!(msgpack_dict < json, "{} >= {}", msgpack_dict, json);
+        }
+    }
+
+    #[test]
+    fn test_dictionary_codes() {
+        let dictionary = FieldDictionary::standard();
+        assert_eq!(dictionary.code("id"), Some(0));
+        assert_eq!(dictionary.name(1), Some("name"));
+        assert_eq!(dictionary.name(10_000), None);
+
+        // Keys outside the dictionary, such as translation languages, stay as text
+        let doc = documents().remove(0);
+        let bytes = doc.to_cbor_with(dictionary).unwrap();
+        assert!(matches!(OmsDocument::from_cbor(&bytes), Err(OmsError::BinaryEncodingError(_))));
+
+        let custom = FieldDictionary::new(["items", "oms_version"]);
+        let bytes = doc.to_msgpack_with(&custom).unwrap();
+        assert_eq!(OmsDocument::from_msgpack_with(&bytes, &custom).unwrap(), doc);
+        assert!(OmsDocument::from_msgpack_with(&bytes, dictionary).is_err());
+    }
+}
@@ -0,0 +1,169 @@
+// src/canonical.rs
+//
+// Canonical JSON serialization (RFC 8785 JSON Canonicalization Scheme) and content hashes
+//
+// Equal documents always serialize to the same bytes: object keys are sorted by their
+// UTF-16 code units, numbers are written the way ECMAScript prints them and no
+// whitespace is emitted.
+
+use crate::types::OmsDocument;
+use crate::OmsResult;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+/// Largest integer every JSON implementation represents exactly (2^53)
+const MAX_EXACT_INTEGER: u64 = 1 << 53;
+
+/// Serialize a JSON value in canonical form
+pub fn canonical_json(value: &Value) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value);
+    out
+}
+
+fn write_value(out: &mut String, value: &Value) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(value) => out.push_str(if *value { "true" } else { "false" }),
+        Value::Number(number) => out.push_str(&format_number(number)),
+        Value::String(value) => write_string(out, value),
+        Value::Array(values) => {
+            out.push('[');
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_value(out, value);
+            }
+            out.push(']');
+        },
+        Value::Object(map) => {
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            // Keys compare by UTF-16 code units, which differs from `str` order above U+FFFF
+            entries.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+            out.push('{');
+            for (index, (key, value)) in entries.into_iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_string(out, key);
+                out.push(':');
+                write_value(out, value);
+            }
+            out.push('}');
+        },
+    }
+}
+
+fn write_string(out: &mut String, value: &str) {
+    // serde_json escapes exactly what JCS requires: quotes, backslashes and control characters
+    out.push_str(&serde_json::to_string(value).expect("strings serialize"));
+}
+
+/// Format a number the way ECMAScript's `Number.prototype.toString` does
+fn format_number(number: &serde_json::Number) -> String {
+    if let Some(value) = number.as_u64().filter(|value| *value <= MAX_EXACT_INTEGER) {
+        return value.to_string();
+    }
+    if let Some(value) = number.as_i64().filter(|value| value.unsigned_abs() <= MAX_EXACT_INTEGER) {
+        return value.to_string();
+    }
+    format_double(number.as_f64().unwrap_or_default())
+}
+
+fn format_double(value: f64) -> String {
+    if value == 0.0 || !value.is_finite() {
+        return "0".to_string();
+    }
+
+    // Rust's exponent format gives the shortest digits that round-trip, e.g. `1.25e-7`
+    let scientific = format!("{:e}", value.abs());
+    let (mantissa, exponent) = scientific.split_once('e').expect("exponent format");
+    let digits = mantissa.replace('.', "");
+    let k = digits.len() as i32;
+    // Position of the decimal point relative to the digits
+    let n = exponent.parse::<i32>().expect("exponent format") + 1;
+
+    let formatted = if k <= n && n <= 21 {
+        format!("{}{}", digits, "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        let (integer, fraction) = digits.split_at(n as usize);
+        format!("{}.{}", integer, fraction)
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{}", "0".repeat(-n as usize), digits)
+    } else {
+        let (first, rest) = digits.split_at(1);
+        let mantissa = if rest.is_empty() { first.to_string() } else { format!("{}.{}", first, rest) };
+        format!("{}e{}{}", mantissa, if n > 0 { "+" } else { "-" }, (n - 1).abs())
+    };
+    if value < 0.0 { format!("-{}", formatted) } else { formatted }
+}
+
+impl OmsDocument {
+    /// Serialize the document as canonical JSON
+    ///
+    /// Unlike [`OmsDocument::to_compact_json`], the output does not depend on the
+    /// iteration order of maps such as extensions, so equal documents give equal bytes.
+    pub fn to_canonical_json(&self) -> OmsResult<String> {
+        Ok(canonical_json(&serde_json::to_value(self)?))
+    }
+
+    /// SHA-256 of the document's canonical JSON, as lowercase hex
+    pub fn content_hash(&self) -> OmsResult<String> {
+        Ok(hex::encode(Sha256::digest(self.to_canonical_json()?.as_bytes())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::create_template;
+    use serde_json::json;
+
+    #[test]
+    fn test_canonical_json() {
+        // Examples from RFC 8785
+        let value: Value = serde_json::from_str(r#"{
+            "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+            "literals": [null, true, false]
+        }"#).unwrap();
+        assert_eq!(canonical_json(&value), r#"{"literals":[null,true,false],"string":"€$\u000f\nA'B\"\\\\\"/"}"#);
+        let long: f64 = "333333333.33333329".parse().unwrap();
+        let numbers = json!([long, 1E30, 4.50, 2e-3, 0.000000000000000000000000001]);
+        assert_eq!(canonical_json(&numbers), "[333333333.3333333,1e+30,4.5,0.002,1e-27]");
+
+        let sorted: Value = serde_json::from_str(r#"{"\u20ac":0,"\r":0,"\ufb33":0,"1":0,"\ud83d\ude00":0,"\u0080":0,"\u00f6":0}"#).unwrap();
+        assert_eq!(
+            canonical_json(&sorted),
+            "{\"\\r\":0,\"1\":0,\"\u{80}\":0,\"ö\":0,\"€\":0,\"😀\":0,\"\u{fb33}\":0}",
+        );
+
+        let numbers = json!([0.0, -0.0, 1e21, 1e20, -12.5, 1e-7, 123456789012345678_u64, -42]);
+        assert_eq!(canonical_json(&numbers), "[0,0,1e+21,100000000000000000000,-12.5,1e-7,123456789012345680,-42]");
+    }
+
+    #[test]
+    fn test_content_hash_ignores_map_order() {
+        let mut doc = create_template("cafe").unwrap();
+        let mut reordered = doc.clone();
+        let keys = ["x-pos", "x-aggregator", "x-loyalty", "x-kitchen", "x-delivery", "x-tax"];
+        let extensions = doc.extensions.get_or_insert_with(Default::default);
+        for (index, key) in keys.iter().enumerate() {
+            extensions.insert(key.to_string(), json!(index));
+        }
+        let extensions = reordered.extensions.get_or_insert_with(Default::default);
+        for (index, key) in keys.iter().enumerate().rev() {
+            extensions.insert(key.to_string(), json!(index));
+        }
+
+        assert_eq!(doc.to_canonical_json().unwrap(), reordered.to_canonical_json().unwrap());
+        assert_eq!(doc.content_hash().unwrap(), reordered.content_hash().unwrap());
+        assert_eq!(doc.content_hash().unwrap().len(), 64);
+
+        let parsed = OmsDocument::from_json(&doc.to_canonical_json().unwrap()).unwrap();
+        assert_eq!(parsed, doc);
+
+        doc.vendor.name = "Other cafe".to_string();
+        assert_ne!(doc.content_hash().unwrap(), reordered.content_hash().unwrap());
+    }
+}
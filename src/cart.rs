@@ -0,0 +1,464 @@
+// src/cart.rs
+//
+// Cart and order-line model for building orders from a menu
+
+use crate::types::*;
+use crate::utils::{calculate_price_adjustments, generate_order};
+use crate::validation::{validate_item_in_stock, validate_selected_customizations};
+use crate::{OmsError, OmsResult};
+use serde::{Deserialize, Serialize};
+
+/// A single line in a cart, referencing a menu item with its own configuration
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OrderLine {
+    /// Unique identifier for the line
+    pub line_id: String,
+
+    /// ID of the menu item being ordered
+    pub item_id: String,
+
+    /// Number of units ordered
+    pub quantity: u32,
+
+    /// Customizations selected for this line
+    pub selections: Vec<SelectedCustomization>,
+
+    /// Optional note specific to this line
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+
+    /// Calculated price of a single unit after customizations
+    pub unit_price: f64,
+
+    /// Calculated price of the whole line (unit price times quantity)
+    pub total_price: f64,
+}
+
+impl OrderLine {
+    /// Whether this line has the same configuration as another item selection
+    fn has_configuration(&self, item_id: &str, selections: &[SelectedCustomization], note: Option<&str>) -> bool {
+        self.item_id == item_id
+            && self.note.as_deref() == note
+            && normalize_selections(&self.selections) == normalize_selections(selections)
+    }
+
+    /// Increase the quantity and total price, failing if the quantity would overflow
+    fn add_quantity(&mut self, quantity: u32) -> OmsResult<()> {
+        self.quantity = self.quantity.checked_add(quantity)
+            .ok_or_else(|| OmsError::InvalidFieldValue(format!("line {} quantity is too large", self.line_id)))?;
+        self.total_price = self.unit_price * self.quantity as f64;
+        Ok(())
+    }
+}
+
+/// A cart of order lines built against a menu document
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cart {
+    menu: OmsDocument,
+    lines: Vec<OrderLine>,
+}
+
+impl Cart {
+    /// Create an empty cart for a menu
+    pub fn new(menu: OmsDocument) -> Self {
+        Self {
+            menu,
+            lines: Vec::new(),
+        }
+    }
+
+    /// Rebuild a cart from an order document rendered by [`Cart::to_document`]
+    ///
+    /// Lines keep their IDs, and items without a line ID get a new one. Prices are
+    /// recalculated against the menu.
+    pub fn from_document(menu: OmsDocument, document: &OmsDocument) -> OmsResult<Self> {
+        let mut cart = Self::new(menu);
+        for item in &document.items {
+            let quantity = item.quantity.unwrap_or(1);
+            if quantity == 0 {
+                return Err(OmsError::InvalidFieldValue(format!("item {} has a quantity of zero", item.id)));
+            }
+            let line_id = item.line_id.clone().unwrap_or_else(new_line_id);
+            if cart.find_line(&line_id).is_some() {
+                return Err(OmsError::InvalidFieldValue(format!("line {} appears more than once", line_id)));
+            }
+
+            let selections = item.selected_customizations.clone().unwrap_or_default();
+            let unit_price = cart.price_configuration(&item.id, &selections)?;
+            cart.lines.push(OrderLine {
+                line_id,
+                item_id: item.id.clone(),
+                quantity,
+                selections,
+                note: item.item_note.clone(),
+                unit_price,
+                total_price: unit_price * quantity as f64,
+            });
+        }
+        Ok(cart)
+    }
+
+    /// Get the menu this cart is built against
+    pub fn menu(&self) -> &OmsDocument {
+        &self.menu
+    }
+
+    /// Get all lines in the cart
+    pub fn lines(&self) -> &[OrderLine] {
+        &self.lines
+    }
+
+    /// Find a line by ID
+    pub fn find_line(&self, line_id: &str) -> Option<&OrderLine> {
+        self.lines.iter().find(|line| line.line_id == line_id)
+    }
+
+    /// Whether the cart has no lines
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Add an item to the cart and return the ID of the line it ended up on
+    ///
+    /// If a line with the same item, selections and note already exists, its
+    /// quantity is increased instead of adding a new line.
+    pub fn add_line(
+        &mut self,
+        item_id: &str,
+        quantity: u32,
+        selections: Vec<SelectedCustomization>,
+        note: Option<String>,
+    ) -> OmsResult<String> {
+        if quantity == 0 {
+            return Err(OmsError::InvalidFieldValue("line quantity must be greater than zero".to_string()));
+        }
+
+        let unit_price = self.price_configuration(item_id, &selections)?;
+
+        if let Some(line) = self.lines.iter_mut()
+            .find(|line| line.has_configuration(item_id, &selections, note.as_deref()))
+        {
+            line.add_quantity(quantity)?;
+            return Ok(line.line_id.clone());
+        }
+
+        let line_id = new_line_id();
+        self.lines.push(OrderLine {
+            line_id: line_id.clone(),
+            item_id: item_id.to_string(),
+            quantity,
+            selections,
+            note,
+            unit_price,
+            total_price: unit_price * quantity as f64,
+        });
+
+        Ok(line_id)
+    }
+
+    /// Change the quantity of a line, removing it when the quantity is zero
+    pub fn update_quantity(&mut self, line_id: &str, quantity: u32) -> OmsResult<()> {
+        let line = self.find_line_mut(line_id)?;
+        if quantity == 0 {
+            self.remove_line(line_id);
+            return Ok(());
+        }
+
+        line.quantity = quantity;
+        line.total_price = line.unit_price * quantity as f64;
+        Ok(())
+    }
+
+    /// Replace the selections of a line and recalculate its price
+    pub fn update_selections(&mut self, line_id: &str, selections: Vec<SelectedCustomization>) -> OmsResult<()> {
+        let item_id = self.find_line_mut(line_id)?.item_id.clone();
+        let unit_price = self.price_configuration(&item_id, &selections)?;
+
+        let mut lines = self.lines.clone();
+        let line = find_line_in(&mut lines, line_id)?;
+        line.selections = selections;
+        line.unit_price = unit_price;
+        line.total_price = unit_price * line.quantity as f64;
+
+        self.lines = merge_identical_lines(lines)?;
+        Ok(())
+    }
+
+    /// Replace the note of a line
+    pub fn update_note(&mut self, line_id: &str, note: Option<String>) -> OmsResult<()> {
+        let mut lines = self.lines.clone();
+        find_line_in(&mut lines, line_id)?.note = note;
+        self.lines = merge_identical_lines(lines)?;
+        Ok(())
+    }
+
+    /// Remove a line by ID
+    pub fn remove_line(&mut self, line_id: &str) -> bool {
+        let initial_len = self.lines.len();
+        self.lines.retain(|line| line.line_id != line_id);
+        self.lines.len() < initial_len
+    }
+
+    /// Remove all lines from the cart
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+
+    /// Calculate the subtotal of all lines
+    pub fn subtotal(&self) -> f64 {
+        self.lines.iter().map(|line| line.total_price).sum()
+    }
+
+    /// Render the cart as a draft order document with one item per line
+    ///
+    /// Each item carries the ID of its line, so lines of the same menu item stay
+    /// apart; look them up with [`OmsDocument::find_line_item`].
+    pub fn to_document(&self) -> OmsResult<OmsDocument> {
+        let mut document = self.menu.clone();
+        document.items = Vec::with_capacity(self.lines.len());
+
+        for line in &self.lines {
+            let mut item = self.menu_item(&line.item_id)?.clone();
+            item.quantity = Some(line.quantity);
+            item.line_id = Some(line.line_id.clone());
+            item.selected_customizations = if line.selections.is_empty() {
+                None
+            } else {
+                Some(line.selections.clone())
+            };
+            item.item_note = line.note.clone();
+            item.calculated = Some(CalculatedValues {
+                item_price: line.unit_price,
+                adjusted_nutrition: None,
+            });
+            document.add_item(item);
+        }
+
+        generate_order(&mut document, None)?;
+        document.validate()?;
+        Ok(document)
+    }
+
+    /// Find a line by ID and return a mutable reference
+    fn find_line_mut(&mut self, line_id: &str) -> OmsResult<&mut OrderLine> {
+        find_line_in(&mut self.lines, line_id)
+    }
+
+    /// Look up an item in the menu
+    fn menu_item(&self, item_id: &str) -> OmsResult<&Item> {
+        self.menu.find_item(item_id)
+            .ok_or_else(|| OmsError::InvalidFieldValue(format!("item {} not found in menu", item_id)))
+    }
+
+    /// Validate a configuration against the menu and calculate its unit price
+    fn price_configuration(&self, item_id: &str, selections: &[SelectedCustomization]) -> OmsResult<f64> {
+        let item = self.menu_item(item_id)?;
+        validate_item_in_stock(item, 1)?;
+
+        match &item.customizations {
+            Some(available) => validate_selected_customizations(selections, available)?,
+            None if !selections.is_empty() => return Err(OmsError::InvalidFieldValue(format!(
+                "item {} has no customizations",
+                item_id
+            ))),
+            None => (),
+        }
+
+        let base_price = item.base_price.unwrap_or(0.0);
+        Ok(base_price + calculate_price_adjustments(item, selections)?)
+    }
+
+}
+
+/// Find a line by ID in a list of lines
+fn find_line_in<'a>(lines: &'a mut [OrderLine], line_id: &str) -> OmsResult<&'a mut OrderLine> {
+    lines.iter_mut()
+        .find(|line| line.line_id == line_id)
+        .ok_or_else(|| OmsError::InvalidFieldValue(format!("line {} not found in cart", line_id)))
+}
+
+/// Fold lines with identical configurations into the first such line
+fn merge_identical_lines(lines: Vec<OrderLine>) -> OmsResult<Vec<OrderLine>> {
+    let mut merged: Vec<OrderLine> = Vec::with_capacity(lines.len());
+
+    for line in lines {
+        match merged.iter_mut().find(|m| m.has_configuration(&line.item_id, &line.selections, line.note.as_deref())) {
+            Some(existing) => existing.add_quantity(line.quantity)?,
+            None => merged.push(line),
+        }
+    }
+
+    Ok(merged)
+}
+
+fn new_line_id() -> String {
+    format!("line-{}", uuid::Uuid::new_v4())
+}
+
+/// Sort selections so that equivalent configurations compare equal
+fn normalize_selections(selections: &[SelectedCustomization]) -> Vec<SelectedCustomization> {
+    let mut normalized: Vec<SelectedCustomization> = selections.iter()
+        .map(|selection| {
+            let mut selection = selection.clone();
+            if let CustomizationSelection::StringArray(ids) = &mut selection.selection {
+                ids.sort();
+            }
+            selection
+        })
+        .collect();
+
+    normalized.sort_by(|a, b| a.customization_id.cmp(&b.customization_id));
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::create_template;
+
+    fn latte_selections(milk: &str, flavors: &[&str]) -> Vec<SelectedCustomization> {
+        vec![
+            SelectedCustomization {
+                customization_id: "size".to_string(),
+                selection: CustomizationSelection::String("medium".to_string()),
+                sub_selections: None,
+            },
+            SelectedCustomization {
+                customization_id: "milk".to_string(),
+                selection: CustomizationSelection::String(milk.to_string()),
+                sub_selections: None,
+            },
+            SelectedCustomization {
+                customization_id: "shots".to_string(),
+                selection: CustomizationSelection::Number(2.0),
+                sub_selections: None,
+            },
+            SelectedCustomization {
+                customization_id: "flavor".to_string(),
+                selection: CustomizationSelection::StringArray(
+                    flavors.iter().map(|f| f.to_string()).collect()
+                ),
+                sub_selections: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_same_item_with_different_selections() {
+        let mut cart = Cart::new(create_template("coffee-shop").unwrap());
+
+        let whole = cart.add_line("latte", 1, latte_selections("whole", &[]), None).unwrap();
+        let oat = cart.add_line("latte", 2, latte_selections("oat", &["vanilla"]), None).unwrap();
+
+        assert_ne!(whole, oat);
+        assert_eq!(cart.lines().len(), 2);
+        assert_eq!(cart.find_line(&whole).unwrap().unit_price, 4.50);
+        assert_eq!(cart.find_line(&oat).unwrap().unit_price, 4.50 + 0.75 + 0.50);
+        assert_eq!(cart.subtotal(), 4.50 + 2.0 * 5.75);
+    }
+
+    #[test]
+    fn test_identical_configurations_are_merged() {
+        let mut cart = Cart::new(create_template("coffee-shop").unwrap());
+
+        let first = cart.add_line("latte", 1, latte_selections("whole", &["vanilla", "caramel"]), None).unwrap();
+        let second = cart.add_line("latte", 2, latte_selections("whole", &["caramel", "vanilla"]), None).unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(cart.lines().len(), 1);
+        assert_eq!(cart.lines()[0].quantity, 3);
+
+        // A different note keeps the lines apart
+        cart.add_line("latte", 1, latte_selections("whole", &["vanilla", "caramel"]), Some("extra hot".to_string())).unwrap();
+        assert_eq!(cart.lines().len(), 2);
+
+        // Updating the selections of one line to match another merges them
+        let skim = cart.add_line("latte", 1, latte_selections("skim", &[]), None).unwrap();
+        cart.update_selections(&skim, latte_selections("whole", &["vanilla", "caramel"])).unwrap();
+        assert_eq!(cart.lines().len(), 2);
+        assert_eq!(cart.find_line(&first).unwrap().quantity, 4);
+    }
+
+    #[test]
+    fn test_update_and_remove_lines() {
+        let mut cart = Cart::new(create_template("coffee-shop").unwrap());
+        let line_id = cart.add_line("latte", 1, latte_selections("whole", &[]), None).unwrap();
+
+        cart.update_quantity(&line_id, 3).unwrap();
+        assert_eq!(cart.find_line(&line_id).unwrap().total_price, 3.0 * 4.50);
+
+        cart.update_note(&line_id, Some("no foam".to_string())).unwrap();
+        assert_eq!(cart.find_line(&line_id).unwrap().note, Some("no foam".to_string()));
+
+        assert!(cart.update_quantity("nonexistent", 1).is_err());
+        assert!(cart.update_quantity("nonexistent", 0).is_err());
+
+        cart.update_quantity(&line_id, 0).unwrap();
+        assert!(cart.is_empty());
+        assert!(!cart.remove_line(&line_id));
+    }
+
+    #[test]
+    fn test_invalid_lines_are_rejected() {
+        let mut cart = Cart::new(create_template("coffee-shop").unwrap());
+
+        assert!(cart.add_line("nonexistent", 1, vec![], None).is_err());
+        assert!(cart.add_line("latte", 0, latte_selections("whole", &[]), None).is_err());
+        assert!(cart.add_line("latte", 1, latte_selections("goat", &[]), None).is_err());
+        assert!(cart.is_empty());
+    }
+
+    #[test]
+    fn test_quantity_overflow_is_rejected() {
+        let mut cart = Cart::new(create_template("coffee-shop").unwrap());
+        let first = cart.add_line("latte", u32::MAX, latte_selections("whole", &[]), None).unwrap();
+        assert!(cart.add_line("latte", 1, latte_selections("whole", &[]), None).is_err());
+        assert_eq!(cart.find_line(&first).unwrap().quantity, u32::MAX);
+
+        // Merging lines that would overflow leaves the cart unchanged
+        let second = cart.add_line("latte", 1, latte_selections("whole", &[]), Some("to go".to_string())).unwrap();
+        let before = cart.clone();
+        assert!(cart.update_note(&second, None).is_err());
+        assert_eq!(cart, before);
+    }
+
+    #[test]
+    fn test_to_document() {
+        let mut cart = Cart::new(create_template("coffee-shop").unwrap());
+        cart.add_line("latte", 1, latte_selections("whole", &[]), None).unwrap();
+        cart.add_line("latte", 2, latte_selections("almond", &[]), Some("to go".to_string())).unwrap();
+
+        let document = cart.to_document().unwrap();
+        assert_eq!(document.items.len(), 2);
+        assert_eq!(document.items[0].id, "latte");
+        assert_eq!(document.items[1].id, "latte");
+        assert_eq!(document.items[1].quantity, Some(2));
+        assert_eq!(document.items[1].item_note, Some("to go".to_string()));
+        assert_eq!(document.calculate_total_price().unwrap(), Some(cart.subtotal()));
+
+        // The rendered document is a valid OMS document
+        let parsed = OmsDocument::from_json(&document.to_json().unwrap()).unwrap();
+        assert_eq!(parsed, document);
+    }
+
+    #[test]
+    fn test_lines_of_the_same_item_round_trip() {
+        let mut cart = Cart::new(create_template("coffee-shop").unwrap());
+        let whole = cart.add_line("latte", 1, latte_selections("whole", &[]), None).unwrap();
+        let oat = cart.add_line("latte", 2, latte_selections("oat", &["vanilla"]), Some("to go".to_string())).unwrap();
+
+        let document = cart.to_document().unwrap();
+        let order = document.order.as_ref().unwrap();
+        assert_eq!(order.status, Some(OrderStatus::Draft));
+        assert_eq!(order.payment.as_ref().unwrap().subtotal, Some(cart.subtotal()));
+
+        let parsed = OmsDocument::from_json(&document.to_json().unwrap()).unwrap();
+        let oat_item = parsed.find_line_item(&oat).unwrap();
+        assert_eq!(oat_item.quantity, Some(2));
+        assert_eq!(oat_item.item_note.as_deref(), Some("to go"));
+        assert_eq!(parsed.find_line_item(&whole).unwrap().quantity, Some(1));
+
+        let restored = Cart::from_document(create_template("coffee-shop").unwrap(), &parsed).unwrap();
+        assert_eq!(restored.lines(), cart.lines());
+    }
+}
@@ -0,0 +1,301 @@
+// src/client.rs
+//
+// Async HTTP client for submitting orders to vendor endpoints
+
+use crate::orders::{idempotency_key, OrderStatusResponse, IDEMPOTENCY_KEY_HEADER};
+use crate::types::*;
+use crate::{OmsError, OmsResult, OMS_MIME_TYPE};
+use reqwest::header::{HeaderMap, ACCEPT, CONTENT_TYPE, RETRY_AFTER};
+use reqwest::{Method, StatusCode};
+use std::time::Duration;
+use url::Url;
+
+/// How failed requests are retried
+///
+/// Connection errors, timeouts, `408`, `429` and `5xx` responses are retried with
+/// exponential backoff. A `Retry-After` header in seconds overrides the backoff,
+/// capped at `max_backoff`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt
+    pub max_retries: u32,
+
+    /// Delay before the first retry
+    pub initial_backoff: Duration,
+
+    /// Upper bound of any delay
+    pub max_backoff: Duration,
+
+    /// Factor the delay grows by after each retry
+    pub multiplier: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+            multiplier: 2.0,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Never retry
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Set the number of retries
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set the initial and maximum delays
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Delay before the given retry, counting from zero
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = self.multiplier.max(1.0).powi(retry.min(i32::MAX as u32) as i32);
+        self.initial_backoff.mul_f64(factor).min(self.max_backoff)
+    }
+}
+
+/// Client for a vendor's order endpoint
+#[derive(Debug, Clone)]
+pub struct OmsClient {
+    pub(crate) http: reqwest::Client,
+    pub(crate) endpoint: Url,
+    pub(crate) retry: RetryPolicy,
+}
+
+impl OmsClient {
+    /// Create a client for an order endpoint such as `https://vendor.example/orders`
+    pub fn new(endpoint: &str) -> OmsResult<Self> {
+        let endpoint = Url::parse(endpoint)
+            .map_err(|e| OmsError::UrlError(format!("Invalid endpoint {}: {}", endpoint, e)))?;
+        Ok(Self {
+            http: reqwest::Client::new(),
+            endpoint,
+            retry: RetryPolicy::default(),
+        })
+    }
+
+    /// Use a preconfigured HTTP client, e.g. with timeouts or a proxy
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Set how failed requests are retried
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// URL of the order endpoint
+    pub fn endpoint(&self) -> &str {
+        self.endpoint.as_str()
+    }
+
+    /// Validate and submit an order document, returning the vendor's order status
+    pub async fn submit_order(&self, document: &OmsDocument) -> OmsResult<OrderStatusResponse> {
+        document.validate()?;
+        let key = idempotency_key(document)?;
+        let body = document.to_compact_json()?;
+
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, OMS_MIME_TYPE.parse().expect("valid header value"));
+        headers.insert(
+            IDEMPOTENCY_KEY_HEADER,
+            key.parse().map_err(|_| OmsError::InvalidFieldValue(format!("order id {} is not a valid header value", key)))?,
+        );
+        self.send(Method::POST, self.endpoint.clone(), headers, Some(body)).await
+    }
+
+    /// Fetch the status of a submitted order from `{endpoint}/{order_id}`
+    pub async fn order_status(&self, order_id: &str) -> OmsResult<OrderStatusResponse> {
+        let mut url = self.endpoint.clone();
+        url.path_segments_mut()
+            .map_err(|_| OmsError::UrlError(format!("{} cannot have a path", self.endpoint)))?
+            .pop_if_empty()
+            .push(order_id);
+        self.send(Method::GET, url, HeaderMap::new(), None).await
+    }
+
+    /// Send a request, retrying transient failures
+    async fn send(&self, method: Method, url: Url, headers: HeaderMap, body: Option<String>) -> OmsResult<OrderStatusResponse> {
+        let mut retry = 0;
+        loop {
+            let mut request = self.http.request(method.clone(), url.clone())
+                .headers(headers.clone())
+                .header(ACCEPT, format!("{}, application/json", OMS_MIME_TYPE));
+            if let Some(body) = &body {
+                request = request.body(body.clone());
+            }
+
+            let (error, retry_after) = match request.send().await {
+                Ok(response) if response.status().is_success() => {
+                    return OrderStatusResponse::from_json(&response.text().await?);
+                },
+                Ok(response) => {
+                    let status = response.status();
+                    let retry_after = response.headers().get(RETRY_AFTER)
+                        .and_then(|value| value.to_str().ok())
+                        .and_then(|value| value.trim().parse().ok())
+                        .map(Duration::from_secs);
+                    let message = response.text().await.unwrap_or_default();
+                    let error = OmsError::HttpStatus(status.as_u16(), message);
+                    if !is_retryable_status(status) {
+                        return Err(error);
+                    }
+                    (error, retry_after)
+                },
+                Err(error) if error.is_connect() || error.is_timeout() => (error.into(), None),
+                Err(error) => return Err(error.into()),
+            };
+
+            if retry >= self.retry.max_retries {
+                return Err(error);
+            }
+            let delay = retry_after.map_or_else(|| self.retry.backoff(retry), |delay| delay.min(self.retry.max_backoff));
+            tokio::time::sleep(delay).await;
+            retry += 1;
+        }
+    }
+}
+
+pub(crate) fn is_retryable_status(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::REQUEST_TIMEOUT || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{create_template, generate_order};
+    use wiremock::matchers::{body_string, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn order() -> OmsDocument {
+        let mut doc = create_template("coffee-shop").unwrap();
+        generate_order(&mut doc, Some("customer-1")).unwrap();
+        doc.order.as_mut().unwrap().id = Some("order-7".to_string());
+        doc
+    }
+
+    fn fast_retries() -> RetryPolicy {
+        RetryPolicy::default().with_backoff(Duration::from_millis(1), Duration::from_millis(5))
+    }
+
+    #[test]
+    fn test_backoff_grows_until_capped() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(0), Duration::from_millis(200));
+        assert_eq!(policy.backoff(2), Duration::from_millis(800));
+        assert_eq!(policy.backoff(10), Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_submit_order_sends_oms_document() {
+        let server = MockServer::start().await;
+        let doc = order();
+        Mock::given(method("POST"))
+            .and(path("/orders"))
+            .and(header("content-type", OMS_MIME_TYPE))
+            .and(header(IDEMPOTENCY_KEY_HEADER, "coffee-shop-template/order-7"))
+            .and(body_string(doc.to_compact_json().unwrap()))
+            .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({
+                "order_id": "order-7",
+                "status": "confirmed",
+                "estimated_ready_time": "2024-05-01T12:30:00Z",
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = OmsClient::new(&format!("{}/orders", server.uri())).unwrap();
+        let response = client.submit_order(&doc).await.unwrap();
+        assert_eq!(response.order_id.as_deref(), Some("order-7"));
+        assert_eq!(response.status, OrderStatus::Confirmed);
+        assert!(response.estimated_ready_time.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_submit_order_retries_with_same_key() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header(IDEMPOTENCY_KEY_HEADER, "coffee-shop-template/order-7"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&server)
+            .await;
+        let mut accepted = order();
+        accepted.update_order_status(OrderStatus::Submitted).unwrap();
+        Mock::given(method("POST"))
+            .and(header(IDEMPOTENCY_KEY_HEADER, "coffee-shop-template/order-7"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(accepted.to_json().unwrap(), OMS_MIME_TYPE))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = OmsClient::new(&server.uri()).unwrap().with_retry_policy(fast_retries());
+        let response = client.submit_order(&order()).await.unwrap();
+        assert_eq!(response.status, OrderStatus::Submitted);
+        assert_eq!(response.order_id.as_deref(), Some("order-7"));
+    }
+
+    #[tokio::test]
+    async fn test_client_errors_are_not_retried() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(422).set_body_string("item sold out"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(3)
+            .mount(&server)
+            .await;
+
+        let client = OmsClient::new(&server.uri()).unwrap().with_retry_policy(fast_retries().with_max_retries(2));
+        match client.submit_order(&order()).await {
+            Err(OmsError::HttpStatus(422, message)) => assert_eq!(message, "item sold out"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(matches!(client.order_status("order-7").await, Err(OmsError::HttpStatus(500, _))));
+
+        let mut unnumbered = order();
+        unnumbered.order.as_mut().unwrap().id = None;
+        assert!(matches!(client.submit_order(&unnumbered).await, Err(OmsError::MissingRequiredField(_))));
+    }
+
+    #[tokio::test]
+    async fn test_order_status() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/orders/order-7"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "order-7",
+                "status": "ready",
+                "message": "Pick up at the counter",
+            })))
+            .mount(&server)
+            .await;
+
+        let client = OmsClient::new(&format!("{}/orders/", server.uri())).unwrap();
+        let response = client.order_status("order-7").await.unwrap();
+        assert_eq!(response.status, OrderStatus::Ready);
+        assert_eq!(response.message.as_deref(), Some("Pick up at the counter"));
+    }
+}
@@ -0,0 +1,602 @@
+// src/currency.rs
+//
+// ISO 4217 currency data and locale-aware price formatting
+
+use crate::localization::LanguageTag;
+use crate::types::*;
+use crate::{OmsError, OmsResult};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+/// Currency used when neither the item nor the document specifies one
+pub const DEFAULT_CURRENCY: &str = "USD";
+
+/// ISO 4217 currency information
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CurrencyInfo {
+    /// Three-letter currency code
+    pub code: &'static str,
+
+    /// Number of digits after the decimal separator
+    pub minor_digits: u8,
+
+    /// Symbol used in locales where this is the local currency
+    pub symbol: Option<&'static str>,
+
+    /// Symbol used in other locales (e.g. `CA$` outside Canada)
+    pub international_symbol: Option<&'static str>,
+}
+
+/// Where the currency symbol is placed relative to the amount
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolPosition {
+    Prefix,
+    Suffix,
+}
+
+/// Number and currency conventions of a locale
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocaleFormat {
+    /// Locale tag the conventions belong to
+    pub locale: &'static str,
+
+    /// Separator between the integer and fractional parts
+    pub decimal_separator: &'static str,
+
+    /// Separator between groups of thousands
+    pub group_separator: &'static str,
+
+    /// Placement of the currency symbol
+    pub symbol_position: SymbolPosition,
+
+    /// Space between the symbol and the amount (empty for none)
+    pub symbol_spacing: &'static str,
+
+    /// Local currency of the locale's region
+    pub currency: &'static str,
+}
+
+/// Build a currency table entry
+const fn currency(
+    code: &'static str,
+    minor_digits: u8,
+    symbol: Option<&'static str>,
+    international_symbol: Option<&'static str>,
+) -> CurrencyInfo {
+    CurrencyInfo { code, minor_digits, symbol, international_symbol }
+}
+
+/// Active ISO 4217 currencies
+const CURRENCIES: &[CurrencyInfo] = &[
+    currency("AED", 2, None, None),
+    currency("AFN", 2, None, None),
+    currency("ALL", 2, None, None),
+    currency("AMD", 2, None, None),
+    currency("ANG", 2, None, None),
+    currency("AOA", 2, None, None),
+    currency("ARS", 2, Some("$"), None),
+    currency("AUD", 2, Some("$"), Some("A$")),
+    currency("AWG", 2, None, None),
+    currency("AZN", 2, None, None),
+    currency("BAM", 2, None, None),
+    currency("BBD", 2, Some("$"), None),
+    currency("BDT", 2, Some("৳"), None),
+    currency("BGN", 2, None, None),
+    currency("BHD", 3, None, None),
+    currency("BIF", 0, None, None),
+    currency("BMD", 2, Some("$"), None),
+    currency("BND", 2, Some("$"), None),
+    currency("BOB", 2, None, None),
+    currency("BOV", 2, None, None),
+    currency("BRL", 2, Some("R$"), Some("R$")),
+    currency("BSD", 2, Some("$"), None),
+    currency("BTN", 2, None, None),
+    currency("BWP", 2, None, None),
+    currency("BYN", 2, None, None),
+    currency("BZD", 2, Some("$"), None),
+    currency("CAD", 2, Some("$"), Some("CA$")),
+    currency("CDF", 2, None, None),
+    currency("CHE", 2, None, None),
+    currency("CHF", 2, None, None),
+    currency("CHW", 2, None, None),
+    currency("CLF", 4, None, None),
+    currency("CLP", 0, Some("$"), None),
+    currency("CNY", 2, Some("¥"), Some("CN¥")),
+    currency("COP", 2, Some("$"), None),
+    currency("COU", 2, None, None),
+    currency("CRC", 2, Some("₡"), None),
+    currency("CUP", 2, None, None),
+    currency("CVE", 2, None, None),
+    currency("CZK", 2, Some("Kč"), None),
+    currency("DJF", 0, None, None),
+    currency("DKK", 2, Some("kr."), None),
+    currency("DOP", 2, None, None),
+    currency("DZD", 2, None, None),
+    currency("EGP", 2, None, None),
+    currency("ERN", 2, None, None),
+    currency("ETB", 2, None, None),
+    currency("EUR", 2, Some("€"), Some("€")),
+    currency("FJD", 2, Some("$"), None),
+    currency("FKP", 2, None, None),
+    currency("GBP", 2, Some("£"), Some("£")),
+    currency("GEL", 2, Some("₾"), None),
+    currency("GHS", 2, None, None),
+    currency("GIP", 2, None, None),
+    currency("GMD", 2, None, None),
+    currency("GNF", 0, None, None),
+    currency("GTQ", 2, None, None),
+    currency("GYD", 2, Some("$"), None),
+    currency("HKD", 2, Some("$"), Some("HK$")),
+    currency("HNL", 2, None, None),
+    currency("HTG", 2, None, None),
+    currency("HUF", 2, Some("Ft"), None),
+    currency("IDR", 2, Some("Rp"), None),
+    currency("ILS", 2, Some("₪"), Some("₪")),
+    currency("INR", 2, Some("₹"), Some("₹")),
+    currency("IQD", 3, None, None),
+    currency("IRR", 2, None, None),
+    currency("ISK", 0, None, None),
+    currency("JMD", 2, Some("$"), None),
+    currency("JOD", 3, None, None),
+    currency("JPY", 0, Some("¥"), Some("¥")),
+    currency("KES", 2, None, None),
+    currency("KGS", 2, None, None),
+    currency("KHR", 2, None, None),
+    currency("KMF", 0, None, None),
+    currency("KPW", 2, None, None),
+    currency("KRW", 0, Some("₩"), Some("₩")),
+    currency("KWD", 3, None, None),
+    currency("KYD", 2, Some("$"), None),
+    currency("KZT", 2, Some("₸"), None),
+    currency("LAK", 2, None, None),
+    currency("LBP", 2, None, None),
+    currency("LKR", 2, None, None),
+    currency("LRD", 2, Some("$"), None),
+    currency("LSL", 2, None, None),
+    currency("LYD", 3, None, None),
+    currency("MAD", 2, None, None),
+    currency("MDL", 2, None, None),
+    currency("MGA", 2, None, None),
+    currency("MKD", 2, None, None),
+    currency("MMK", 2, None, None),
+    currency("MNT", 2, Some("₮"), None),
+    currency("MOP", 2, None, None),
+    currency("MRU", 2, None, None),
+    currency("MUR", 2, None, None),
+    currency("MVR", 2, None, None),
+    currency("MWK", 2, None, None),
+    currency("MXN", 2, Some("$"), Some("MX$")),
+    currency("MXV", 2, None, None),
+    currency("MYR", 2, Some("RM"), None),
+    currency("MZN", 2, None, None),
+    currency("NAD", 2, Some("$"), None),
+    currency("NGN", 2, Some("₦"), None),
+    currency("NIO", 2, None, None),
+    currency("NOK", 2, Some("kr"), None),
+    currency("NPR", 2, None, None),
+    currency("NZD", 2, Some("$"), Some("NZ$")),
+    currency("OMR", 3, None, None),
+    currency("PAB", 2, None, None),
+    currency("PEN", 2, None, None),
+    currency("PGK", 2, None, None),
+    currency("PHP", 2, Some("₱"), Some("₱")),
+    currency("PKR", 2, None, None),
+    currency("PLN", 2, Some("zł"), None),
+    currency("PYG", 0, None, None),
+    currency("QAR", 2, None, None),
+    currency("RON", 2, None, None),
+    currency("RSD", 2, None, None),
+    currency("RUB", 2, Some("₽"), None),
+    currency("RWF", 0, None, None),
+    currency("SAR", 2, None, None),
+    currency("SBD", 2, Some("$"), None),
+    currency("SCR", 2, None, None),
+    currency("SDG", 2, None, None),
+    currency("SEK", 2, Some("kr"), None),
+    currency("SGD", 2, Some("$"), None),
+    currency("SHP", 2, None, None),
+    currency("SLE", 2, None, None),
+    currency("SOS", 2, None, None),
+    currency("SRD", 2, Some("$"), None),
+    currency("SSP", 2, None, None),
+    currency("STN", 2, None, None),
+    currency("SVC", 2, None, None),
+    currency("SYP", 2, None, None),
+    currency("SZL", 2, None, None),
+    currency("THB", 2, Some("฿"), Some("฿")),
+    currency("TJS", 2, None, None),
+    currency("TMT", 2, None, None),
+    currency("TND", 3, None, None),
+    currency("TOP", 2, None, None),
+    currency("TRY", 2, Some("₺"), None),
+    currency("TTD", 2, Some("$"), None),
+    currency("TWD", 2, Some("$"), Some("NT$")),
+    currency("TZS", 2, None, None),
+    currency("UAH", 2, Some("₴"), None),
+    currency("UGX", 0, None, None),
+    currency("USD", 2, Some("$"), Some("US$")),
+    currency("USN", 2, None, None),
+    currency("UYI", 0, None, None),
+    currency("UYU", 2, Some("$"), None),
+    currency("UYW", 4, None, None),
+    currency("UZS", 2, None, None),
+    currency("VED", 2, None, None),
+    currency("VES", 2, None, None),
+    currency("VND", 0, Some("₫"), Some("₫")),
+    currency("VUV", 0, None, None),
+    currency("WST", 2, None, None),
+    currency("XAF", 0, None, Some("FCFA")),
+    currency("XCD", 2, Some("$"), Some("EC$")),
+    currency("XCG", 2, None, None),
+    currency("XOF", 0, None, Some("F\u{202F}CFA")),
+    currency("XPF", 0, None, Some("CFPF")),
+    currency("YER", 2, None, None),
+    currency("ZAR", 2, Some("R"), None),
+    currency("ZMW", 2, None, None),
+    currency("ZWG", 2, None, None),
+];
+
+/// Number and currency conventions of supported locales; the first entry for
+/// a language is used for locales of that language that are not listed
+const LOCALE_FORMATS: &[LocaleFormat] = &[
+    locale("en-US", ".", ",", SymbolPosition::Prefix, "", "USD"),
+    locale("en-GB", ".", ",", SymbolPosition::Prefix, "", "GBP"),
+    locale("en-CA", ".", ",", SymbolPosition::Prefix, "", "CAD"),
+    locale("en-AU", ".", ",", SymbolPosition::Prefix, "", "AUD"),
+    locale("en-NZ", ".", ",", SymbolPosition::Prefix, "", "NZD"),
+    locale("en-IE", ".", ",", SymbolPosition::Prefix, "", "EUR"),
+    locale("fr-FR", ",", "\u{202F}", SymbolPosition::Suffix, "\u{A0}", "EUR"),
+    locale("fr-CA", ",", "\u{A0}", SymbolPosition::Suffix, "\u{A0}", "CAD"),
+    locale("de-DE", ",", ".", SymbolPosition::Suffix, "\u{A0}", "EUR"),
+    locale("de-AT", ",", "\u{A0}", SymbolPosition::Prefix, "\u{A0}", "EUR"),
+    locale("de-CH", ".", "’", SymbolPosition::Prefix, "\u{A0}", "CHF"),
+    locale("es-ES", ",", ".", SymbolPosition::Suffix, "\u{A0}", "EUR"),
+    locale("es-MX", ".", ",", SymbolPosition::Prefix, "", "MXN"),
+    locale("es-US", ".", ",", SymbolPosition::Prefix, "", "USD"),
+    locale("it-IT", ",", ".", SymbolPosition::Suffix, "\u{A0}", "EUR"),
+    locale("nl-NL", ",", ".", SymbolPosition::Prefix, "\u{A0}", "EUR"),
+    locale("pt-BR", ",", ".", SymbolPosition::Prefix, "\u{A0}", "BRL"),
+    locale("pt-PT", ",", "\u{A0}", SymbolPosition::Suffix, "\u{A0}", "EUR"),
+    locale("sv-SE", ",", "\u{A0}", SymbolPosition::Suffix, "\u{A0}", "SEK"),
+    locale("ja-JP", ".", ",", SymbolPosition::Prefix, "", "JPY"),
+    locale("zh-CN", ".", ",", SymbolPosition::Prefix, "", "CNY"),
+    locale("zh-TW", ".", ",", SymbolPosition::Prefix, "", "TWD"),
+    locale("ko-KR", ".", ",", SymbolPosition::Prefix, "", "KRW"),
+];
+
+/// Build a locale table entry
+const fn locale(
+    locale: &'static str,
+    decimal_separator: &'static str,
+    group_separator: &'static str,
+    symbol_position: SymbolPosition,
+    symbol_spacing: &'static str,
+    currency: &'static str,
+) -> LocaleFormat {
+    LocaleFormat { locale, decimal_separator, group_separator, symbol_position, symbol_spacing, currency }
+}
+
+/// Look up an ISO 4217 currency by its three-letter code
+pub fn currency_info(code: &str) -> Option<&'static CurrencyInfo> {
+    CURRENCIES.iter().find(|info| info.code == code)
+}
+
+/// Whether a code is an active ISO 4217 currency code
+pub fn is_iso_currency(code: &str) -> bool {
+    currency_info(code).is_some()
+}
+
+/// Look up an ISO 4217 currency, returning an error for unknown codes
+pub fn require_currency(code: &str) -> OmsResult<&'static CurrencyInfo> {
+    currency_info(code).ok_or_else(|| OmsError::InvalidCurrency(code.to_string()))
+}
+
+/// Get the formatting conventions for a locale, falling back to its language and then `en-US`
+pub fn locale_format(locale: &str) -> &'static LocaleFormat {
+    let tag = match LanguageTag::parse(locale) {
+        Ok(tag) => tag,
+        Err(_) => return &LOCALE_FORMATS[0],
+    };
+    let subtags: Vec<&str> = tag.as_str().split('-').collect();
+    let language = subtags[0];
+
+    // Match language and region, skipping any script subtag (`zh-Hant-TW` finds `zh-TW`)
+    let region = subtags[1..].iter()
+        .find(|subtag| subtag.len() == 2 && subtag.chars().all(|c| c.is_ascii_uppercase()));
+    if let Some(region) = region {
+        let wanted = format!("{}-{}", language, region);
+        if let Some(format) = LOCALE_FORMATS.iter().find(|format| format.locale == wanted) {
+            return format;
+        }
+    }
+
+    LOCALE_FORMATS.iter()
+        .find(|format| format.locale.split('-').next() == Some(language))
+        .unwrap_or(&LOCALE_FORMATS[0])
+}
+
+/// Round an amount to the minor units of a currency (e.g. whole yen)
+pub fn round_to_currency(amount: f64, currency: &str) -> OmsResult<f64> {
+    let factor = 10f64.powi(require_currency(currency)?.minor_digits as i32);
+    Ok((amount * factor).round() / factor)
+}
+
+/// Format an amount of money for display in a locale
+///
+/// The amount is rounded to the currency's minor digits and laid out with the
+/// locale's separators and symbol placement. The local symbol is used when the
+/// currency is the locale's own (`$` for USD in `en-US`), an unambiguous one
+/// otherwise (`US$` in `en-CA`), and the ISO code when there is no symbol.
+pub fn format_money(amount: f64, currency: &str, locale: &str) -> OmsResult<String> {
+    let info = require_currency(currency)?;
+    let format = locale_format(locale);
+
+    let symbol = if format.currency == info.code {
+        info.symbol.or(info.international_symbol)
+    } else {
+        info.international_symbol
+    };
+    let (symbol, spacing) = match symbol {
+        Some(symbol) => (symbol, format.symbol_spacing),
+        // ISO codes are always separated from the amount
+        None => (info.code, if format.symbol_spacing.is_empty() { "\u{A0}" } else { format.symbol_spacing }),
+    };
+
+    let rounded = round_to_currency(amount, currency)?;
+    let number = format_number(rounded.abs(), info.minor_digits, format);
+    let sign = if rounded < 0.0 { "-" } else { "" };
+
+    Ok(match format.symbol_position {
+        SymbolPosition::Prefix => format!("{}{}{}{}", sign, symbol, spacing, number),
+        SymbolPosition::Suffix => format!("{}{}{}{}", sign, number, spacing, symbol),
+    })
+}
+
+/// Exchange rates supplied by the caller for converting prices between currencies
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExchangeRates {
+    rates: HashMap<(String, String), f64>,
+    source: Option<String>,
+    as_of: Option<DateTime<Utc>>,
+}
+
+impl ExchangeRates {
+    /// Create an empty rate table
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set where the rates came from, recorded with every conversion
+    pub fn with_source(mut self, source: &str) -> Self {
+        self.source = Some(source.to_string());
+        self
+    }
+
+    /// Set when the rates were quoted, recorded with every conversion
+    pub fn with_as_of(mut self, as_of: DateTime<Utc>) -> Self {
+        self.as_of = Some(as_of);
+        self
+    }
+
+    /// Add a rate giving the units of `to` per unit of `from`
+    pub fn add_rate(&mut self, from: &str, to: &str, rate: f64) -> OmsResult<()> {
+        require_currency(from)?;
+        require_currency(to)?;
+        if !(rate.is_finite() && rate > 0.0) {
+            return Err(OmsError::InvalidFieldValue(format!(
+                "exchange rate from {} to {} must be positive",
+                from, to
+            )));
+        }
+
+        self.rates.insert((from.to_string(), to.to_string()), rate);
+        Ok(())
+    }
+
+    /// Get the rate from one currency to another, using the inverse rate if needed
+    pub fn rate(&self, from: &str, to: &str) -> Option<f64> {
+        if from == to {
+            return Some(1.0);
+        }
+
+        self.rates.get(&(from.to_string(), to.to_string())).copied()
+            .or_else(|| self.rates.get(&(to.to_string(), from.to_string())).map(|rate| 1.0 / rate))
+    }
+
+    /// Convert an amount from one currency to another
+    pub fn convert(&self, amount: f64, from: &str, to: &str) -> OmsResult<f64> {
+        Ok(amount * self.require_rate(from, to)?)
+    }
+
+    /// Build an audit record for applying the rate from one currency to another
+    pub fn record(&self, from: &str, to: &str) -> OmsResult<ExchangeRateRecord> {
+        Ok(ExchangeRateRecord {
+            from: from.to_string(),
+            to: to.to_string(),
+            rate: self.require_rate(from, to)?,
+            source: self.source.clone(),
+            as_of: self.as_of,
+            applied_at: Utc::now(),
+        })
+    }
+
+    /// Get a rate, returning an error if the table has none
+    fn require_rate(&self, from: &str, to: &str) -> OmsResult<f64> {
+        self.rate(from, to).ok_or_else(|| {
+            OmsError::CurrencyMismatch(format!("no exchange rate from {} to {}", from, to))
+        })
+    }
+}
+
+/// Total of a document converted into a single currency
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConvertedTotal {
+    /// Total in the target currency, rounded to its minor unit
+    pub total: f64,
+
+    /// Target currency
+    pub currency: String,
+
+    /// Rates applied to reach the target currency
+    pub exchange_rates: Vec<ExchangeRateRecord>,
+}
+
+impl OmsDocument {
+    /// Get the document's currency: the first currency on an item, or the payment currency
+    pub fn currency(&self) -> Option<&str> {
+        self.items.iter()
+            .find_map(|item| item.currency.as_deref())
+            .or_else(|| self.order.as_ref()?.payment.as_ref().map(|payment| payment.currency.as_str()))
+    }
+
+    /// Format an amount in a currency using the document's locale
+    pub fn format_price(&self, amount: f64, currency: &str) -> OmsResult<String> {
+        format_money(amount, currency, &self.metadata.locale)
+    }
+
+    /// Format an item's base price in the item's currency (or the document's)
+    pub fn format_item_price(&self, item: &Item) -> OmsResult<Option<String>> {
+        let currency = item.currency.as_deref()
+            .or_else(|| self.currency())
+            .unwrap_or(DEFAULT_CURRENCY);
+
+        item.base_price
+            .map(|price| self.format_price(price, currency))
+            .transpose()
+    }
+
+    /// Format the order's payment total in the payment currency
+    pub fn format_payment_total(&self) -> OmsResult<Option<String>> {
+        self.order.as_ref()
+            .and_then(|order| order.payment.as_ref())
+            .map(|payment| self.format_price(payment.total, &payment.currency))
+            .transpose()
+    }
+}
+
+/// Format an unsigned amount with grouping and the currency's minor digits
+fn format_number(amount: f64, minor_digits: u8, format: &LocaleFormat) -> String {
+    let formatted = format!("{:.*}", minor_digits as usize, amount);
+    let (integer, fraction) = match formatted.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (formatted.as_str(), None),
+    };
+
+    let mut grouped = String::new();
+    for (index, digit) in integer.chars().enumerate() {
+        if index > 0 && (integer.len() - index) % 3 == 0 {
+            grouped.push_str(format.group_separator);
+        }
+        grouped.push(digit);
+    }
+
+    match fraction {
+        Some(fraction) => format!("{}{}{}", grouped, format.decimal_separator, fraction),
+        None => grouped,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::create_template;
+
+    #[test]
+    fn test_minor_digits() {
+        assert_eq!(currency_info("JPY").unwrap().minor_digits, 0);
+        assert_eq!(currency_info("BHD").unwrap().minor_digits, 3);
+        assert_eq!(currency_info("USD").unwrap().minor_digits, 2);
+        assert!(!is_iso_currency("usd"));
+        assert!(!is_iso_currency("XYZ"));
+
+        assert_eq!(format_money(1234.5, "JPY", "ja-JP").unwrap(), "¥1,235");
+        assert_eq!(format_money(1.5, "BHD", "en-US").unwrap(), "BHD\u{A0}1.500");
+        assert_eq!(round_to_currency(10.125, "BHD").unwrap(), 10.125);
+        assert_eq!(round_to_currency(10.6, "JPY").unwrap(), 11.0);
+    }
+
+    #[test]
+    fn test_locale_conventions() {
+        assert_eq!(format_money(1234.5, "USD", "en-US").unwrap(), "$1,234.50");
+        assert_eq!(format_money(1234.5, "EUR", "de-DE").unwrap(), "1.234,50\u{A0}€");
+        assert_eq!(format_money(1234.5, "EUR", "fr-FR").unwrap(), "1\u{202F}234,50\u{A0}€");
+        assert_eq!(format_money(1234.5, "EUR", "nl-NL").unwrap(), "€\u{A0}1.234,50");
+        assert_eq!(format_money(1234.5, "CHF", "de-CH").unwrap(), "CHF\u{A0}1’234.50");
+        assert_eq!(format_money(-4.25, "GBP", "en-GB").unwrap(), "-£4.25");
+
+        // Dollar signs are disambiguated outside their home locale
+        assert_eq!(format_money(5.0, "USD", "en-CA").unwrap(), "US$5.00");
+        assert_eq!(format_money(5.0, "CAD", "en-CA").unwrap(), "$5.00");
+        assert_eq!(format_money(5.0, "CAD", "fr-CA").unwrap(), "5,00\u{A0}$");
+
+        // Unlisted locales fall back to their language, then to en-US
+        assert_eq!(format_money(5.0, "EUR", "fr-BE").unwrap(), "5,00\u{A0}€");
+        assert_eq!(format_money(5.0, "TWD", "zh-Hant-TW").unwrap(), "$5.00");
+        assert_eq!(format_money(5.0, "USD", "xx").unwrap(), "$5.00");
+
+        assert!(format_money(5.0, "ABC", "en-US").is_err());
+    }
+
+    #[test]
+    fn test_document_price_formatting() {
+        let mut doc = create_template("coffee-shop").unwrap();
+        let latte = doc.find_item("latte").unwrap().clone();
+        assert_eq!(doc.format_item_price(&latte).unwrap(), Some("$4.50".to_string()));
+
+        doc.metadata.locale = "ja-JP".to_string();
+        for item in &mut doc.items {
+            item.currency = Some("JPY".to_string());
+            item.base_price = Some(550.0);
+        }
+        let latte = doc.find_item("latte").unwrap().clone();
+        assert_eq!(doc.format_item_price(&latte).unwrap(), Some("¥550".to_string()));
+    }
+
+    #[test]
+    fn test_currency_validation() {
+        let mut doc = create_template("coffee-shop").unwrap();
+        assert!(doc.validate().is_ok());
+
+        doc.items[0].currency = Some("DOLLARS".to_string());
+        assert!(matches!(doc.validate(), Err(OmsError::InvalidCurrency(_))));
+
+        doc.items[0].currency = Some("CAD".to_string());
+        assert!(matches!(doc.validate(), Err(OmsError::CurrencyMismatch(_))));
+
+        doc.items[0].currency = Some("USD".to_string());
+        crate::utils::generate_order(&mut doc, None).unwrap();
+        assert!(doc.validate().is_ok());
+
+        doc.order.as_mut().unwrap().payment.as_mut().unwrap().currency = "EUR".to_string();
+        assert!(matches!(doc.validate(), Err(OmsError::CurrencyMismatch(_))));
+    }
+
+    #[test]
+    fn test_generate_order_with_exchange_rates() {
+        let mut doc = create_template("coffee-shop").unwrap();
+        doc.find_item_mut("cappuccino").unwrap().currency = Some("CAD".to_string());
+        assert!(doc.validate().is_err());
+        assert!(crate::utils::generate_order(&mut doc, None).is_err());
+
+        let mut rates = ExchangeRates::new().with_source("ecb").with_as_of(Utc::now());
+        rates.add_rate("CAD", "USD", 0.8).unwrap();
+        assert!(rates.add_rate("CAD", "XYZ", 0.8).is_err());
+        assert!(rates.add_rate("CAD", "EUR", 0.0).is_err());
+
+        crate::utils::generate_order_in_currency(&mut doc, None, "USD", &rates).unwrap();
+        let order = doc.order.as_ref().unwrap();
+        let payment = order.payment.as_ref().unwrap();
+        assert_eq!(payment.currency, "USD");
+        assert_eq!(payment.subtotal, Some(4.50 + 4.25 * 0.8));
+
+        let records = order.exchange_rates.as_ref().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!((records[0].from.as_str(), records[0].to.as_str(), records[0].rate), ("CAD", "USD", 0.8));
+        assert_eq!(records[0].source, Some("ecb".to_string()));
+
+        // The recorded conversion makes the mixed-currency document valid
+        assert!(doc.validate().is_ok());
+        let json = doc.to_json().unwrap();
+        assert_eq!(OmsDocument::from_json(&json).unwrap().order, doc.order);
+    }
+}
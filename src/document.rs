@@ -5,11 +5,12 @@
 use std::collections::HashMap;
 
 use crate::{OMS_VERSION, OmsError, OmsResult};
+use crate::currency::{require_currency, round_to_currency, ConvertedTotal, ExchangeRates, DEFAULT_CURRENCY};
+use crate::parsing::{parse_oms_document_with, ParseOptions};
 use crate::types::*;
 use crate::validation::validate_document;
 use chrono::Utc;
 use serde_json::{to_string_pretty, from_str};
-use validator::Validate;
 
 impl OmsDocument {
     /// Create a new OMS document with the minimum required fields
@@ -18,6 +19,7 @@ impl OmsDocument {
             oms_version: OMS_VERSION.to_string(),
             metadata,
             vendor,
+            ingredients: None,
             items,
             order: None,
             extensions: None,
@@ -30,6 +32,7 @@ impl OmsDocument {
             oms_version: OMS_VERSION.to_string(),
             metadata,
             vendor,
+            ingredients: None,
             items,
             order: Some(order),
             extensions: None,
@@ -64,32 +67,60 @@ impl OmsDocument {
     }
     
     /// Calculate total price for all items in the order
-    pub fn calculate_total_price(&self) -> Option<f64> {
+    ///
+    /// Returns an error if items are priced in different currencies; use
+    /// `calculate_total_price_in` to convert them with exchange rates.
+    pub fn calculate_total_price(&self) -> OmsResult<Option<f64>> {
+        let mut currencies: Vec<&str> = Vec::new();
+        for currency in self.items.iter().filter_map(|item| item.currency.as_deref()) {
+            if !currencies.contains(&currency) {
+                currencies.push(currency);
+            }
+        }
+        if currencies.len() > 1 {
+            return Err(OmsError::CurrencyMismatch(format!(
+                "items are priced in {}",
+                currencies.join(", ")
+            )));
+        }
+        
         // Sum up the prices of all items
-        let items_total = self.items.iter().fold(0.0, |acc, item| {
-            // Get the base price or fallback to 0.0
-            let base_price = item.base_price.unwrap_or(0.0);
-            
-            // Get the quantity or fallback to 1
-            let quantity = item.quantity.unwrap_or(1) as f64;
-            
-            // Get the calculated price if available
-            let item_price = match &item.calculated {
-                Some(calc) => calc.item_price,
-                None => base_price,
-            };
-            
-            acc + (item_price * quantity)
-        });
+        let items_total = self.items.iter().fold(0.0, |acc, item| acc + line_total(item));
         
         // Return the total if it's greater than zero
         if items_total > 0.0 {
-            Some(items_total)
+            Ok(Some(items_total))
         } else {
-            None
+            Ok(None)
         }
     }
     
+    /// Calculate the total price converted into a single currency
+    ///
+    /// Items without a currency are taken to be in the document's currency.
+    /// Every rate used is returned as an audit record.
+    pub fn calculate_total_price_in(&self, currency: &str, rates: &ExchangeRates) -> OmsResult<ConvertedTotal> {
+        require_currency(currency)?;
+        let default_currency = self.currency().unwrap_or(DEFAULT_CURRENCY);
+        
+        let mut total = 0.0;
+        let mut exchange_rates: Vec<ExchangeRateRecord> = Vec::new();
+        for item in &self.items {
+            let from = item.currency.as_deref().unwrap_or(default_currency);
+            total += rates.convert(line_total(item), from, currency)?;
+            
+            if from != currency && !exchange_rates.iter().any(|record| record.from == from) {
+                exchange_rates.push(rates.record(from, currency)?);
+            }
+        }
+        
+        Ok(ConvertedTotal {
+            total: round_to_currency(total, currency)?,
+            currency: currency.to_string(),
+            exchange_rates,
+        })
+    }
+    
     /// Create an OMS URL for this document
     pub fn create_url(&self) -> Option<String> {
         // We need vendor ID to create a URL
@@ -128,11 +159,21 @@ impl OmsDocument {
         self.items.iter().find(|item| item.id == item_id)
     }
     
+    /// Find an ordered item by the ID of its order line
+    pub fn find_line_item(&self, line_id: &str) -> Option<&Item> {
+        self.items.iter().find(|item| item.line_id.as_deref() == Some(line_id))
+    }
+    
     /// Find an item by ID and return a mutable reference
     pub fn find_item_mut(&mut self, item_id: &str) -> Option<&mut Item> {
         self.items.iter_mut().find(|item| item.id == item_id)
     }
     
+    /// Find an ingredient in the ingredient catalog by ID
+    pub fn find_ingredient(&self, ingredient_id: &str) -> Option<&Ingredient> {
+        self.ingredients.as_ref()?.iter().find(|ingredient| ingredient.id == ingredient_id)
+    }
+    
     /// Add order information to the document
     pub fn set_order(&mut self, order: Order) {
         self.order = Some(order);
@@ -200,7 +241,7 @@ impl OmsDocument {
 
 /// Parse an OMS document from a JSON string
 pub fn parse_oms_document(json: &str) -> OmsResult<OmsDocument> {
-    OmsDocument::from_json(json)
+    Ok(parse_oms_document_with(json, &ParseOptions::default())?.document)
 }
 
 /// Create a compact JSON representation suitable for NFC tags
@@ -208,10 +249,20 @@ pub fn create_compact_oms_json(document: &OmsDocument) -> OmsResult<String> {
     document.to_compact_json()
 }
 
+/// Price of an item line: the calculated (or base) price times the quantity
+pub(crate) fn line_total(item: &Item) -> f64 {
+    // Get the calculated price if available, falling back to the base price
+    let item_price = match &item.calculated {
+        Some(calc) => calc.item_price,
+        None => item.base_price.unwrap_or(0.0),
+    };
+    
+    item_price * item.quantity.unwrap_or(1) as f64
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::*;
     
     fn create_test_document() -> OmsDocument {
         let metadata = Metadata {
@@ -235,7 +286,7 @@ mod tests {
         
         let item = Item {
             id: "test-item".to_string(),
-            name: "Test Item".to_string(),
+            name: "Test Item".into(),
             category: "test".to_string(),
             vendor_id: None,
             description: None,
@@ -247,11 +298,13 @@ mod tests {
             customizations: None,
             selected_customizations: None,
             quantity: Some(1),
+            line_id: None,
             item_note: None,
             calculated: None,
             components: None,
             availability: None,
             popularity: None,
+            stock: None,
         };
         
         OmsDocument::new(metadata, vendor, vec![item])
@@ -272,13 +325,13 @@ mod tests {
         let mut doc = create_test_document();
         
         // Test with one item
-        let total = doc.calculate_total_price().unwrap();
+        let total = doc.calculate_total_price().unwrap().unwrap();
         assert_eq!(total, 10.0);
         
         // Add another item
         let item2 = Item {
             id: "test-item-2".to_string(),
-            name: "Test Item 2".to_string(),
+            name: "Test Item 2".into(),
             category: "test".to_string(),
             vendor_id: None,
             description: None,
@@ -290,18 +343,49 @@ mod tests {
             customizations: None,
             selected_customizations: None,
             quantity: Some(2),
+            line_id: None,
             item_note: None,
             calculated: None,
             components: None,
             availability: None,
             popularity: None,
+            stock: None,
         };
         
         doc.add_item(item2);
         
         // Test with two items
-        let total = doc.calculate_total_price().unwrap();
+        let total = doc.calculate_total_price().unwrap().unwrap();
         assert_eq!(total, 10.0 + (5.0 * 2.0));
+        
+        // Mixing currencies is an error
+        doc.items[1].currency = Some("CAD".to_string());
+        assert!(matches!(doc.calculate_total_price(), Err(OmsError::CurrencyMismatch(_))));
+    }
+    
+    #[test]
+    fn test_calculate_total_price_in() {
+        let mut doc = create_test_document();
+        let mut item2 = doc.items[0].clone();
+        item2.id = "test-item-2".to_string();
+        item2.base_price = Some(5.0);
+        item2.currency = Some("CAD".to_string());
+        item2.quantity = Some(2);
+        doc.add_item(item2);
+        
+        let mut rates = ExchangeRates::new().with_source("test-feed");
+        assert!(doc.calculate_total_price_in("USD", &rates).is_err());
+        
+        rates.add_rate("USD", "CAD", 1.25).unwrap();
+        let converted = doc.calculate_total_price_in("USD", &rates).unwrap();
+        assert_eq!(converted.total, 18.0); // 10 USD + 10 CAD at 0.8
+        assert_eq!(converted.exchange_rates.len(), 1);
+        assert_eq!(converted.exchange_rates[0].from, "CAD");
+        assert_eq!(converted.exchange_rates[0].rate, 0.8);
+        assert_eq!(converted.exchange_rates[0].source, Some("test-feed".to_string()));
+        
+        let converted = doc.calculate_total_price_in("CAD", &rates).unwrap();
+        assert_eq!(converted.total, 22.5);
     }
     
     #[test]
@@ -331,7 +415,7 @@ mod tests {
         // Add another item
         let item2 = Item {
             id: "test-item-2".to_string(),
-            name: "Test Item 2".to_string(),
+            name: "Test Item 2".into(),
             category: "test".to_string(),
             vendor_id: None,
             description: None,
@@ -343,11 +427,13 @@ mod tests {
             customizations: None,
             selected_customizations: None,
             quantity: None,
+            line_id: None,
             item_note: None,
             calculated: None,
             components: None,
             availability: None,
             popularity: None,
+            stock: None,
         };
         
         doc.add_item(item2);
@@ -380,6 +466,7 @@ mod tests {
             payment: None,
             customer: None,
             delivery: None,
+            exchange_rates: None,
         };
         
         doc.set_order(order);
@@ -408,6 +495,7 @@ mod tests {
             payment: None,
             customer: None,
             delivery: None,
+            exchange_rates: None,
         };
         
         doc.set_order(order);
@@ -0,0 +1,246 @@
+// src/events.rs
+//
+// Publish/subscribe of order status events, served over Server-Sent Events and WebSocket
+//
+// Subscribers resume after a reconnect by sending the ID of the last event they
+// received, as the `Last-Event-ID` header or the `last_event_id` query parameter.
+
+use crate::orders::OrderStatusEvent;
+use crate::types::OrderStatus;
+use axum::extract::ws::{Message, WebSocket};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use chrono::Utc;
+use futures_util::Stream;
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+/// Number of recent events kept for resuming subscribers by default
+pub const DEFAULT_EVENT_HISTORY: usize = 1024;
+
+/// Most live events buffered for a subscriber before it lags and catches up from the history
+const LIVE_EVENT_BUFFER: usize = 64;
+
+/// Publish/subscribe hub for order status events
+///
+/// Keeps the most recent events so that subscribers can catch up on events published
+/// before they subscribed or while they were disconnected.
+#[derive(Debug, Clone)]
+pub struct OrderEvents {
+    sender: broadcast::Sender<OrderStatusEvent>,
+    history: Arc<Mutex<EventHistory>>,
+}
+
+#[derive(Debug)]
+struct EventHistory {
+    next_id: u64,
+    capacity: usize,
+    events: VecDeque<OrderStatusEvent>,
+}
+
+impl Default for OrderEvents {
+    fn default() -> Self {
+        Self::with_history(DEFAULT_EVENT_HISTORY)
+    }
+}
+
+impl OrderEvents {
+    /// Create a hub keeping the default number of recent events
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a hub keeping up to `capacity` recent events
+    pub fn with_history(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.clamp(1, LIVE_EVENT_BUFFER));
+        Self {
+            sender,
+            history: Arc::new(Mutex::new(EventHistory {
+                next_id: 1,
+                capacity,
+                events: VecDeque::new(),
+            })),
+        }
+    }
+
+    /// Publish a status change of an order
+    pub fn publish(&self, order_id: &str, status: OrderStatus) -> OrderStatusEvent {
+        // Events are sent while holding the history lock so subscribers see each event
+        // either in their backlog or live, never both or neither
+        let mut history = self.history.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let event = OrderStatusEvent {
+            id: history.next_id,
+            order_id: order_id.to_string(),
+            status,
+            timestamp: Utc::now(),
+        };
+        history.next_id += 1;
+        if history.capacity > 0 {
+            if history.events.len() == history.capacity {
+                history.events.pop_front();
+            }
+            history.events.push_back(event.clone());
+        }
+        // Sending only fails when nobody is subscribed
+        let _ = self.sender.send(event.clone());
+        event
+    }
+
+    /// Subscribe to an order's events after `last_event_id`, or to all its kept events
+    pub fn subscribe(&self, order_id: &str, last_event_id: Option<u64>) -> OrderSubscription {
+        let history = self.history.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let last_id = last_event_id.unwrap_or(0);
+        let backlog = history.events.iter()
+            .filter(|event| event.order_id == order_id && event.id > last_id)
+            .cloned()
+            .collect();
+        OrderSubscription {
+            order_id: order_id.to_string(),
+            last_id,
+            backlog,
+            receiver: self.sender.subscribe(),
+            history: Arc::clone(&self.history),
+        }
+    }
+}
+
+/// Subscription to the status events of one order
+#[derive(Debug)]
+pub struct OrderSubscription {
+    order_id: String,
+    last_id: u64,
+    backlog: VecDeque<OrderStatusEvent>,
+    receiver: broadcast::Receiver<OrderStatusEvent>,
+    history: Arc<Mutex<EventHistory>>,
+}
+
+impl OrderSubscription {
+    /// ID of the order
+    pub fn order_id(&self) -> &str {
+        &self.order_id
+    }
+
+    /// Wait for the next event, returning `None` once the hub is dropped
+    pub async fn next(&mut self) -> Option<OrderStatusEvent> {
+        loop {
+            if let Some(event) = self.backlog.pop_front() {
+                self.last_id = event.id;
+                return Some(event);
+            }
+
+            match self.receiver.recv().await {
+                Ok(event) if event.order_id == self.order_id && event.id > self.last_id => {
+                    self.last_id = event.id;
+                    return Some(event);
+                },
+                Ok(_) => continue,
+                // Events dropped from the live buffer are still in the history
+                Err(broadcast::error::RecvError::Lagged(_)) => self.catch_up(),
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// Queue the kept events of the order after the last one returned
+    fn catch_up(&mut self) {
+        let history = self.history.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        self.backlog = history.events.iter()
+            .filter(|event| event.order_id == self.order_id && event.id > self.last_id)
+            .cloned()
+            .collect();
+    }
+}
+
+/// Stream a subscription as Server-Sent Events, ending after a final status
+pub(crate) fn sse_response(subscription: OrderSubscription) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let events = futures_util::stream::unfold((subscription, false), |(mut subscription, finished)| async move {
+        if finished {
+            return None;
+        }
+        let event = subscription.next().await?;
+        let finished = event.status.is_final();
+        let sse = Event::default()
+            .id(event.id.to_string())
+            .event("status")
+            .data(serde_json::to_string(&event).expect("order status events serialize"));
+        Some((Ok(sse), (subscription, finished)))
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// Forward a subscription over a WebSocket as JSON text messages, closing after a final status
+pub(crate) async fn forward_to_websocket(mut socket: WebSocket, mut subscription: OrderSubscription) {
+    loop {
+        tokio::select! {
+            event = subscription.next() => {
+                let Some(event) = event else { break };
+                let json = serde_json::to_string(&event).expect("order status events serialize");
+                if socket.send(Message::Text(json.into())).await.is_err() {
+                    return;
+                }
+                if event.status.is_final() {
+                    break;
+                }
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => {},
+            },
+        }
+    }
+    let _ = socket.send(Message::Close(None)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_subscribers_catch_up_and_resume() {
+        let events = OrderEvents::with_history(3);
+        events.publish("order-1", OrderStatus::Submitted);
+        events.publish("order-2", OrderStatus::Submitted);
+        let confirmed = events.publish("order-1", OrderStatus::Confirmed);
+
+        let mut from_start = events.subscribe("order-1", None);
+        let mut resumed = events.subscribe("order-1", Some(confirmed.id));
+        events.publish("order-1", OrderStatus::InProgress);
+
+        let statuses: Vec<OrderStatus> = [from_start.next().await, from_start.next().await, from_start.next().await]
+            .into_iter()
+            .map(|event| event.unwrap().status)
+            .collect();
+        assert_eq!(statuses, [OrderStatus::Submitted, OrderStatus::Confirmed, OrderStatus::InProgress]);
+        let next = resumed.next().await.unwrap();
+        assert_eq!((next.id, next.status), (4, OrderStatus::InProgress));
+
+        // Only the last three events are kept
+        let mut late = events.subscribe("order-1", None);
+        assert_eq!(late.next().await.unwrap().status, OrderStatus::Confirmed);
+    }
+
+    #[tokio::test]
+    async fn test_lagging_subscribers_catch_up_from_history() {
+        let events = OrderEvents::new();
+        let mut subscription = events.subscribe("order-1", None);
+        let statuses = [OrderStatus::Submitted, OrderStatus::Confirmed, OrderStatus::InProgress];
+        for status in &statuses {
+            events.publish("order-1", status.clone());
+            // Enough events of other orders to overflow the live buffer
+            for index in 0..LIVE_EVENT_BUFFER {
+                events.publish(&format!("order-{}", index + 2), OrderStatus::Submitted);
+            }
+        }
+        events.publish("order-1", OrderStatus::Ready);
+
+        let mut received = Vec::new();
+        for _ in 0..4 {
+            received.push(subscription.next().await.unwrap().status);
+        }
+        assert_eq!(received, [OrderStatus::Submitted, OrderStatus::Confirmed, OrderStatus::InProgress, OrderStatus::Ready]);
+
+        events.publish("order-1", OrderStatus::Completed);
+        assert_eq!(subscription.next().await.unwrap().status, OrderStatus::Completed);
+    }
+}
@@ -0,0 +1,245 @@
+// src/fetch.rs
+//
+// Fetching vendor menus over HTTP with conditional requests and an offline cache
+
+use crate::resolver::{CacheEntry, CachedMenu, MenuCache, MenuLocation, MenuResolver};
+use crate::types::OmsDocument;
+use crate::{OmsError, OmsResult, OMS_MIME_TYPE};
+use chrono::Utc;
+use reqwest::header::{HeaderName, ACCEPT, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
+
+/// Where a fetched menu came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuSource {
+    /// Downloaded from the vendor endpoint
+    Network,
+
+    /// Cached copy confirmed current by the endpoint (`304 Not Modified`)
+    Revalidated,
+
+    /// Cached copy used because the endpoint could not be reached
+    Offline,
+}
+
+/// A fetched menu and where it came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct FetchedMenu {
+    /// The menu document
+    pub document: OmsDocument,
+
+    /// Where the menu came from
+    pub source: MenuSource,
+}
+
+/// Fetches menus from the endpoints of a [`MenuResolver`], keeping a [`MenuCache`]
+///
+/// Cached menus are revalidated with `If-None-Match` and `If-Modified-Since`. If the
+/// endpoint cannot be reached or answers with a server error, the cached menu is
+/// returned instead. A cached menu only stands in for the endpoint it was fetched
+/// from, so it is ignored once the resolver points the location elsewhere.
+#[derive(Debug, Clone)]
+pub struct MenuFetcher<R> {
+    http: reqwest::Client,
+    resolver: R,
+    cache: MenuCache,
+}
+
+impl<R: MenuResolver> MenuFetcher<R> {
+    /// Create a fetcher resolving endpoints with `resolver` and caching menus in `cache`
+    pub fn new(resolver: R, cache: MenuCache) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            resolver,
+            cache,
+        }
+    }
+
+    /// Use a preconfigured HTTP client, e.g. with timeouts or a proxy
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Cache of fetched menus
+    pub fn cache(&self) -> &MenuCache {
+        &self.cache
+    }
+
+    /// Fetch the menu of an `omenu://view?v=...&l=...` URL
+    pub async fn fetch_url(&self, url: &str) -> OmsResult<FetchedMenu> {
+        self.fetch(&MenuLocation::from_oms_url(url)?).await
+    }
+
+    /// Fetch the menu of a vendor location
+    pub async fn fetch(&self, location: &MenuLocation) -> OmsResult<FetchedMenu> {
+        let endpoint = self.resolver.menu_endpoint(location)?;
+        // A damaged cache entry is refetched rather than reported, and validators
+        // and offline copies only apply to the endpoint that issued them
+        let cached = self.cache.load(location)
+            .unwrap_or(None)
+            .filter(|cached| cached.entry.url == endpoint.as_str());
+
+        let mut request = self.http.get(endpoint.clone())
+            .header(ACCEPT, format!("{}, application/json", OMS_MIME_TYPE));
+        if let Some(cached) = &cached {
+            if let Some(etag) = &cached.entry.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &cached.entry.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(error) => return offline(cached, error.into()),
+        };
+        let status = response.status();
+
+        if status == StatusCode::NOT_MODIFIED {
+            let Some(cached) = cached else {
+                return Err(OmsError::HttpStatus(status.as_u16(), "not modified, but nothing is cached".to_string()));
+            };
+            let entry = CacheEntry {
+                fetched: Utc::now(),
+                ..cached.entry
+            };
+            self.cache.update_entry(location, &entry)?;
+            return Ok(FetchedMenu { document: cached.document, source: MenuSource::Revalidated });
+        }
+
+        if !status.is_success() {
+            let error = OmsError::HttpStatus(status.as_u16(), response.text().await.unwrap_or_default());
+            return if status.is_server_error() { offline(cached, error) } else { Err(error) };
+        }
+
+        let header = |name: HeaderName| response.headers().get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let entry = CacheEntry {
+            url: endpoint.to_string(),
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+            fetched: Utc::now(),
+        };
+        let body = match response.text().await {
+            Ok(body) => body,
+            Err(error) => return offline(cached, error.into()),
+        };
+
+        let document = OmsDocument::from_json(&body)?;
+        self.cache.store(location, &body, &entry)?;
+        Ok(FetchedMenu { document, source: MenuSource::Network })
+    }
+}
+
+/// Fall back to the cached menu, or report the error if nothing is cached
+fn offline(cached: Option<CachedMenu>, error: OmsError) -> OmsResult<FetchedMenu> {
+    match cached {
+        Some(cached) => Ok(FetchedMenu { document: cached.document, source: MenuSource::Offline }),
+        None => Err(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolver::UrlTemplateResolver;
+    use crate::utils::create_template;
+    use wiremock::matchers::{header, header_exists, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn fetcher(server: &str, cache: &std::path::Path) -> MenuFetcher<UrlTemplateResolver> {
+        let resolver = UrlTemplateResolver::new(&format!("{}/vendors/{{vendor}}/locations/{{location}}/menu", server));
+        MenuFetcher::new(resolver, MenuCache::new(cache))
+    }
+
+    #[tokio::test]
+    async fn test_fetch_revalidates_with_etag() {
+        let server = MockServer::start().await;
+        let menu = create_template("cafe").unwrap();
+        Mock::given(method("GET"))
+            .and(path("/vendors/cafe/locations/downtown/menu"))
+            .and(header("if-none-match", "\"v1\""))
+            .and(header_exists("if-modified-since"))
+            .respond_with(ResponseTemplate::new(304))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/vendors/cafe/locations/downtown/menu"))
+            .respond_with(ResponseTemplate::new(200)
+                .insert_header("etag", "\"v1\"")
+                .insert_header("last-modified", "Wed, 01 May 2024 12:00:00 GMT")
+                .set_body_raw(menu.to_json().unwrap(), OMS_MIME_TYPE))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let fetcher = fetcher(&server.uri(), dir.path());
+        let first = fetcher.fetch_url("omenu://view?v=cafe&l=downtown").await.unwrap();
+        assert_eq!(first, FetchedMenu { document: menu.clone(), source: MenuSource::Network });
+        let location = MenuLocation::new("cafe", Some("downtown"));
+        assert!(fetcher.cache().menu_path(&location).exists());
+
+        let second = fetcher.fetch_url("omenu://view?v=cafe&l=downtown").await.unwrap();
+        assert_eq!(second, FetchedMenu { document: menu, source: MenuSource::Revalidated });
+    }
+
+    #[tokio::test]
+    async fn test_fetch_falls_back_to_cache_when_offline() {
+        let dir = tempfile::tempdir().unwrap();
+        let menu = create_template("coffee-shop").unwrap();
+        let location = MenuLocation::new("coffee", None);
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(menu.to_json().unwrap(), OMS_MIME_TYPE))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+
+        let online = fetcher(&server.uri(), dir.path());
+        assert_eq!(online.fetch(&location).await.unwrap().source, MenuSource::Network);
+        assert_eq!(online.fetch(&location).await.unwrap().source, MenuSource::Offline);
+
+        // Nothing listens on a port released by the OS
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let unreachable = fetcher(&format!("http://{}", closed), dir.path());
+        // The cached menu came from another endpoint, so it cannot stand in for this one
+        assert!(matches!(unreachable.fetch(&location).await, Err(OmsError::NetworkError(_))));
+
+        let entry = CacheEntry {
+            url: unreachable.resolver.menu_endpoint(&location).unwrap().to_string(),
+            etag: None,
+            last_modified: None,
+            fetched: Utc::now(),
+        };
+        unreachable.cache().store(&location, &menu.to_json().unwrap(), &entry).unwrap();
+        let offline = unreachable.fetch(&location).await.unwrap();
+        assert_eq!(offline, FetchedMenu { document: menu, source: MenuSource::Offline });
+
+        unreachable.cache().remove(&location).unwrap();
+        assert!(matches!(unreachable.fetch(&location).await, Err(OmsError::NetworkError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_reports_client_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let fetcher = fetcher(&server.uri(), dir.path());
+        let result = fetcher.fetch_url("omenu://view?v=missing").await;
+        assert!(matches!(result, Err(OmsError::HttpStatus(404, _))));
+    }
+}
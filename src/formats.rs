@@ -0,0 +1,180 @@
+// src/formats.rs
+//
+// Text formats for authoring OMS documents by hand (JSON, YAML, TOML)
+
+use crate::types::*;
+use crate::{OmsError, OmsResult};
+use std::path::Path;
+
+/// Text format of an OMS document file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentFormat {
+    /// JSON (`.omenu`, `.json`)
+    Json,
+    /// YAML (`.omenu.yaml`, `.yaml`, `.yml`), requires the `yaml` feature
+    Yaml,
+    /// TOML (`.omenu.toml`, `.toml`), requires the `toml` feature
+    Toml,
+}
+
+impl DocumentFormat {
+    /// Detect the format from a file name, defaulting to JSON
+    pub fn from_path(path: &Path) -> Self {
+        let name = path.file_name()
+            .map(|name| name.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+
+        if name.ends_with(".yaml") || name.ends_with(".yml") {
+            DocumentFormat::Yaml
+        } else if name.ends_with(".toml") {
+            DocumentFormat::Toml
+        } else {
+            DocumentFormat::Json
+        }
+    }
+
+    /// Conventional file extension for the format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            DocumentFormat::Json => "omenu",
+            DocumentFormat::Yaml => "omenu.yaml",
+            DocumentFormat::Toml => "omenu.toml",
+        }
+    }
+}
+
+impl OmsDocument {
+    /// Serialize the OMS document to the given format
+    pub fn to_format(&self, format: DocumentFormat) -> OmsResult<String> {
+        match format {
+            DocumentFormat::Json => self.to_json(),
+            #[cfg(feature = "yaml")]
+            DocumentFormat::Yaml => self.to_yaml(),
+            #[cfg(feature = "toml")]
+            DocumentFormat::Toml => self.to_toml(),
+            #[allow(unreachable_patterns)]
+            _ => Err(unsupported(format)),
+        }
+    }
+
+    /// Deserialize an OMS document from the given format
+    pub fn from_format(text: &str, format: DocumentFormat) -> OmsResult<Self> {
+        match format {
+            DocumentFormat::Json => Self::from_json(text),
+            #[cfg(feature = "yaml")]
+            DocumentFormat::Yaml => Self::from_yaml(text),
+            #[cfg(feature = "toml")]
+            DocumentFormat::Toml => Self::from_toml(text),
+            #[allow(unreachable_patterns)]
+            _ => Err(unsupported(format)),
+        }
+    }
+
+    /// Serialize the OMS document to YAML
+    #[cfg(feature = "yaml")]
+    pub fn to_yaml(&self) -> OmsResult<String> {
+        Ok(serde_yaml::to_string(self)?)
+    }
+
+    /// Deserialize an OMS document from YAML
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml(yaml: &str) -> OmsResult<Self> {
+        let document: Self = serde_yaml::from_str(yaml)?;
+        document.validate()?;
+        Ok(document)
+    }
+
+    /// Serialize the OMS document to TOML
+    #[cfg(feature = "toml")]
+    pub fn to_toml(&self) -> OmsResult<String> {
+        toml::to_string_pretty(self).map_err(|e| OmsError::TomlError(e.to_string()))
+    }
+
+    /// Deserialize an OMS document from TOML
+    #[cfg(feature = "toml")]
+    pub fn from_toml(toml: &str) -> OmsResult<Self> {
+        let document: Self = toml::from_str(toml).map_err(|e| OmsError::TomlError(e.to_string()))?;
+        document.validate()?;
+        Ok(document)
+    }
+}
+
+/// Error for a format whose feature is not enabled
+fn unsupported(format: DocumentFormat) -> OmsError {
+    let feature = match format {
+        DocumentFormat::Json => "json",
+        DocumentFormat::Yaml => "yaml",
+        DocumentFormat::Toml => "toml",
+    };
+    OmsError::UnsupportedFormat(format!("{:?} requires the `{}` feature", format, feature))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{create_template, generate_order, load_document_from_file, save_document_to_file};
+
+    #[test]
+    fn test_format_from_path() {
+        let cases = [
+            ("menu.omenu", DocumentFormat::Json),
+            ("menu.json", DocumentFormat::Json),
+            ("menu.omenu.yaml", DocumentFormat::Yaml),
+            ("Menu.YML", DocumentFormat::Yaml),
+            ("menu.omenu.toml", DocumentFormat::Toml),
+            ("menu", DocumentFormat::Json),
+        ];
+        for (name, format) in cases {
+            assert_eq!(DocumentFormat::from_path(Path::new(name)), format, "{}", name);
+        }
+        for format in [DocumentFormat::Json, DocumentFormat::Yaml, DocumentFormat::Toml] {
+            let name = format!("menu.{}", format.extension());
+            assert_eq!(DocumentFormat::from_path(Path::new(&name)), format);
+        }
+    }
+
+    #[test]
+    fn test_files_round_trip_in_enabled_formats() {
+        let mut doc = create_template("coffee-shop").unwrap();
+        generate_order(&mut doc, Some("customer-1")).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+
+        for format in [DocumentFormat::Json, DocumentFormat::Yaml, DocumentFormat::Toml] {
+            let enabled = match format {
+                DocumentFormat::Json => true,
+                DocumentFormat::Yaml => cfg!(feature = "yaml"),
+                DocumentFormat::Toml => cfg!(feature = "toml"),
+            };
+            let path = dir.path().join(format!("menu.{}", format.extension()));
+            match save_document_to_file(&doc, &path) {
+                Ok(()) => assert_eq!(load_document_from_file(&path).unwrap(), doc),
+                Err(OmsError::UnsupportedFormat(_)) => assert!(!enabled, "{:?} should be supported", format),
+                Err(error) => panic!("{:?}: {}", format, error),
+            }
+        }
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_yaml_is_validated() {
+        let doc = create_template("cafe").unwrap();
+        let yaml = doc.to_yaml().unwrap();
+        assert!(yaml.contains("oms_version:"));
+        assert_eq!(OmsDocument::from_yaml(&yaml).unwrap(), doc);
+
+        let invalid = yaml.replacen("currency: USD", "currency: ZZZ", 1);
+        assert!(matches!(OmsDocument::from_yaml(&invalid), Err(OmsError::InvalidCurrency(_))));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_toml_is_validated() {
+        let doc = create_template("cafe").unwrap();
+        let toml = doc.to_toml().unwrap();
+        assert!(toml.contains("[[items]]"));
+        assert_eq!(OmsDocument::from_toml(&toml).unwrap(), doc);
+
+        let invalid = toml.replacen("currency = \"USD\"", "currency = \"ZZZ\"", 1);
+        assert!(matches!(OmsDocument::from_toml(&invalid), Err(OmsError::InvalidCurrency(_))));
+    }
+}
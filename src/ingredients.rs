@@ -0,0 +1,464 @@
+// src/ingredients.rs
+//
+// Ingredient-level modelling: deriving allergens, dietary flags and nutrition from recipes
+
+use crate::types::*;
+use crate::units::Unit;
+use crate::{OmsError, OmsResult};
+use std::collections::HashMap;
+
+/// Allergens, dietary flags and nutrition derived from catalog ingredients
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DerivedIngredientInfo {
+    /// Union of the allergens of all ingredients
+    pub allergens: Vec<String>,
+
+    /// Dietary flags shared by every ingredient
+    pub dietary_flags: Vec<String>,
+
+    /// Dietary flags the catalog tracks that some ingredient lacks, so the recipe cannot carry them
+    pub excluded_dietary_flags: Vec<String>,
+
+    /// Nutrition summed over the quantities used
+    pub nutrition: BasicNutrition,
+
+    /// Whether every ingredient came from the catalog, so the values cover the whole recipe
+    pub complete: bool,
+}
+
+/// Derive allergens, dietary flags and nutrition from ingredient usages
+pub fn derive_ingredient_info(
+    usages: &[IngredientUsage],
+    catalog: &[Ingredient],
+) -> OmsResult<DerivedIngredientInfo> {
+    let mut info = DerivedIngredientInfo::default();
+    let mut shared_flags: Option<Vec<String>> = None;
+
+    for usage in usages {
+        let ingredient = catalog.iter()
+            .find(|ingredient| ingredient.id == usage.ingredient_id)
+            .ok_or_else(|| OmsError::InvalidFieldValue(format!(
+                "ingredient {} not found in ingredient catalog",
+                usage.ingredient_id
+            )))?;
+
+        for allergen in ingredient.allergens.iter().flatten() {
+            if !info.allergens.contains(allergen) {
+                info.allergens.push(allergen.clone());
+            }
+        }
+
+        // A flag only applies if every ingredient carries it
+        let flags = ingredient.dietary_flags.clone().unwrap_or_default();
+        shared_flags = Some(match shared_flags {
+            Some(shared) => shared.into_iter().filter(|flag| flags.contains(flag)).collect(),
+            None => flags,
+        });
+
+        if let Some(per_100g) = &ingredient.nutrition_per_100g {
+            let factor = usage.quantity.convert_to(Unit::Gram)?.value / 100.0;
+            let nutrition = &mut info.nutrition;
+            add_scaled(&mut nutrition.calories, per_100g.calories, factor);
+            add_scaled(&mut nutrition.protein, per_100g.protein, factor);
+            add_scaled(&mut nutrition.fat, per_100g.fat, factor);
+            add_scaled(&mut nutrition.carbohydrates, per_100g.carbohydrates, factor);
+            add_scaled(&mut nutrition.sodium, per_100g.sodium, factor);
+        }
+    }
+
+    if let Some(shared_flags) = shared_flags {
+        // A flag used anywhere in the catalog is tracked, so missing it rules it out
+        for flag in catalog.iter().flat_map(|ingredient| ingredient.dietary_flags.iter().flatten()) {
+            if !shared_flags.contains(flag) && !info.excluded_dietary_flags.contains(flag) {
+                info.excluded_dietary_flags.push(flag.clone());
+            }
+        }
+        info.dietary_flags = shared_flags;
+    }
+    info.complete = true;
+    Ok(info)
+}
+
+/// Derive ingredient information for an item from its ingredient groups
+///
+/// Returns `None` if the item does not reference the ingredient catalog. If the
+/// item also lists plain ingredient names, the result is not complete and no
+/// dietary flags are derived because those ingredients cannot be checked, though
+/// flags ruled out by the referenced ingredients stay excluded.
+pub fn derive_item_info(item: &Item, catalog: &[Ingredient]) -> OmsResult<Option<DerivedIngredientInfo>> {
+    let entries: Vec<&IngredientEntry> = item.nutrition.iter()
+        .flat_map(|nutrition| nutrition.ingredients.iter().flatten())
+        .flat_map(|group| group.ingredients.iter())
+        .collect();
+
+    let usages: Vec<IngredientUsage> = entries.iter()
+        .filter_map(|entry| match entry {
+            IngredientEntry::Usage(usage) => Some(usage.clone()),
+            IngredientEntry::Name(_) => None,
+        })
+        .collect();
+
+    if usages.is_empty() {
+        return Ok(None);
+    }
+
+    let mut info = derive_ingredient_info(&usages, catalog)?;
+    if usages.len() < entries.len() {
+        info.dietary_flags.clear();
+        info.complete = false;
+    }
+
+    Ok(Some(info))
+}
+
+/// Fill derived allergens, dietary flags and nutrition into the items and options of a document
+///
+/// Only items and options that reference the document's ingredient catalog are
+/// updated. Derived allergens and dietary flags are added to hand-written ones;
+/// hand-written flags are only removed when an ingredient rules them out (e.g.
+/// `vegan` with an ingredient the catalog does not tag `vegan`). Items that also
+/// list plain ingredient names keep their hand-written nutrition.
+pub fn apply_ingredient_derivations(document: &mut OmsDocument) -> OmsResult<()> {
+    let catalog = document.ingredients.clone().unwrap_or_default();
+
+    for item in &mut document.items {
+        apply_to_item(item, &catalog)?;
+    }
+
+    Ok(())
+}
+
+/// Fill derived values into an item, its options and its components
+fn apply_to_item(item: &mut Item, catalog: &[Ingredient]) -> OmsResult<()> {
+    if let Some(info) = derive_item_info(item, catalog)? {
+        if let Some(nutrition) = &mut item.nutrition {
+            apply_to_nutrition(nutrition, info);
+        }
+    }
+
+    if let Some(customizations) = &mut item.customizations {
+        apply_to_customizations(customizations, catalog)?;
+    }
+
+    for component in item.components.iter_mut().flatten() {
+        apply_to_item(component, catalog)?;
+    }
+
+    Ok(())
+}
+
+/// Fill derived values into options, including nested customizations
+fn apply_to_customizations(customizations: &mut [Customization], catalog: &[Ingredient]) -> OmsResult<()> {
+    for option in customizations.iter_mut().flat_map(|c| c.options.iter_mut().flatten()) {
+        if let Some(usages) = &option.ingredients {
+            let info = derive_ingredient_info(usages, catalog)?;
+            option.dietary_flags = merge_dietary_flags(option.dietary_flags.take(), &info);
+            option.allergens = merge_allergens(option.allergens.take(), info.allergens);
+
+            let adjustments = option.nutrition_adjustments.get_or_insert_with(HashMap::new);
+            let mut adjust = |key: &str, value: Option<f64>, unit: &str| {
+                if let Some(value) = value {
+                    adjustments.insert(key.to_string(), NutrientValue::Simple(MeasurementValue {
+                        value,
+                        unit: unit.to_string(),
+                    }));
+                }
+            };
+            adjust("calories", info.nutrition.calories, "kcal");
+            adjust("protein", info.nutrition.protein, "g");
+            adjust("fat", info.nutrition.fat, "g");
+            adjust("carbohydrates", info.nutrition.carbohydrates, "g");
+            adjust("sodium", info.nutrition.sodium, "mg");
+        }
+
+        if let Some(sub_customizations) = &mut option.sub_customizations {
+            apply_to_customizations(sub_customizations, catalog)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Merge derived allergens and dietary flags into nutrition and, for complete derivations, overwrite values
+///
+/// Partial derivations only cover some ingredients, so their sums would
+/// understate the recipe and are not applied.
+fn apply_to_nutrition(nutrition: &mut Nutrition, info: DerivedIngredientInfo) {
+    nutrition.dietary_flags = merge_dietary_flags(nutrition.dietary_flags.take(), &info);
+    nutrition.allergens = merge_allergens(nutrition.allergens.take(), info.allergens);
+    if !info.complete {
+        return;
+    }
+
+    let derived = info.nutrition;
+    if derived.calories.is_some() {
+        nutrition.calories = derived.calories;
+    }
+    if let Some(protein) = derived.protein {
+        nutrition.protein = Some(MeasurementValue { value: protein, unit: "g".to_string() });
+    }
+    if let Some(fat) = derived.fat {
+        set_nutrient(&mut nutrition.fat, fat);
+    }
+    if let Some(carbohydrates) = derived.carbohydrates {
+        set_nutrient(&mut nutrition.carbohydrates, carbohydrates);
+    }
+    if let Some(sodium) = derived.sodium {
+        nutrition.sodium = Some(MeasurementValue { value: sodium, unit: "mg".to_string() });
+    }
+}
+
+/// Set a nutrient's value in grams, keeping its detailed breakdown
+fn set_nutrient(nutrient: &mut Option<NutrientWithDetails>, grams: f64) {
+    let details = nutrient.take().and_then(|nutrient| nutrient.details);
+    *nutrient = Some(NutrientWithDetails { value: grams, unit: "g".to_string(), details });
+}
+
+/// Union of existing and derived allergens, keeping the existing order first
+fn merge_allergens(existing: Option<Vec<String>>, derived: Vec<String>) -> Option<Vec<String>> {
+    let mut allergens = existing.unwrap_or_default();
+    for allergen in derived {
+        if !allergens.contains(&allergen) {
+            allergens.push(allergen);
+        }
+    }
+    non_empty(allergens)
+}
+
+/// Existing dietary flags that no ingredient rules out, followed by the derived ones
+fn merge_dietary_flags(existing: Option<Vec<String>>, info: &DerivedIngredientInfo) -> Option<Vec<String>> {
+    let mut flags: Vec<String> = existing.unwrap_or_default()
+        .into_iter()
+        .filter(|flag| !info.excluded_dietary_flags.contains(flag))
+        .collect();
+    for flag in &info.dietary_flags {
+        if !flags.contains(flag) {
+            flags.push(flag.clone());
+        }
+    }
+    non_empty(flags)
+}
+
+/// Add a scaled per-100g value to a running total
+fn add_scaled(total: &mut Option<f64>, per_100g: Option<f64>, factor: f64) {
+    if let Some(value) = per_100g {
+        *total = Some(total.unwrap_or(0.0) + value * factor);
+    }
+}
+
+/// Convert an empty list to `None`
+fn non_empty(values: Vec<String>) -> Option<Vec<String>> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::create_template;
+
+    fn ingredient(id: &str, allergens: &[&str], flags: &[&str], calories: f64, sodium: f64) -> Ingredient {
+        Ingredient {
+            id: id.to_string(),
+            name: id.to_string(),
+            allergens: Some(allergens.iter().map(|a| a.to_string()).collect()),
+            dietary_flags: Some(flags.iter().map(|f| f.to_string()).collect()),
+            nutrition_per_100g: Some(BasicNutrition {
+                calories: Some(calories),
+                protein: None,
+                fat: None,
+                carbohydrates: None,
+                sodium: Some(sodium),
+            }),
+        }
+    }
+
+    fn usage(ingredient_id: &str, grams: f64) -> IngredientUsage {
+        IngredientUsage {
+            ingredient_id: ingredient_id.to_string(),
+            quantity: MeasurementValue { value: grams, unit: "g".to_string() },
+        }
+    }
+
+    fn catalog() -> Vec<Ingredient> {
+        vec![
+            ingredient("bread", &["wheat"], &["vegetarian", "vegan"], 250.0, 500.0),
+            ingredient("turkey", &[], &[], 150.0, 1000.0),
+            ingredient("cheddar", &["dairy"], &["vegetarian"], 400.0, 600.0),
+            ingredient("avocado", &[], &["vegetarian", "vegan"], 160.0, 0.0),
+        ]
+    }
+
+    #[test]
+    fn test_derive_ingredient_info() {
+        let info = derive_ingredient_info(&[usage("bread", 80.0), usage("cheddar", 20.0)], &catalog()).unwrap();
+
+        assert_eq!(info.allergens, vec!["wheat", "dairy"]);
+        assert_eq!(info.dietary_flags, vec!["vegetarian"]);
+        assert_eq!(info.nutrition.calories, Some(200.0 + 80.0));
+        assert_eq!(info.nutrition.sodium, Some(400.0 + 120.0));
+        assert_eq!(info.nutrition.protein, None);
+
+        // Unknown ingredients and non-mass units are rejected
+        assert!(derive_ingredient_info(&[usage("tofu", 50.0)], &catalog()).is_err());
+        let mut in_ml = usage("bread", 50.0);
+        in_ml.quantity.unit = "ml".to_string();
+        assert!(derive_ingredient_info(&[in_ml], &catalog()).is_err());
+    }
+
+    #[test]
+    fn test_apply_ingredient_derivations() {
+        let mut doc = create_template("cafe").unwrap();
+        doc.ingredients = Some(catalog());
+
+        let sandwich = &mut doc.items[0];
+        sandwich.nutrition = Some(Nutrition {
+            serving_size: None,
+            calories: None,
+            protein: None,
+            fat: None,
+            carbohydrates: None,
+            sodium: None,
+            cholesterol: None,
+            vitamins: None,
+            minerals: None,
+            allergens: None,
+            dietary_flags: None,
+            health_claims: None,
+            ingredients: Some(vec![IngredientGroup {
+                name: "Sandwich".to_string(),
+                ingredients: vec![
+                    IngredientEntry::Usage(usage("bread", 100.0)),
+                    IngredientEntry::Usage(usage("turkey", 100.0)),
+                ],
+            }]),
+            nutrition_standards: None,
+        });
+
+        let cheese = &mut sandwich.customizations.as_mut().unwrap()[1];
+        let cheddar = &mut cheese.options.as_mut().unwrap()[0];
+        cheddar.allergens = None;
+        cheddar.ingredients = Some(vec![usage("cheddar", 25.0)]);
+
+        apply_ingredient_derivations(&mut doc).unwrap();
+
+        let nutrition = doc.items[0].nutrition.as_ref().unwrap();
+        assert_eq!(nutrition.allergens, Some(vec!["wheat".to_string()]));
+        assert_eq!(nutrition.dietary_flags, None);
+        assert_eq!(nutrition.calories, Some(400.0));
+        assert_eq!(nutrition.sodium.as_ref().unwrap().value, 1500.0);
+
+        let cheddar = &doc.items[0].customizations.as_ref().unwrap()[1].options.as_ref().unwrap()[0];
+        assert_eq!(cheddar.allergens, Some(vec!["dairy".to_string()]));
+        assert_eq!(
+            cheddar.nutrition_adjustments.as_ref().unwrap().get("calories"),
+            Some(&NutrientValue::Simple(MeasurementValue { value: 100.0, unit: "kcal".to_string() }))
+        );
+
+        // References must point into the ingredient catalog
+        assert!(doc.validate().is_ok());
+        doc.ingredients.as_mut().unwrap().retain(|ingredient| ingredient.id != "cheddar");
+        assert!(doc.validate().is_err());
+    }
+
+    #[test]
+    fn test_mixed_ingredient_lists_keep_hand_entered_values() {
+        let mut doc = create_template("cafe").unwrap();
+        let mut catalog = catalog();
+        catalog[0].nutrition_per_100g.as_mut().unwrap().fat = Some(4.0);
+        doc.ingredients = Some(catalog);
+
+        let saturated = HashMap::from([(
+            "saturated".to_string(),
+            NutrientValue::Simple(MeasurementValue { value: 1.5, unit: "g".to_string() }),
+        )]);
+        doc.items[0].nutrition = Some(Nutrition {
+            serving_size: None,
+            calories: Some(520.0),
+            protein: None,
+            fat: Some(NutrientWithDetails { value: 12.0, unit: "g".to_string(), details: Some(saturated.clone()) }),
+            carbohydrates: None,
+            sodium: None,
+            cholesterol: None,
+            vitamins: None,
+            minerals: None,
+            allergens: Some(vec!["sesame".to_string()]),
+            dietary_flags: Some(vec!["halal".to_string()]),
+            health_claims: None,
+            ingredients: Some(vec![IngredientGroup {
+                name: "Bagel".to_string(),
+                ingredients: vec![
+                    IngredientEntry::Name("sesame seeds".to_string()),
+                    IngredientEntry::Usage(usage("bread", 100.0)),
+                ],
+            }]),
+            nutrition_standards: None,
+        });
+
+        apply_ingredient_derivations(&mut doc).unwrap();
+        let nutrition = doc.items[0].nutrition.as_ref().unwrap();
+        assert_eq!(nutrition.allergens, Some(vec!["sesame".to_string(), "wheat".to_string()]));
+        assert_eq!(nutrition.dietary_flags, Some(vec!["halal".to_string()]));
+        assert_eq!(nutrition.calories, Some(520.0));
+        assert_eq!(nutrition.fat.as_ref().unwrap().value, 12.0);
+
+        // Once every ingredient comes from the catalog, values are derived but the
+        // hand-entered allergen and the fat breakdown are kept
+        let nutrition = doc.items[0].nutrition.as_mut().unwrap();
+        nutrition.ingredients.as_mut().unwrap()[0].ingredients.remove(0);
+        apply_ingredient_derivations(&mut doc).unwrap();
+        let nutrition = doc.items[0].nutrition.as_ref().unwrap();
+        assert_eq!(nutrition.allergens, Some(vec!["sesame".to_string(), "wheat".to_string()]));
+        assert_eq!(nutrition.calories, Some(250.0));
+        assert_eq!(nutrition.fat, Some(NutrientWithDetails { value: 4.0, unit: "g".to_string(), details: Some(saturated) }));
+    }
+
+    #[test]
+    fn test_hand_entered_dietary_flags_are_kept_unless_ruled_out() {
+        let mut doc = create_template("cafe").unwrap();
+        doc.ingredients = Some(catalog());
+        let recipe = |ingredients: &[&str], flags: &[&str]| Nutrition {
+            serving_size: None,
+            calories: None,
+            protein: None,
+            fat: None,
+            carbohydrates: None,
+            sodium: None,
+            cholesterol: None,
+            vitamins: None,
+            minerals: None,
+            allergens: None,
+            dietary_flags: Some(flags.iter().map(|flag| flag.to_string()).collect()),
+            health_claims: None,
+            ingredients: Some(vec![IngredientGroup {
+                name: "Sandwich".to_string(),
+                ingredients: ingredients.iter().map(|id| IngredientEntry::Usage(usage(id, 100.0))).collect(),
+            }]),
+            nutrition_standards: None,
+        };
+        let flags = |doc: &OmsDocument| doc.items[0].nutrition.as_ref().unwrap().dietary_flags.clone();
+
+        // The catalog never tags halal, so it cannot rule it out; turkey rules out vegan
+        doc.items[0].nutrition = Some(recipe(&["bread", "turkey"], &["halal", "vegan"]));
+        apply_ingredient_derivations(&mut doc).unwrap();
+        assert_eq!(flags(&doc), Some(vec!["halal".to_string()]));
+
+        // Flags shared by every ingredient are added to the hand-entered ones
+        doc.items[0].nutrition = Some(recipe(&["bread", "avocado"], &["halal"]));
+        apply_ingredient_derivations(&mut doc).unwrap();
+        assert_eq!(flags(&doc), Some(vec!["halal".to_string(), "vegetarian".to_string(), "vegan".to_string()]));
+    }
+
+    #[test]
+    fn test_plain_ingredient_names_are_backward_compatible() {
+        let json = r#"{"name": "Bun", "ingredients": ["flour", {"ingredient_id": "bread", "quantity": {"value": 60.0, "unit": "g"}}]}"#;
+        let group: IngredientGroup = serde_json::from_str(json).unwrap();
+
+        assert_eq!(group.ingredients[0], IngredientEntry::Name("flour".to_string()));
+        assert_eq!(group.ingredients[1], IngredientEntry::Usage(usage("bread", 60.0)));
+        assert_eq!(serde_json::to_value(&group).unwrap(), serde_json::from_str::<serde_json::Value>(json).unwrap());
+    }
+}
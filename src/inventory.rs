@@ -0,0 +1,378 @@
+// src/inventory.rs
+//
+// Inventory tracking for items and customization options
+
+use crate::types::*;
+use crate::validation::selected_option_ids;
+use crate::{OmsError, OmsResult};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+/// Default number of remaining units at or below which stock is reported as low
+pub const DEFAULT_LOW_STOCK_THRESHOLD: u32 = 5;
+
+/// Tracks stock for items and customization options
+///
+/// Items are tracked by item ID. Options are tracked by customization ID and option
+/// ID, because option IDs such as `large` are reused across customizations; marking
+/// `avocado` in `toppings` as sold out affects every item offering that customization.
+/// Entries that are not tracked are treated as unlimited.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inventory {
+    items: HashMap<String, StockInfo>,
+    options: HashMap<OptionKey, StockInfo>,
+    low_stock_threshold: u32,
+}
+
+/// Customization ID and option ID of a tracked option
+type OptionKey = (String, String);
+
+fn option_key(customization_id: &str, option_id: &str) -> OptionKey {
+    (customization_id.to_string(), option_id.to_string())
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Inventory {
+    /// Create an empty inventory
+    pub fn new() -> Self {
+        Self {
+            items: HashMap::new(),
+            options: HashMap::new(),
+            low_stock_threshold: DEFAULT_LOW_STOCK_THRESHOLD,
+        }
+    }
+
+    /// Set the number of remaining units at or below which stock is reported as low
+    pub fn with_low_stock_threshold(mut self, threshold: u32) -> Self {
+        self.low_stock_threshold = threshold;
+        self
+    }
+
+    /// Set the remaining quantity of an item
+    pub fn set_item_quantity(&mut self, item_id: &str, quantity: u32) {
+        let stock = self.stock_for_quantity(quantity);
+        self.items.insert(item_id.to_string(), stock);
+    }
+
+    /// Set the remaining quantity of an option of a customization
+    pub fn set_option_quantity(&mut self, customization_id: &str, option_id: &str, quantity: u32) {
+        let stock = self.stock_for_quantity(quantity);
+        self.options.insert(option_key(customization_id, option_id), stock);
+    }
+
+    /// Mark an item as sold out, with an optional expected restock time
+    pub fn mark_item_sold_out(&mut self, item_id: &str, restock_time: Option<DateTime<Utc>>) {
+        self.items.insert(item_id.to_string(), sold_out(restock_time));
+    }
+
+    /// Mark an option of a customization as sold out ("86" it), with an optional expected restock time
+    pub fn mark_option_sold_out(&mut self, customization_id: &str, option_id: &str, restock_time: Option<DateTime<Utc>>) {
+        self.options.insert(option_key(customization_id, option_id), sold_out(restock_time));
+    }
+
+    /// Stop tracking an item, making it unlimited again
+    pub fn untrack_item(&mut self, item_id: &str) -> bool {
+        self.items.remove(item_id).is_some()
+    }
+
+    /// Stop tracking an option of a customization, making it unlimited again
+    pub fn untrack_option(&mut self, customization_id: &str, option_id: &str) -> bool {
+        self.options.remove(&option_key(customization_id, option_id)).is_some()
+    }
+
+    /// Get the stock of an item, if tracked
+    pub fn item_stock(&self, item_id: &str) -> Option<&StockInfo> {
+        self.items.get(item_id)
+    }
+
+    /// Get the stock of an option of a customization, if tracked
+    pub fn option_stock(&self, customization_id: &str, option_id: &str) -> Option<&StockInfo> {
+        self.options.get(&option_key(customization_id, option_id))
+    }
+
+    /// Publish the tracked stock onto the items and options of a document
+    pub fn apply_to_document(&self, document: &mut OmsDocument) {
+        for item in &mut document.items {
+            self.apply_to_item(item);
+        }
+    }
+
+    /// Confirm a submitted order in a document, decrementing stock for everything it uses
+    ///
+    /// The order must be in a status that can move to confirmed, i.e. submitted, so
+    /// an order is never confirmed (and its stock decremented) twice. Stock is only
+    /// decremented if every ordered item and selected option is available in the
+    /// required quantity. On success the order status is set to confirmed and the
+    /// updated stock is published onto the document.
+    pub fn confirm_order(&mut self, document: &mut OmsDocument) -> OmsResult<()> {
+        let order = document.order.as_ref()
+            .ok_or_else(|| OmsError::MissingRequiredField("order".to_string()))?;
+        let status = order.status.clone().unwrap_or(OrderStatus::Draft);
+        if !status.can_transition_to(&OrderStatus::Confirmed) {
+            return Err(OmsError::InvalidStatusTransition(format!("{:?} to {:?}", status, OrderStatus::Confirmed)));
+        }
+
+        // Add up how much of each item and option the order uses
+        let mut item_demand: HashMap<&str, u32> = HashMap::new();
+        let mut option_demand: HashMap<OptionKey, u32> = HashMap::new();
+        for item in &document.items {
+            let quantity = item.quantity.unwrap_or(1);
+            *item_demand.entry(item.id.as_str()).or_insert(0) += quantity;
+
+            if let Some(selected) = &item.selected_customizations {
+                collect_option_demand(selected, quantity, &mut option_demand);
+            }
+        }
+
+        // Check everything before changing anything
+        for (item_id, quantity) in &item_demand {
+            check_available(self.items.get(*item_id), *quantity, "item", item_id)?;
+        }
+        for (key, quantity) in &option_demand {
+            check_available(self.options.get(key), *quantity, "option", &format!("{}/{}", key.0, key.1))?;
+        }
+
+        for (item_id, quantity) in item_demand {
+            if let Some(remaining) = self.items.get(item_id).and_then(|stock| stock.quantity) {
+                self.set_item_quantity(item_id, remaining - quantity);
+            }
+        }
+        for (key, quantity) in option_demand {
+            if let Some(remaining) = self.options.get(&key).and_then(|stock| stock.quantity) {
+                let stock = self.stock_for_quantity(remaining - quantity);
+                self.options.insert(key, stock);
+            }
+        }
+
+        document.transition_order_status(OrderStatus::Confirmed)?;
+        self.apply_to_document(document);
+        Ok(())
+    }
+
+    /// Publish the tracked stock onto an item, its options and its components
+    fn apply_to_item(&self, item: &mut Item) {
+        if let Some(stock) = self.items.get(&item.id) {
+            item.stock = Some(stock.clone());
+        }
+
+        if let Some(customizations) = &mut item.customizations {
+            self.apply_to_customizations(customizations);
+        }
+
+        for component in item.components.iter_mut().flatten() {
+            self.apply_to_item(component);
+        }
+    }
+
+    /// Publish the tracked stock onto options, including nested customizations
+    fn apply_to_customizations(&self, customizations: &mut [Customization]) {
+        for customization in customizations {
+            for option in customization.options.iter_mut().flatten() {
+                if let Some(stock) = self.options.get(&option_key(&customization.id, &option.id)) {
+                    option.stock = Some(stock.clone());
+                }
+
+                if let Some(sub_customizations) = &mut option.sub_customizations {
+                    self.apply_to_customizations(sub_customizations);
+                }
+            }
+        }
+    }
+
+    /// Build stock information for a remaining quantity
+    fn stock_for_quantity(&self, quantity: u32) -> StockInfo {
+        let status = if quantity == 0 {
+            StockStatus::SoldOut
+        } else if quantity <= self.low_stock_threshold {
+            StockStatus::Low
+        } else {
+            StockStatus::InStock
+        };
+
+        StockInfo {
+            status,
+            quantity: Some(quantity),
+            restock_time: None,
+        }
+    }
+}
+
+/// Build stock information for a sold out entry
+fn sold_out(restock_time: Option<DateTime<Utc>>) -> StockInfo {
+    StockInfo {
+        status: StockStatus::SoldOut,
+        quantity: Some(0),
+        restock_time,
+    }
+}
+
+/// Add the options chosen by selections (including nested ones) to the demand
+fn collect_option_demand(
+    selected: &[SelectedCustomization],
+    quantity: u32,
+    demand: &mut HashMap<OptionKey, u32>,
+) {
+    for selection in selected {
+        if matches!(selection.selection, CustomizationSelection::Number(_) | CustomizationSelection::Boolean(_)) {
+            continue;
+        }
+
+        for option_id in selected_option_ids(&selection.selection) {
+            *demand.entry((selection.customization_id.clone(), option_id)).or_insert(0) += quantity;
+        }
+
+        for sub_selected in selection.sub_selections.iter().flat_map(|subs| subs.values()) {
+            collect_option_demand(sub_selected, quantity, demand);
+        }
+    }
+}
+
+/// Check that a tracked entry can cover the requested quantity
+fn check_available(stock: Option<&StockInfo>, quantity: u32, kind: &str, id: &str) -> OmsResult<()> {
+    let stock = match stock {
+        Some(stock) => stock,
+        None => return Ok(()), // Untracked entries are unlimited
+    };
+
+    if stock.status == StockStatus::SoldOut {
+        return Err(OmsError::InvalidFieldValue(format!("{} '{}' is sold out", kind, id)));
+    }
+
+    if let Some(remaining) = stock.quantity {
+        if quantity > remaining {
+            return Err(OmsError::InvalidFieldValue(format!(
+                "ordered quantity {} exceeds remaining stock {} for {} '{}'",
+                quantity, remaining, kind, id
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{create_template, generate_order};
+
+    fn pizza_order(toppings: &[&str], quantity: u32) -> OmsDocument {
+        let mut doc = create_template("pizzeria").unwrap();
+        let pizza = &mut doc.items[0];
+        pizza.quantity = Some(quantity);
+        pizza.selected_customizations = Some(vec![
+            SelectedCustomization {
+                customization_id: "size".to_string(),
+                selection: CustomizationSelection::String("large".to_string()),
+                sub_selections: None,
+            },
+            SelectedCustomization {
+                customization_id: "crust".to_string(),
+                selection: CustomizationSelection::String("thin".to_string()),
+                sub_selections: None,
+            },
+            SelectedCustomization {
+                customization_id: "toppings".to_string(),
+                selection: CustomizationSelection::StringArray(
+                    toppings.iter().map(|t| t.to_string()).collect()
+                ),
+                sub_selections: None,
+            },
+        ]);
+        generate_order(&mut doc, None).unwrap();
+        doc.transition_order_status(OrderStatus::Submitted).unwrap();
+        doc
+    }
+
+    #[test]
+    fn test_sold_out_option_is_rejected() {
+        let mut inventory = Inventory::new();
+        inventory.mark_option_sold_out("toppings", "mushrooms", Some(Utc::now() + chrono::Duration::hours(4)));
+
+        let mut doc = pizza_order(&["mushrooms", "onions"], 1);
+        assert!(doc.validate().is_ok());
+
+        // Once the 86 is published, validation rejects the order
+        inventory.apply_to_document(&mut doc);
+        let toppings = &doc.items[0].customizations.as_ref().unwrap()[2];
+        let mushrooms = &toppings.options.as_ref().unwrap()[2];
+        assert_eq!(mushrooms.stock.as_ref().unwrap().status, StockStatus::SoldOut);
+        assert!(doc.validate().is_err());
+
+        // Confirmation is refused as well
+        assert!(inventory.confirm_order(&mut doc).is_err());
+        assert_eq!(doc.order.as_ref().unwrap().status, Some(OrderStatus::Submitted));
+    }
+
+    #[test]
+    fn test_confirm_order_decrements_stock() {
+        let mut inventory = Inventory::new().with_low_stock_threshold(3);
+        inventory.set_item_quantity("cheese-pizza", 10);
+        inventory.set_option_quantity("toppings", "onions", 4);
+
+        let mut doc = pizza_order(&["onions"], 2);
+        inventory.confirm_order(&mut doc).unwrap();
+
+        assert_eq!(doc.order.as_ref().unwrap().status, Some(OrderStatus::Confirmed));
+        assert_eq!(inventory.item_stock("cheese-pizza").unwrap().quantity, Some(8));
+        assert_eq!(inventory.item_stock("cheese-pizza").unwrap().status, StockStatus::InStock);
+
+        let onions = inventory.option_stock("toppings", "onions").unwrap();
+        assert_eq!(onions.quantity, Some(2));
+        assert_eq!(onions.status, StockStatus::Low);
+
+        // Untracked options are unlimited
+        assert!(inventory.option_stock("crust", "thin").is_none());
+
+        // The updated stock is published onto the document
+        assert_eq!(doc.items[0].stock.as_ref().unwrap().quantity, Some(8));
+
+        // A confirmed order cannot be confirmed again
+        assert!(matches!(inventory.confirm_order(&mut doc), Err(OmsError::InvalidStatusTransition(_))));
+        assert_eq!(inventory.item_stock("cheese-pizza").unwrap().quantity, Some(8));
+
+        // Draft orders have to be submitted first
+        let mut draft = pizza_order(&[], 1);
+        draft.order.as_mut().unwrap().status = Some(OrderStatus::Draft);
+        assert!(inventory.confirm_order(&mut draft).is_err());
+    }
+
+    #[test]
+    fn test_options_are_tracked_per_customization() {
+        let mut inventory = Inventory::new();
+        // Another customization reusing the option ID does not affect the size
+        inventory.mark_option_sold_out("crust", "large", None);
+
+        let mut doc = pizza_order(&[], 1);
+        inventory.apply_to_document(&mut doc);
+        let size = &doc.items[0].customizations.as_ref().unwrap()[0];
+        assert!(size.options.as_ref().unwrap().iter().all(|option| option.stock.is_none()));
+        inventory.confirm_order(&mut doc).unwrap();
+
+        inventory.mark_option_sold_out("size", "large", None);
+        assert!(inventory.confirm_order(&mut pizza_order(&[], 1)).is_err());
+    }
+
+    #[test]
+    fn test_confirm_order_is_all_or_nothing() {
+        let mut inventory = Inventory::new();
+        inventory.set_item_quantity("cheese-pizza", 10);
+        inventory.set_option_quantity("toppings", "olives", 1);
+
+        let mut doc = pizza_order(&["olives"], 2);
+        assert!(inventory.confirm_order(&mut doc).is_err());
+
+        // Nothing was decremented
+        assert_eq!(inventory.item_stock("cheese-pizza").unwrap().quantity, Some(10));
+        assert_eq!(inventory.option_stock("toppings", "olives").unwrap().quantity, Some(1));
+
+        // Selling the last one marks the option sold out
+        let mut doc = pizza_order(&["olives"], 1);
+        inventory.confirm_order(&mut doc).unwrap();
+        assert_eq!(inventory.option_stock("toppings", "olives").unwrap().status, StockStatus::SoldOut);
+    }
+}
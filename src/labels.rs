@@ -0,0 +1,798 @@
+// src/labels.rs
+//
+// Regulatory nutrition labels: US FDA Nutrition Facts and EU nutrition declarations
+//
+// Nutrient breakdowns are read from `NutrientWithDetails::details` using the keys
+// `saturated`, `trans`, `monounsaturated` and `polyunsaturated` for fat and
+// `fiber`, `sugars` and `added_sugars` for carbohydrates.
+
+use crate::types::*;
+use crate::units::{Unit, KJ_PER_KCAL};
+use crate::{OmsError, OmsResult};
+use serde::Serialize;
+use std::fmt;
+
+/// Daily value reference year used when a nutrition entry does not specify one
+pub const DEFAULT_DAILY_VALUE_YEAR: u16 = 2016;
+
+/// Reference intake description used when a nutrition entry does not specify one
+pub const DEFAULT_REFERENCE_INTAKE_DESCRIPTION: &str = "Reference intake of an average adult (8400 kJ/2000 kcal)";
+
+/// Width of plain text labels in characters
+const TEXT_WIDTH: usize = 40;
+
+/// Daily values from the 2016 FDA final rule
+const DAILY_VALUES_2016: &[(&str, f64, Unit)] = &[
+    ("total_fat", 78.0, Unit::Gram),
+    ("saturated_fat", 20.0, Unit::Gram),
+    ("cholesterol", 300.0, Unit::Milligram),
+    ("sodium", 2300.0, Unit::Milligram),
+    ("total_carbohydrate", 275.0, Unit::Gram),
+    ("dietary_fiber", 28.0, Unit::Gram),
+    ("added_sugars", 50.0, Unit::Gram),
+    ("vitamin a", 900.0, Unit::Microgram),
+    ("vitamin c", 90.0, Unit::Milligram),
+    ("vitamin d", 20.0, Unit::Microgram),
+    ("calcium", 1300.0, Unit::Milligram),
+    ("iron", 18.0, Unit::Milligram),
+    ("potassium", 4700.0, Unit::Milligram),
+];
+
+/// Daily values in effect before the 2016 FDA final rule
+const DAILY_VALUES_1993: &[(&str, f64, Unit)] = &[
+    ("total_fat", 65.0, Unit::Gram),
+    ("saturated_fat", 20.0, Unit::Gram),
+    ("cholesterol", 300.0, Unit::Milligram),
+    ("sodium", 2400.0, Unit::Milligram),
+    ("total_carbohydrate", 300.0, Unit::Gram),
+    ("dietary_fiber", 25.0, Unit::Gram),
+    ("vitamin a", 5000.0, Unit::InternationalUnit),
+    ("vitamin c", 60.0, Unit::Milligram),
+    ("vitamin d", 400.0, Unit::InternationalUnit),
+    ("calcium", 1000.0, Unit::Milligram),
+    ("iron", 18.0, Unit::Milligram),
+    ("potassium", 3500.0, Unit::Milligram),
+];
+
+/// EU reference intakes (Regulation (EU) No 1169/2011, Annex XIII)
+const EU_REFERENCE_INTAKES: &[(&str, f64, Unit)] = &[
+    ("energy", 2000.0, Unit::Kilocalorie),
+    ("fat", 70.0, Unit::Gram),
+    ("saturates", 20.0, Unit::Gram),
+    ("carbohydrate", 260.0, Unit::Gram),
+    ("sugars", 90.0, Unit::Gram),
+    ("protein", 50.0, Unit::Gram),
+    ("salt", 6.0, Unit::Gram),
+    ("vitamin a", 800.0, Unit::Microgram),
+    ("vitamin c", 80.0, Unit::Milligram),
+    ("vitamin d", 5.0, Unit::Microgram),
+    ("calcium", 800.0, Unit::Milligram),
+    ("iron", 14.0, Unit::Milligram),
+    ("potassium", 2000.0, Unit::Milligram),
+];
+
+/// Amount declared on a label, possibly as "less than" a threshold
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct LabelAmount {
+    /// Declared value
+    pub value: f64,
+
+    /// Unit of the declared value
+    pub unit: String,
+
+    /// Whether the amount is declared as less than the value
+    pub less_than: bool,
+}
+
+impl fmt::Display for LabelAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let prefix = if self.less_than { "<" } else { "" };
+        write!(f, "{}{}{}", prefix, format_number(self.value), self.unit)
+    }
+}
+
+/// A nutrient line on a Nutrition Facts label
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct NutritionFactsLine {
+    /// Nutrient name as printed on the label
+    pub name: String,
+
+    /// Rounded amount per serving
+    pub amount: LabelAmount,
+
+    /// Rounded percent daily value, if the nutrient has one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daily_value_percent: Option<u32>,
+
+    /// Indentation level (0 for top-level nutrients)
+    pub level: u8,
+}
+
+/// US FDA Nutrition Facts label
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct NutritionFactsLabel {
+    /// Serving size as printed on the label
+    pub serving_size: String,
+
+    /// Rounded calories per serving
+    pub calories: u32,
+
+    /// Year of the daily value reference set
+    pub daily_value_year: u16,
+
+    /// Macronutrient lines
+    pub nutrients: Vec<NutritionFactsLine>,
+
+    /// Vitamin and mineral lines
+    pub vitamins_minerals: Vec<NutritionFactsLine>,
+}
+
+/// Energy declared on an EU nutrition declaration
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+pub struct EuEnergy {
+    /// Energy in kilojoules
+    pub kj: u32,
+
+    /// Energy in kilocalories
+    pub kcal: u32,
+}
+
+/// A nutrient line on an EU nutrition declaration
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct EuNutritionLine {
+    /// Nutrient name as printed on the declaration
+    pub name: String,
+
+    /// Rounded amount per 100 g or 100 ml
+    pub per_100: LabelAmount,
+
+    /// Rounded amount per portion
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub per_portion: Option<LabelAmount>,
+
+    /// Percentage of the reference intake (per portion when a portion is declared)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference_intake_percent: Option<u32>,
+
+    /// Indentation level (1 for "of which" lines)
+    pub level: u8,
+}
+
+/// EU nutrition declaration
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct EuNutritionDeclaration {
+    /// Basis of the per-100 column (`100 g` or `100 ml`)
+    pub basis: String,
+
+    /// Portion size as printed on the declaration
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub portion: Option<String>,
+
+    /// Energy per 100 g or 100 ml
+    pub energy_per_100: EuEnergy,
+
+    /// Energy per portion
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub energy_per_portion: Option<EuEnergy>,
+
+    /// Energy as a percentage of the reference intake
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub energy_reference_intake_percent: Option<u32>,
+
+    /// Nutrient lines
+    pub nutrients: Vec<EuNutritionLine>,
+
+    /// Reference intake statement
+    pub reference_intake_description: String,
+}
+
+/// Rounding rule for a US nutrient
+#[derive(Debug, Clone, Copy)]
+enum FdaRounding {
+    Fat,
+    Cholesterol,
+    Sodium,
+    Carbohydrate,
+    None,
+}
+
+/// Rounding rule for an EU nutrient
+#[derive(Debug, Clone, Copy)]
+enum EuRounding {
+    Macronutrient,
+    Saturates,
+    Salt,
+    Micronutrient,
+}
+
+impl Nutrition {
+    /// Build a US FDA Nutrition Facts label for this serving
+    ///
+    /// The daily value reference set and serving size description are taken
+    /// from `nutrition_standards.us_fda` when present.
+    pub fn fda_label(&self) -> OmsResult<NutritionFactsLabel> {
+        let fda = self.nutrition_standards.as_ref().and_then(|standards| standards.us_fda.as_ref());
+        let daily_value_year = fda.map(|fda| fda.daily_value_year).unwrap_or(DEFAULT_DAILY_VALUE_YEAR);
+        let daily_values = if daily_value_year >= 2016 { DAILY_VALUES_2016 } else { DAILY_VALUES_1993 };
+
+        let measured = self.serving_size.as_ref()
+            .map(|size| format!("{}{}", format_number(size.value), size.unit));
+        let serving_size = match (fda.map(|fda| fda.serving_size_description.as_str()), measured) {
+            (Some(description), Some(measured)) => format!("{} ({})", description, measured),
+            (Some(description), None) => description.to_string(),
+            (None, Some(measured)) => measured,
+            (None, None) => return Err(OmsError::MissingRequiredField("nutrition.serving_size".to_string())),
+        };
+
+        let calories = self.calories
+            .ok_or_else(|| OmsError::MissingRequiredField("nutrition.calories".to_string()))?;
+
+        let mut nutrients = Vec::new();
+        let mut add = |name: &str, amount: Option<MeasurementValue>, unit: Unit, rounding: FdaRounding, dv_key: Option<&str>, level: u8| -> OmsResult<()> {
+            if let Some(amount) = amount {
+                let amount = amount.convert_to(unit)?;
+                let daily_value_percent = match dv_key.and_then(|key| daily_value(daily_values, key)) {
+                    Some(reference) => Some(round_percent(percent_of(&amount, reference)?)),
+                    None => None,
+                };
+                nutrients.push(NutritionFactsLine {
+                    name: name.to_string(),
+                    amount: round_fda(amount.value, &amount.unit, rounding),
+                    daily_value_percent,
+                    level,
+                });
+            }
+            Ok(())
+        };
+
+        let sugars_name = if daily_value_year >= 2016 { "Total Sugars" } else { "Sugars" };
+
+        add("Total Fat", self.fat.as_ref().map(|fat| fat.measurement()), Unit::Gram, FdaRounding::Fat, Some("total_fat"), 0)?;
+        add("Saturated Fat", detail(&self.fat, "saturated"), Unit::Gram, FdaRounding::Fat, Some("saturated_fat"), 1)?;
+        add("Trans Fat", detail(&self.fat, "trans"), Unit::Gram, FdaRounding::Fat, None, 1)?;
+        add("Cholesterol", self.cholesterol.clone(), Unit::Milligram, FdaRounding::Cholesterol, Some("cholesterol"), 0)?;
+        add("Sodium", self.sodium.clone(), Unit::Milligram, FdaRounding::Sodium, Some("sodium"), 0)?;
+        add("Total Carbohydrate", self.carbohydrates.as_ref().map(|carbs| carbs.measurement()), Unit::Gram, FdaRounding::Carbohydrate, Some("total_carbohydrate"), 0)?;
+        add("Dietary Fiber", detail(&self.carbohydrates, "fiber"), Unit::Gram, FdaRounding::Carbohydrate, Some("dietary_fiber"), 1)?;
+        add(sugars_name, detail(&self.carbohydrates, "sugars"), Unit::Gram, FdaRounding::Carbohydrate, None, 1)?;
+        if daily_value_year >= 2016 {
+            add("Added Sugars", detail(&self.carbohydrates, "added_sugars"), Unit::Gram, FdaRounding::Carbohydrate, Some("added_sugars"), 2)?;
+        }
+        add("Protein", self.protein.clone(), Unit::Gram, FdaRounding::Carbohydrate, None, 0)?;
+
+        let vitamins_minerals = self.vitamins.iter().chain(&self.minerals).flatten()
+            .map(|entry| {
+                let amount = MeasurementValue { value: entry.value, unit: entry.unit.clone() };
+                let percent = match entry.daily_value_percent {
+                    Some(percent) => Some(percent),
+                    None => match daily_value(daily_values, &entry.name.to_lowercase()) {
+                        // Units such as IU and mcg cannot be compared without nutrient-specific factors
+                        Some(reference) => percent_of(&amount, reference).ok(),
+                        None => None,
+                    },
+                };
+                NutritionFactsLine {
+                    name: entry.name.clone(),
+                    amount: round_fda(entry.value, &entry.unit, FdaRounding::None),
+                    daily_value_percent: percent.map(round_micronutrient_percent),
+                    level: 0,
+                }
+            })
+            .collect();
+
+        Ok(NutritionFactsLabel {
+            serving_size,
+            calories: round_calories(calories),
+            daily_value_year,
+            nutrients,
+            vitamins_minerals,
+        })
+    }
+
+    /// Build an EU nutrition declaration per 100 g (or 100 ml) and per portion
+    ///
+    /// The serving size must be a mass or volume so the values can be
+    /// expressed per 100 g or 100 ml; the serving is declared as the portion.
+    pub fn eu_declaration(&self) -> OmsResult<EuNutritionDeclaration> {
+        let serving = self.serving_size.as_ref()
+            .ok_or_else(|| OmsError::MissingRequiredField("nutrition.serving_size".to_string()))?;
+        let base_unit = match serving.parsed_unit()? {
+            unit if unit.dimension() == crate::units::Dimension::Volume => Unit::Milliliter,
+            _ => Unit::Gram,
+        };
+        let per_100 = self.scale_to_serving(&MeasurementValue::new(100.0, base_unit))?;
+
+        let calories = self.calories
+            .ok_or_else(|| OmsError::MissingRequiredField("nutrition.calories".to_string()))?;
+        let energy = |kcal: f64| EuEnergy {
+            kj: (kcal * KJ_PER_KCAL).round() as u32,
+            kcal: kcal.round() as u32,
+        };
+
+        let mut nutrients = Vec::new();
+        let mut add = |name: &str, portion: Option<MeasurementValue>, hundred: Option<MeasurementValue>, unit: Unit, rounding: EuRounding, ri_key: Option<&str>, level: u8| -> OmsResult<()> {
+            if let (Some(portion), Some(hundred)) = (portion, hundred) {
+                let portion = portion.convert_to(unit)?;
+                let hundred = hundred.convert_to(unit)?;
+                let reference_intake_percent = match ri_key.and_then(|key| daily_value(EU_REFERENCE_INTAKES, key)) {
+                    Some(reference) => percent_of(&portion, reference).ok().map(round_percent),
+                    None => None,
+                };
+                nutrients.push(EuNutritionLine {
+                    name: name.to_string(),
+                    per_100: round_eu(hundred.value, &hundred.unit, rounding),
+                    per_portion: Some(round_eu(portion.value, &portion.unit, rounding)),
+                    reference_intake_percent,
+                    level,
+                });
+            }
+            Ok(())
+        };
+
+        let fat = |n: &Nutrition| n.fat.as_ref().map(|fat| fat.measurement());
+        let carbohydrate = |n: &Nutrition| n.carbohydrates.as_ref().map(|carbs| carbs.measurement());
+        let salt = |n: &Nutrition| n.sodium.as_ref().map(|sodium| sodium.scale(2.5));
+
+        add("Fat", fat(self), fat(&per_100), Unit::Gram, EuRounding::Macronutrient, Some("fat"), 0)?;
+        add("of which saturates", detail(&self.fat, "saturated"), detail(&per_100.fat, "saturated"), Unit::Gram, EuRounding::Saturates, Some("saturates"), 1)?;
+        add("of which mono-unsaturates", detail(&self.fat, "monounsaturated"), detail(&per_100.fat, "monounsaturated"), Unit::Gram, EuRounding::Macronutrient, None, 1)?;
+        add("of which polyunsaturates", detail(&self.fat, "polyunsaturated"), detail(&per_100.fat, "polyunsaturated"), Unit::Gram, EuRounding::Macronutrient, None, 1)?;
+        add("Carbohydrate", carbohydrate(self), carbohydrate(&per_100), Unit::Gram, EuRounding::Macronutrient, Some("carbohydrate"), 0)?;
+        add("of which sugars", detail(&self.carbohydrates, "sugars"), detail(&per_100.carbohydrates, "sugars"), Unit::Gram, EuRounding::Macronutrient, Some("sugars"), 1)?;
+        add("Fibre", detail(&self.carbohydrates, "fiber"), detail(&per_100.carbohydrates, "fiber"), Unit::Gram, EuRounding::Macronutrient, None, 0)?;
+        add("Protein", self.protein.clone(), per_100.protein.clone(), Unit::Gram, EuRounding::Macronutrient, Some("protein"), 0)?;
+        add("Salt", salt(self), salt(&per_100), Unit::Gram, EuRounding::Salt, Some("salt"), 0)?;
+
+        let portion_entries = self.vitamins.iter().chain(&self.minerals).flatten();
+        let hundred_entries = per_100.vitamins.iter().chain(&per_100.minerals).flatten();
+        for (portion, hundred) in portion_entries.zip(hundred_entries) {
+            let measurement = |entry: &VitaminMineral| MeasurementValue { value: entry.value, unit: entry.unit.clone() };
+            let unit = portion.unit.parse::<Unit>()?;
+            add(&portion.name, Some(measurement(portion)), Some(measurement(hundred)), unit, EuRounding::Micronutrient, Some(&portion.name.to_lowercase()), 0)?;
+        }
+
+        let reference_intake_description = self.nutrition_standards.as_ref()
+            .and_then(|standards| standards.eu_regulation.as_ref())
+            .map(|eu| eu.reference_intake_description.clone())
+            .unwrap_or_else(|| DEFAULT_REFERENCE_INTAKE_DESCRIPTION.to_string());
+
+        let basis = format!("100 {}", if base_unit == Unit::Milliliter { "ml" } else { "g" });
+        let energy_reference = daily_value(EU_REFERENCE_INTAKES, "energy").map(|(value, _)| value).unwrap_or(2000.0);
+
+        Ok(EuNutritionDeclaration {
+            basis,
+            portion: Some(format!("{} {}", format_number(serving.value), serving.unit)),
+            energy_per_100: energy(per_100.calories.unwrap_or(0.0)),
+            energy_per_portion: Some(energy(calories)),
+            energy_reference_intake_percent: Some(round_percent(calories / energy_reference * 100.0)),
+            nutrients,
+            reference_intake_description,
+        })
+    }
+}
+
+impl NutritionFactsLabel {
+    /// Render the label as fixed-width plain text
+    pub fn to_text(&self) -> String {
+        let heavy = "=".repeat(TEXT_WIDTH);
+        let light = "-".repeat(TEXT_WIDTH);
+
+        let mut lines = vec![
+            "Nutrition Facts".to_string(),
+            format!("Serving size {}", self.serving_size),
+            heavy.clone(),
+            text_row("Calories", &self.calories.to_string()),
+            light.clone(),
+            text_row("", "% Daily Value*"),
+        ];
+
+        for line in &self.nutrients {
+            lines.push(text_row(&fda_line_text(line), &percent_text(line.daily_value_percent)));
+        }
+
+        if !self.vitamins_minerals.is_empty() {
+            lines.push(heavy.clone());
+            for line in &self.vitamins_minerals {
+                lines.push(text_row(&fda_line_text(line), &percent_text(line.daily_value_percent)));
+            }
+        }
+
+        lines.push(light);
+        lines.push(self.footnote().to_string());
+        lines.join("\n")
+    }
+
+    /// Render the label as an HTML fragment
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        html.push_str("<section class=\"nutrition-facts\">\n");
+        html.push_str("  <h1>Nutrition Facts</h1>\n");
+        html.push_str(&format!("  <p class=\"serving-size\">Serving size {}</p>\n", escape_html(&self.serving_size)));
+        html.push_str(&format!("  <p class=\"calories\">Calories <span>{}</span></p>\n", self.calories));
+        html.push_str("  <table>\n");
+        html.push_str("    <thead><tr><th></th><th>% Daily Value*</th></tr></thead>\n");
+        html.push_str("    <tbody>\n");
+        for line in self.nutrients.iter().chain(&self.vitamins_minerals) {
+            html.push_str(&format!(
+                "      <tr class=\"level-{}\"><td>{}</td><td>{}</td></tr>\n",
+                line.level,
+                escape_html(&fda_line_text(line)),
+                percent_text(line.daily_value_percent),
+            ));
+        }
+        html.push_str("    </tbody>\n");
+        html.push_str("  </table>\n");
+        html.push_str(&format!("  <p class=\"footnote\">{}</p>\n", escape_html(self.footnote())));
+        html.push_str("</section>\n");
+        html
+    }
+
+    /// Render the label as pretty-printed JSON
+    pub fn to_json(&self) -> OmsResult<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Daily value footnote for the reference year
+    fn footnote(&self) -> &'static str {
+        if self.daily_value_year >= 2016 {
+            "* The % Daily Value (DV) tells you how much a nutrient in a serving of food contributes to a daily diet. 2,000 calories a day is used for general nutrition advice."
+        } else {
+            "* Percent Daily Values are based on a 2,000 calorie diet."
+        }
+    }
+}
+
+impl EuNutritionDeclaration {
+    /// Render the declaration as fixed-width plain text
+    pub fn to_text(&self) -> String {
+        let columns = |name: &str, per_100: &str, per_portion: &str, percent: &str| {
+            format!("{:<26}{:>14}{:>14}{:>8}", name, per_100, per_portion, percent)
+        };
+
+        let portion = self.portion.as_deref().map(|p| format!("Per {}", p)).unwrap_or_default();
+        let mut lines = vec![
+            "Nutrition declaration".to_string(),
+            columns("", &format!("Per {}", self.basis), &portion, "%RI*"),
+            columns(
+                "Energy",
+                &energy_text(&self.energy_per_100),
+                &self.energy_per_portion.as_ref().map(energy_text).unwrap_or_default(),
+                &percent_text(self.energy_reference_intake_percent),
+            ),
+        ];
+
+        for line in &self.nutrients {
+            lines.push(columns(
+                &format!("{}{}", "  ".repeat(line.level as usize), line.name),
+                &line.per_100.to_string(),
+                &line.per_portion.as_ref().map(ToString::to_string).unwrap_or_default(),
+                &percent_text(line.reference_intake_percent),
+            ));
+        }
+
+        lines.push(format!("* {}", self.reference_intake_description));
+        lines.join("\n")
+    }
+
+    /// Render the declaration as an HTML fragment
+    pub fn to_html(&self) -> String {
+        let portion = self.portion.as_deref().map(|p| format!("Per {}", p)).unwrap_or_default();
+
+        let mut html = String::new();
+        html.push_str("<section class=\"nutrition-declaration\">\n");
+        html.push_str("  <table>\n");
+        html.push_str(&format!(
+            "    <thead><tr><th>Nutrition declaration</th><th>Per {}</th><th>{}</th><th>%RI*</th></tr></thead>\n",
+            escape_html(&self.basis),
+            escape_html(&portion),
+        ));
+        html.push_str("    <tbody>\n");
+        html.push_str(&format!(
+            "      <tr class=\"level-0\"><td>Energy</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            energy_text(&self.energy_per_100),
+            self.energy_per_portion.as_ref().map(energy_text).unwrap_or_default(),
+            percent_text(self.energy_reference_intake_percent),
+        ));
+        for line in &self.nutrients {
+            html.push_str(&format!(
+                "      <tr class=\"level-{}\"><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                line.level,
+                escape_html(&line.name),
+                escape_html(&line.per_100.to_string()),
+                escape_html(&line.per_portion.as_ref().map(ToString::to_string).unwrap_or_default()),
+                percent_text(line.reference_intake_percent),
+            ));
+        }
+        html.push_str("    </tbody>\n");
+        html.push_str("  </table>\n");
+        html.push_str(&format!("  <p class=\"footnote\">* {}</p>\n", escape_html(&self.reference_intake_description)));
+        html.push_str("</section>\n");
+        html
+    }
+
+    /// Render the declaration as pretty-printed JSON
+    pub fn to_json(&self) -> OmsResult<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Get a breakdown entry of a nutrient as a measurement
+fn detail(nutrient: &Option<NutrientWithDetails>, key: &str) -> Option<MeasurementValue> {
+    nutrient.as_ref()
+        .and_then(|nutrient| nutrient.details.as_ref())
+        .and_then(|details| details.get(key))
+        .map(NutrientValue::measurement)
+}
+
+/// Look up a reference value by key
+fn daily_value(table: &[(&str, f64, Unit)], key: &str) -> Option<(f64, Unit)> {
+    table.iter()
+        .find(|(name, _, _)| *name == key)
+        .map(|(_, value, unit)| (*value, *unit))
+}
+
+/// Express an amount as a percentage of a reference value
+fn percent_of(amount: &MeasurementValue, reference: (f64, Unit)) -> OmsResult<f64> {
+    let (reference_value, reference_unit) = reference;
+    Ok(amount.convert_to(reference_unit)?.value / reference_value * 100.0)
+}
+
+/// Round calories per FDA rules (21 CFR 101.9(c)(1))
+fn round_calories(calories: f64) -> u32 {
+    if calories < 5.0 {
+        0
+    } else if calories <= 50.0 {
+        round_to(calories, 5.0) as u32
+    } else {
+        round_to(calories, 10.0) as u32
+    }
+}
+
+/// Round a nutrient amount per FDA rules (21 CFR 101.9(c))
+fn round_fda(value: f64, unit: &str, rounding: FdaRounding) -> LabelAmount {
+    let amount = |value: f64, less_than: bool| LabelAmount { value, unit: unit.to_string(), less_than };
+
+    match rounding {
+        FdaRounding::Fat if value < 0.5 => amount(0.0, false),
+        FdaRounding::Fat if value < 5.0 => amount(round_to(value, 0.5), false),
+        FdaRounding::Fat => amount(round_to(value, 1.0), false),
+        FdaRounding::Cholesterol if value < 2.0 => amount(0.0, false),
+        FdaRounding::Cholesterol if value <= 5.0 => amount(5.0, true),
+        FdaRounding::Cholesterol => amount(round_to(value, 5.0), false),
+        FdaRounding::Sodium if value < 5.0 => amount(0.0, false),
+        FdaRounding::Sodium if value <= 140.0 => amount(round_to(value, 5.0), false),
+        FdaRounding::Sodium => amount(round_to(value, 10.0), false),
+        FdaRounding::Carbohydrate if value < 0.5 => amount(0.0, false),
+        FdaRounding::Carbohydrate if value < 1.0 => amount(1.0, true),
+        FdaRounding::Carbohydrate => amount(round_to(value, 1.0), false),
+        FdaRounding::None => amount(round_to(value, 0.01), false),
+    }
+}
+
+/// Round a nutrient amount per the European Commission rounding guidance
+fn round_eu(value: f64, unit: &str, rounding: EuRounding) -> LabelAmount {
+    let amount = |value: f64, less_than: bool| LabelAmount { value, unit: unit.to_string(), less_than };
+
+    match rounding {
+        EuRounding::Macronutrient if value >= 10.0 => amount(round_to(value, 1.0), false),
+        EuRounding::Macronutrient if value > 0.5 => amount(round_to(value, 0.1), false),
+        EuRounding::Macronutrient => amount(0.5, true),
+        EuRounding::Saturates if value >= 10.0 => amount(round_to(value, 1.0), false),
+        EuRounding::Saturates if value > 0.1 => amount(round_to(value, 0.1), false),
+        EuRounding::Saturates => amount(0.1, true),
+        EuRounding::Salt if value >= 1.0 => amount(round_to(value, 0.1), false),
+        EuRounding::Salt if value > 0.0125 => amount(round_to(value, 0.01), false),
+        EuRounding::Salt => amount(0.01, true),
+        EuRounding::Micronutrient if value >= 100.0 => amount(round_to(value, 1.0), false),
+        EuRounding::Micronutrient if value >= 10.0 => amount(round_to(value, 0.1), false),
+        EuRounding::Micronutrient => amount(round_to(value, 0.01), false),
+    }
+}
+
+/// Round a macronutrient percent daily value to the nearest percent
+fn round_percent(percent: f64) -> u32 {
+    percent.max(0.0).round() as u32
+}
+
+/// Round a vitamin or mineral percent daily value per FDA rules (21 CFR 101.9(c)(8)(iii))
+fn round_micronutrient_percent(percent: f64) -> u32 {
+    if percent < 2.0 {
+        0
+    } else if percent <= 10.0 {
+        round_to(percent, 2.0) as u32
+    } else if percent <= 50.0 {
+        round_to(percent, 5.0) as u32
+    } else {
+        round_to(percent, 10.0) as u32
+    }
+}
+
+/// Round a value to the nearest multiple of a step
+fn round_to(value: f64, step: f64) -> f64 {
+    let rounded = (value / step).round() * step;
+    // Remove floating point noise such as 2.4000000000000004
+    (rounded * 100.0).round() / 100.0
+}
+
+/// Format a number without trailing zeros
+pub(crate) fn format_number(value: f64) -> String {
+    let formatted = format!("{:.2}", value);
+    formatted.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Text of a Nutrition Facts line, without the percent daily value
+fn fda_line_text(line: &NutritionFactsLine) -> String {
+    let indent = "  ".repeat(line.level as usize);
+    if line.name == "Added Sugars" {
+        format!("{}Includes {} Added Sugars", indent, line.amount)
+    } else {
+        format!("{}{} {}", indent, line.name, line.amount)
+    }
+}
+
+/// Format an optional percentage
+fn percent_text(percent: Option<u32>) -> String {
+    percent.map(|percent| format!("{}%", percent)).unwrap_or_default()
+}
+
+/// Format an EU energy value
+fn energy_text(energy: &EuEnergy) -> String {
+    format!("{} kJ / {} kcal", energy.kj, energy.kcal)
+}
+
+/// Lay out a plain text row with a right-aligned value
+fn text_row(left: &str, right: &str) -> String {
+    let padding = TEXT_WIDTH.saturating_sub(left.chars().count() + right.chars().count()).max(1);
+    format!("{}{}{}", left, " ".repeat(padding), right)
+}
+
+/// Escape text for inclusion in HTML
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn sandwich_nutrition(daily_value_year: u16) -> Nutrition {
+        let grams = |value: f64| NutrientValue::Simple(MeasurementValue::new(value, Unit::Gram));
+
+        Nutrition {
+            serving_size: Some(MeasurementValue::new(250.0, Unit::Gram)),
+            calories: Some(487.0),
+            protein: Some(MeasurementValue::new(21.6, Unit::Gram)),
+            fat: Some(NutrientWithDetails {
+                value: 18.3,
+                unit: "g".to_string(),
+                details: Some(HashMap::from([
+                    ("saturated".to_string(), grams(4.2)),
+                    ("trans".to_string(), grams(0.3)),
+                ])),
+            }),
+            carbohydrates: Some(NutrientWithDetails {
+                value: 52.4,
+                unit: "g".to_string(),
+                details: Some(HashMap::from([
+                    ("fiber".to_string(), grams(0.7)),
+                    ("sugars".to_string(), grams(6.4)),
+                    ("added_sugars".to_string(), grams(2.2)),
+                ])),
+            }),
+            sodium: Some(MeasurementValue::new(0.962, Unit::Gram)),
+            cholesterol: Some(MeasurementValue::new(3.0, Unit::Milligram)),
+            vitamins: Some(vec![VitaminMineral {
+                name: "Vitamin D".to_string(),
+                value: 2.0,
+                unit: "mcg".to_string(),
+                daily_value_percent: None,
+            }]),
+            minerals: Some(vec![VitaminMineral {
+                name: "Calcium".to_string(),
+                value: 260.0,
+                unit: "mg".to_string(),
+                daily_value_percent: None,
+            }]),
+            allergens: None,
+            dietary_flags: None,
+            health_claims: None,
+            ingredients: None,
+            nutrition_standards: Some(NutritionStandards {
+                us_fda: Some(UsFdaInfo {
+                    serving_size_description: "1 sandwich".to_string(),
+                    daily_value_year,
+                }),
+                eu_regulation: None,
+            }),
+        }
+    }
+
+    fn line<'a>(lines: &'a [NutritionFactsLine], name: &str) -> &'a NutritionFactsLine {
+        lines.iter().find(|line| line.name == name).unwrap()
+    }
+
+    #[test]
+    fn test_fda_label_rounding_and_daily_values() {
+        let label = sandwich_nutrition(2020).fda_label().unwrap();
+        assert_eq!(label.serving_size, "1 sandwich (250g)");
+        assert_eq!(label.calories, 490);
+
+        let fat = line(&label.nutrients, "Total Fat");
+        assert_eq!(fat.amount.to_string(), "18g");
+        assert_eq!(fat.daily_value_percent, Some(23)); // 18.3 / 78
+
+        assert_eq!(line(&label.nutrients, "Saturated Fat").amount.to_string(), "4g");
+        assert_eq!(line(&label.nutrients, "Trans Fat").amount.to_string(), "0g");
+        assert_eq!(line(&label.nutrients, "Cholesterol").amount.to_string(), "<5mg");
+        assert_eq!(line(&label.nutrients, "Sodium").amount.to_string(), "960mg");
+        assert_eq!(line(&label.nutrients, "Sodium").daily_value_percent, Some(42)); // 962 / 2300
+        assert_eq!(line(&label.nutrients, "Dietary Fiber").amount.to_string(), "<1g");
+        assert_eq!(line(&label.nutrients, "Protein").amount.to_string(), "22g");
+
+        let vitamin_d = line(&label.vitamins_minerals, "Vitamin D");
+        assert_eq!(vitamin_d.daily_value_percent, Some(10));
+        assert_eq!(line(&label.vitamins_minerals, "Calcium").daily_value_percent, Some(20));
+
+        let text = label.to_text();
+        assert!(text.contains("Includes 2g Added Sugars"));
+        assert!(text.contains("2,000 calories a day"));
+        assert!(text.contains(&format!("Calories{}490", " ".repeat(TEXT_WIDTH - 11))));
+    }
+
+    #[test]
+    fn test_fda_label_uses_reference_year() {
+        let label = sandwich_nutrition(1993).fda_label().unwrap();
+        assert_eq!(line(&label.nutrients, "Total Fat").daily_value_percent, Some(28)); // 18.3 / 65
+        assert_eq!(line(&label.nutrients, "Sodium").daily_value_percent, Some(40)); // 962 / 2400
+        assert!(label.nutrients.iter().any(|line| line.name == "Sugars"));
+        assert!(!label.nutrients.iter().any(|line| line.name == "Added Sugars"));
+
+        // The 1993 vitamin D reference is in IU and cannot be compared to mcg
+        assert_eq!(line(&label.vitamins_minerals, "Vitamin D").daily_value_percent, None);
+        assert_eq!(line(&label.vitamins_minerals, "Calcium").daily_value_percent, Some(25)); // 26% rounds to 5%
+    }
+
+    #[test]
+    fn test_eu_declaration() {
+        let declaration = sandwich_nutrition(2020).eu_declaration().unwrap();
+        assert_eq!(declaration.basis, "100 g");
+        assert_eq!(declaration.portion, Some("250 g".to_string()));
+        assert_eq!(declaration.energy_per_100, EuEnergy { kj: 815, kcal: 195 });
+        assert_eq!(declaration.energy_per_portion, Some(EuEnergy { kj: 2038, kcal: 487 }));
+        assert_eq!(declaration.energy_reference_intake_percent, Some(24));
+
+        let nutrient = |name: &str| declaration.nutrients.iter().find(|line| line.name == name).unwrap();
+        assert_eq!(nutrient("Fat").per_100.to_string(), "7.3g");
+        assert_eq!(nutrient("Fat").per_portion.as_ref().unwrap().to_string(), "18g");
+        assert_eq!(nutrient("Fat").reference_intake_percent, Some(26));
+        assert_eq!(nutrient("of which saturates").per_100.to_string(), "1.7g");
+        assert_eq!(nutrient("Fibre").per_100.to_string(), "<0.5g");
+        assert_eq!(nutrient("Salt").per_100.to_string(), "0.96g");
+        assert_eq!(nutrient("Salt").per_portion.as_ref().unwrap().to_string(), "2.4g");
+        assert_eq!(nutrient("Salt").reference_intake_percent, Some(40));
+
+        let html = declaration.to_html();
+        assert!(html.contains("<td>Energy</td><td>815 kJ / 195 kcal</td>"));
+        assert!(html.contains(DEFAULT_REFERENCE_INTAKE_DESCRIPTION));
+
+        let json: serde_json::Value = serde_json::from_str(&declaration.to_json().unwrap()).unwrap();
+        assert_eq!(json["energy_per_100"]["kj"], 815);
+    }
+
+    #[test]
+    fn test_labels_require_serving_size() {
+        let mut nutrition = sandwich_nutrition(2020);
+        nutrition.serving_size = None;
+        assert!(nutrition.eu_declaration().is_err());
+
+        // The FDA serving description alone is enough for a US label
+        assert_eq!(nutrition.fda_label().unwrap().serving_size, "1 sandwich");
+
+        nutrition.nutrition_standards = None;
+        assert!(nutrition.fda_label().is_err());
+    }
+}
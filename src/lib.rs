@@ -8,8 +8,39 @@ pub use crate::document::*;
 pub use crate::validation::*;
 pub use crate::url::*;
 pub use crate::utils::*;
-pub use crate::builder::*;
+pub use crate::merge::*;
+pub use crate::cart::*;
+pub use crate::inventory::*;
+pub use crate::ingredients::*;
+pub use crate::units::*;
+pub use crate::labels::*;
+pub use crate::localization::*;
+pub use crate::currency::*;
+pub use crate::schema::*;
+pub use crate::parsing::*;
+pub use crate::streaming::*;
+pub use crate::binary::*;
+pub use crate::formats::*;
+pub use crate::spreadsheet::*;
+pub use crate::schema_org::*;
+pub use crate::render::*;
+pub use crate::tickets::*;
+pub use crate::resolver::*;
+pub use crate::orders::*;
+pub use crate::webhooks::*;
+pub use crate::canonical::*;
 
+#[cfg(feature = "network")]
+pub use crate::client::*;
+#[cfg(feature = "network")]
+pub use crate::fetch::*;
+#[cfg(feature = "network")]
+pub use crate::subscriber::*;
+
+#[cfg(feature = "server")]
+pub use crate::server::*;
+#[cfg(feature = "server")]
+pub use crate::events::*;
 
 #[cfg(feature = "tap-to-order")]
 pub use crate::tap_to_order::*;
@@ -21,6 +52,39 @@ mod validation;
 mod url;
 mod utils;
 mod builder;
+mod merge;
+mod cart;
+mod inventory;
+mod ingredients;
+mod units;
+mod labels;
+mod localization;
+mod currency;
+mod schema;
+mod parsing;
+mod streaming;
+mod binary;
+mod formats;
+mod spreadsheet;
+mod schema_org;
+mod render;
+mod tickets;
+mod resolver;
+mod orders;
+mod webhooks;
+mod canonical;
+
+#[cfg(feature = "network")]
+mod client;
+#[cfg(feature = "network")]
+mod fetch;
+#[cfg(feature = "network")]
+mod subscriber;
+
+#[cfg(feature = "server")]
+mod server;
+#[cfg(feature = "server")]
+mod events;
 
 #[cfg(feature = "tap-to-order")]
 mod tap_to_order;
@@ -64,10 +128,59 @@ pub enum OmsError {
     #[error("Invalid field value: {0}")]
     InvalidFieldValue(String),
     
+    #[error("Invalid unit: {0}")]
+    InvalidUnit(String),
+    
+    #[error("Incompatible units: {0}")]
+    IncompatibleUnits(String),
+    
+    #[error("Invalid currency: {0}")]
+    InvalidCurrency(String),
+    
+    #[error("Currency mismatch: {0}")]
+    CurrencyMismatch(String),
+    
+    #[error("Invalid order status transition: {0}")]
+    InvalidStatusTransition(String),
+    
+    #[error("Invalid webhook signature: {0}")]
+    InvalidSignature(String),
+    
+    #[error("Schema validation failed: {}", crate::schema::describe_violations(.0))]
+    SchemaViolation(Vec<SchemaViolation>),
+    
+    #[error("Unknown fields: {}", .0.join(", "))]
+    UnknownFields(Vec<String>),
+    
+    #[error("Binary encoding error: {0}")]
+    BinaryEncodingError(String),
+    
+    #[error("CSV error: {0}")]
+    CsvError(#[from] csv::Error),
+    
+    #[error("Unsupported format: {0}")]
+    UnsupportedFormat(String),
+    
+    #[cfg(feature = "yaml")]
+    #[error("YAML error: {0}")]
+    YamlError(#[from] serde_yaml::Error),
+    
+    #[cfg(feature = "toml")]
+    #[error("TOML error: {0}")]
+    TomlError(String),
+    
     #[cfg(feature = "network")]
     #[error("Network error: {0}")]
     NetworkError(#[from] reqwest::Error),
     
+    #[cfg(feature = "network")]
+    #[error("HTTP status {0}: {1}")]
+    HttpStatus(u16, String),
+    
+    #[cfg(feature = "network")]
+    #[error("Event stream error: {0}")]
+    EventStreamError(String),
+    
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
     
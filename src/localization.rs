@@ -0,0 +1,489 @@
+// src/localization.rs
+//
+// Language tags, localized text lookup and document localization
+
+use crate::types::*;
+use crate::{OmsError, OmsResult};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// A BCP 47 language tag such as `en`, `ja-JP` or `zh-Hant-TW`
+///
+/// Tags are stored in canonical case (`en-US`, `zh-Hant`) and compared
+/// case-insensitively when looking up translations.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LanguageTag(String);
+
+impl LanguageTag {
+    /// Parse and canonicalize a language tag
+    pub fn parse(tag: &str) -> OmsResult<Self> {
+        let invalid = || OmsError::InvalidFieldValue(format!("invalid language tag '{}'", tag));
+
+        let mut subtags = tag.trim().split(['-', '_']);
+        let language = subtags.next().filter(|s| (2..=8).contains(&s.len()) && s.chars().all(|c| c.is_ascii_alphabetic()))
+            .ok_or_else(invalid)?;
+
+        let mut canonical = vec![language.to_ascii_lowercase()];
+        for subtag in subtags {
+            if subtag.is_empty() || subtag.len() > 8 || !subtag.chars().all(|c| c.is_ascii_alphanumeric()) {
+                return Err(invalid());
+            }
+
+            let alphabetic = subtag.chars().all(|c| c.is_ascii_alphabetic());
+            let subtag = match subtag.len() {
+                // Script, e.g. `Hant`
+                4 if alphabetic && canonical.len() == 1 => {
+                    let lower = subtag.to_ascii_lowercase();
+                    lower[..1].to_ascii_uppercase() + &lower[1..]
+                },
+                // Region, e.g. `US`
+                2 if alphabetic && canonical.len() <= 2 => subtag.to_ascii_uppercase(),
+                _ => subtag.to_ascii_lowercase(),
+            };
+            canonical.push(subtag);
+        }
+
+        Ok(Self(canonical.join("-")))
+    }
+
+    /// Get the tag as a string
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Get the tag with its last subtag removed (`zh-Hant-TW` → `zh-Hant`)
+    pub fn parent(&self) -> Option<LanguageTag> {
+        self.0.rfind('-').map(|index| Self(self.0[..index].to_string()))
+    }
+
+    /// Get this tag followed by each of its parents, most specific first
+    pub fn fallback_chain(&self) -> Vec<LanguageTag> {
+        let mut chain = vec![self.clone()];
+        while let Some(parent) = chain.last().and_then(LanguageTag::parent) {
+            chain.push(parent);
+        }
+        chain
+    }
+
+    /// Whether either tag is a parent of (or equal to) the other
+    ///
+    /// `en` and `en-GB` are related, `zh-Hans` and `zh-Hant` are not.
+    pub fn is_related(&self, other: &LanguageTag) -> bool {
+        self.fallback_chain().contains(other) || other.fallback_chain().contains(self)
+    }
+}
+
+impl FromStr for LanguageTag {
+    type Err = OmsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for LanguageTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl LocalizedText {
+    /// Create localized text from translations keyed by language tag
+    pub fn from_translations<I, K, V>(translations: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        LocalizedText::Translations(
+            translations.into_iter().map(|(tag, text)| (tag.into(), text.into())).collect()
+        )
+    }
+
+    /// Get the text without a target language
+    ///
+    /// For translations this prefers English, then the alphabetically first tag;
+    /// use [`LocalizedText::resolve`] or [`OmsDocument::localize`] to pick a language.
+    pub fn text(&self) -> &str {
+        match self {
+            LocalizedText::Plain(text) => text,
+            LocalizedText::Translations(translations) => {
+                let english = translations.iter()
+                    .filter(|(tag, _)| tag.eq_ignore_ascii_case("en") || tag.to_ascii_lowercase().starts_with("en-"))
+                    .min_by(|a, b| a.0.cmp(b.0));
+                english.or_else(|| translations.iter().min_by(|a, b| a.0.cmp(b.0)))
+                    .map(|(_, text)| text.as_str())
+                    .unwrap_or("")
+            },
+        }
+    }
+
+    /// Get the translation for a language, falling back to its parent tags
+    ///
+    /// Plain text is only returned when `default_locale` is related to the language.
+    pub fn get(&self, language: &LanguageTag, default_locale: Option<&LanguageTag>) -> Option<&str> {
+        match self {
+            LocalizedText::Plain(text) => match default_locale {
+                Some(locale) if locale.is_related(language) => Some(text),
+                _ => None,
+            },
+            LocalizedText::Translations(translations) => {
+                let parsed: Vec<(LanguageTag, &String)> = sorted(translations).into_iter()
+                    .filter_map(|(tag, text)| LanguageTag::parse(tag).ok().map(|tag| (tag, text)))
+                    .collect();
+
+                // Prefer the tag itself or one of its parents, then a more specific variant
+                language.fallback_chain().iter()
+                    .find_map(|tag| parsed.iter().find(|(key, _)| key == tag))
+                    .or_else(|| parsed.iter().find(|(key, _)| key.fallback_chain().contains(language)))
+                    .map(|(_, text)| text.as_str())
+            },
+        }
+    }
+
+    /// Resolve the text for the first language in a fallback chain that has a translation
+    pub fn resolve(&self, chain: &[LanguageTag], default_locale: Option<&LanguageTag>) -> &str {
+        chain.iter()
+            .find_map(|language| self.get(language, default_locale))
+            .unwrap_or_else(|| self.text())
+    }
+
+    /// Get the language tags this text is available in
+    pub fn languages(&self, default_locale: Option<&LanguageTag>) -> Vec<LanguageTag> {
+        match self {
+            LocalizedText::Plain(_) => default_locale.cloned().into_iter().collect(),
+            LocalizedText::Translations(translations) => {
+                sorted(translations).into_iter()
+                    .filter_map(|(tag, _)| LanguageTag::parse(tag).ok())
+                    .collect()
+            },
+        }
+    }
+}
+
+impl From<&str> for LocalizedText {
+    fn from(text: &str) -> Self {
+        LocalizedText::Plain(text.to_string())
+    }
+}
+
+impl From<String> for LocalizedText {
+    fn from(text: String) -> Self {
+        LocalizedText::Plain(text)
+    }
+}
+
+impl fmt::Display for LocalizedText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+impl PartialEq<str> for LocalizedText {
+    fn eq(&self, other: &str) -> bool {
+        self.text() == other
+    }
+}
+
+impl PartialEq<&str> for LocalizedText {
+    fn eq(&self, other: &&str) -> bool {
+        self.text() == *other
+    }
+}
+
+/// A localized field that lacks a translation for a language
+#[derive(Debug, Clone, PartialEq)]
+pub struct MissingTranslation {
+    /// Path to the field (e.g. `items[latte].customizations[size].options[small].name`)
+    pub path: String,
+
+    /// Language the field has no translation for
+    pub language: LanguageTag,
+}
+
+impl OmsDocument {
+    /// Get a copy of the document with all localized text resolved to one language
+    ///
+    /// Each field falls back through the parents of the requested tag, then
+    /// the document's own locale and its parents. The copy's locale is set to
+    /// the requested language.
+    pub fn localize(&self, language: &LanguageTag) -> OmsDocument {
+        let default_locale = self.default_locale();
+        let mut chain = language.fallback_chain();
+        for tag in default_locale.iter().flat_map(LanguageTag::fallback_chain) {
+            if !chain.contains(&tag) {
+                chain.push(tag);
+            }
+        }
+
+        let mut document = self.clone();
+        for_each_localized_text_mut(&mut document, &mut |text| {
+            let resolved = text.resolve(&chain, default_locale.as_ref()).to_string();
+            *text = LocalizedText::Plain(resolved);
+        });
+        document.metadata.locale = language.to_string();
+        document
+    }
+
+    /// Get every language the document's localized text is available in
+    pub fn languages(&self) -> Vec<LanguageTag> {
+        let default_locale = self.default_locale();
+        let mut languages: Vec<LanguageTag> = default_locale.iter().cloned().collect();
+        for_each_localized_text(self, &mut |_, text| {
+            for language in text.languages(default_locale.as_ref()) {
+                if !languages.contains(&language) {
+                    languages.push(language);
+                }
+            }
+        });
+        languages
+    }
+
+    /// The document's locale as a language tag, if valid
+    fn default_locale(&self) -> Option<LanguageTag> {
+        LanguageTag::parse(&self.metadata.locale).ok()
+    }
+}
+
+/// Find localized fields that have no translation for the given languages
+///
+/// Plain strings count as text in the document's locale. A translation for a
+/// related tag (e.g. `pt` for `pt-BR`) satisfies the requirement.
+pub fn missing_translations(document: &OmsDocument, languages: &[LanguageTag]) -> Vec<MissingTranslation> {
+    let default_locale = LanguageTag::parse(&document.metadata.locale).ok();
+    let mut missing = Vec::new();
+
+    for_each_localized_text(document, &mut |path, text| {
+        let available = text.languages(default_locale.as_ref());
+        for language in languages {
+            if !available.iter().any(|tag| tag.is_related(language)) {
+                missing.push(MissingTranslation {
+                    path: path.to_string(),
+                    language: language.clone(),
+                });
+            }
+        }
+    });
+
+    missing
+}
+
+/// Validate that every localized field has a translation for the given languages
+pub fn validate_translations(document: &OmsDocument, languages: &[LanguageTag]) -> OmsResult<()> {
+    let missing = missing_translations(document, languages);
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let details: Vec<String> = missing.iter()
+        .map(|entry| format!("{} ({})", entry.path, entry.language))
+        .collect();
+    Err(OmsError::InvalidFieldValue(format!("missing translations: {}", details.join(", "))))
+}
+
+/// Validate the structure of a localized field
+pub(crate) fn validate_localized_text(text: &LocalizedText, path: &str) -> OmsResult<()> {
+    if let LocalizedText::Translations(translations) = text {
+        if translations.is_empty() {
+            return Err(OmsError::MissingRequiredField(format!("translations for {}", path)));
+        }
+
+        for tag in translations.keys() {
+            LanguageTag::parse(tag)
+                .map_err(|_| OmsError::InvalidFieldValue(format!("invalid language tag '{}' in {}", tag, path)))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Visit every localized field in a document together with its path
+pub(crate) fn for_each_localized_text(document: &OmsDocument, visit: &mut dyn FnMut(&str, &LocalizedText)) {
+    for item in &document.items {
+        visit_item(item, &format!("items[{}]", item.id), visit);
+    }
+}
+
+/// Visit the localized fields of an item, its customizations and its components
+pub(crate) fn visit_item(item: &Item, path: &str, visit: &mut dyn FnMut(&str, &LocalizedText)) {
+    visit(&format!("{}.name", path), &item.name);
+    if let Some(description) = &item.description {
+        visit(&format!("{}.description", path), description);
+    }
+
+    if let Some(customizations) = &item.customizations {
+        visit_customizations(customizations, path, visit);
+    }
+
+    for component in item.components.iter().flatten() {
+        visit_item(component, &format!("{}.components[{}]", path, component.id), visit);
+    }
+}
+
+/// Visit the localized fields of customizations and their options
+fn visit_customizations(customizations: &[Customization], path: &str, visit: &mut dyn FnMut(&str, &LocalizedText)) {
+    for customization in customizations {
+        let customization_path = format!("{}.customizations[{}]", path, customization.id);
+        visit(&format!("{}.name", customization_path), &customization.name);
+
+        for option in customization.options.iter().flatten() {
+            let option_path = format!("{}.options[{}]", customization_path, option.id);
+            visit(&format!("{}.name", option_path), &option.name);
+
+            if let Some(sub_customizations) = &option.sub_customizations {
+                visit_customizations(sub_customizations, &option_path, visit);
+            }
+        }
+    }
+}
+
+/// Visit every localized field in a document mutably
+fn for_each_localized_text_mut(document: &mut OmsDocument, visit: &mut dyn FnMut(&mut LocalizedText)) {
+    for item in &mut document.items {
+        visit_item_mut(item, visit);
+    }
+}
+
+/// Visit the localized fields of an item mutably
+fn visit_item_mut(item: &mut Item, visit: &mut dyn FnMut(&mut LocalizedText)) {
+    visit(&mut item.name);
+    if let Some(description) = &mut item.description {
+        visit(description);
+    }
+
+    if let Some(customizations) = &mut item.customizations {
+        visit_customizations_mut(customizations, visit);
+    }
+
+    for component in item.components.iter_mut().flatten() {
+        visit_item_mut(component, visit);
+    }
+}
+
+/// Visit the localized fields of customizations and their options mutably
+fn visit_customizations_mut(customizations: &mut [Customization], visit: &mut dyn FnMut(&mut LocalizedText)) {
+    for customization in customizations {
+        visit(&mut customization.name);
+
+        for option in customization.options.iter_mut().flatten() {
+            visit(&mut option.name);
+
+            if let Some(sub_customizations) = &mut option.sub_customizations {
+                visit_customizations_mut(sub_customizations, visit);
+            }
+        }
+    }
+}
+
+/// Sort translations by tag so lookups are deterministic
+fn sorted(translations: &HashMap<String, String>) -> Vec<(&String, &String)> {
+    let mut entries: Vec<(&String, &String)> = translations.iter().collect();
+    entries.sort();
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::create_template;
+
+    fn tag(tag: &str) -> LanguageTag {
+        LanguageTag::parse(tag).unwrap()
+    }
+
+    fn tourist_menu() -> OmsDocument {
+        let mut doc = create_template("coffee-shop").unwrap();
+        let latte = doc.find_item_mut("latte").unwrap();
+        latte.name = LocalizedText::from_translations([
+            ("en", "Latte"),
+            ("es", "Café con leche"),
+            ("ja", "カフェラテ"),
+            ("zh-Hans", "拿铁"),
+        ]);
+        latte.customizations.as_mut().unwrap()[0].name = LocalizedText::from_translations([
+            ("en", "Size"),
+            ("es", "Tamaño"),
+            ("ja", "サイズ"),
+        ]);
+        doc
+    }
+
+    #[test]
+    fn test_language_tags() {
+        assert_eq!(tag("EN-us").as_str(), "en-US");
+        assert_eq!(tag("zh_hant_tw").as_str(), "zh-Hant-TW");
+        assert_eq!(tag("es-419").as_str(), "es-419");
+        assert!(LanguageTag::parse("").is_err());
+        assert!(LanguageTag::parse("e").is_err());
+        assert!(LanguageTag::parse("en--US").is_err());
+
+        let chain: Vec<String> = tag("zh-Hant-TW").fallback_chain().iter().map(ToString::to_string).collect();
+        assert_eq!(chain, vec!["zh-Hant-TW", "zh-Hant", "zh"]);
+
+        assert!(tag("en").is_related(&tag("en-GB")));
+        assert!(!tag("zh-Hans").is_related(&tag("zh-Hant")));
+    }
+
+    #[test]
+    fn test_plain_strings_remain_backward_compatible() {
+        let json = r#""Latte""#;
+        let text: LocalizedText = serde_json::from_str(json).unwrap();
+        assert_eq!(text, LocalizedText::Plain("Latte".to_string()));
+        assert_eq!(serde_json::to_string(&text).unwrap(), json);
+
+        let text: LocalizedText = serde_json::from_str(r#"{"en": "Latte", "ja": "カフェラテ"}"#).unwrap();
+        assert_eq!(text.get(&tag("ja-JP"), None), Some("カフェラテ"));
+        assert_eq!(text, "Latte");
+    }
+
+    #[test]
+    fn test_localize_with_fallbacks() {
+        let doc = tourist_menu();
+        assert!(doc.validate().is_ok());
+
+        let japanese = doc.localize(&tag("ja-JP"));
+        assert_eq!(japanese.metadata.locale, "ja-JP");
+        let latte = japanese.find_item("latte").unwrap();
+        assert_eq!(latte.name, LocalizedText::Plain("カフェラテ".to_string()));
+        assert_eq!(latte.customizations.as_ref().unwrap()[0].name, "サイズ");
+
+        // Plain strings are kept as they are
+        assert_eq!(latte.customizations.as_ref().unwrap()[1].name, "Milk");
+
+        // Chinese has no size translation and falls back to the document locale
+        let chinese = doc.localize(&tag("zh-Hans-CN"));
+        let latte = chinese.find_item("latte").unwrap();
+        assert_eq!(latte.name, "拿铁");
+        assert_eq!(latte.customizations.as_ref().unwrap()[0].name, "Size");
+    }
+
+    #[test]
+    fn test_missing_translations() {
+        let doc = tourist_menu();
+        let languages = doc.languages();
+        assert_eq!(languages, vec![tag("en-US"), tag("en"), tag("es"), tag("ja"), tag("zh-Hans")]);
+
+        let missing = missing_translations(&doc, &[tag("ja")]);
+        assert!(!missing.iter().any(|entry| entry.path == "items[latte].name"));
+        assert!(missing.iter().any(|entry| entry.path == "items[latte].customizations[milk].name"));
+        assert!(missing.iter().any(|entry| entry.path == "items[cappuccino].name"));
+
+        let missing = missing_translations(&doc, &[tag("zh-Hans")]);
+        assert!(missing.iter().any(|entry| entry.path == "items[latte].customizations[size].name"));
+
+        // English is covered everywhere by the plain strings
+        assert!(validate_translations(&doc, &[tag("en")]).is_ok());
+        assert!(validate_translations(&doc, &[tag("es")]).is_err());
+    }
+
+    #[test]
+    fn test_validation_rejects_malformed_translations() {
+        let mut doc = tourist_menu();
+        doc.items[0].name = LocalizedText::from_translations([("not a tag", "Latte")]);
+        assert!(doc.validate().is_err());
+
+        doc.items[0].name = LocalizedText::Translations(HashMap::new());
+        assert!(doc.validate().is_err());
+    }
+}
@@ -0,0 +1,374 @@
+// src/merge.rs
+//
+// Three-way merging of concurrently edited OMS documents
+
+use crate::types::*;
+use crate::OmsResult;
+use serde_json::{Map, Value};
+
+/// Kind of conflict detected during a three-way merge
+#[derive(Debug, Clone, PartialEq)]
+pub enum MergeConflictKind {
+    /// Both sides changed the same value in different ways
+    BothModified,
+    /// Both sides added the same value with different contents
+    BothAdded,
+    /// One side removed a value that the other side modified
+    DeleteModify,
+}
+
+/// A single conflict detected during a three-way merge
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeConflict {
+    /// Path to the conflicting value (e.g. `items[burger].base_price`)
+    pub path: String,
+
+    /// Kind of conflict
+    pub kind: MergeConflictKind,
+
+    /// Value in the common ancestor, if present
+    pub base: Option<Value>,
+
+    /// Value on our side, if present
+    pub ours: Option<Value>,
+
+    /// Value on their side, if present
+    pub theirs: Option<Value>,
+}
+
+/// Outcome of a three-way merge
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeResult {
+    /// Merged document (conflicting values keep our side)
+    pub document: OmsDocument,
+
+    /// Conflicts that could not be merged automatically
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl MergeResult {
+    /// Whether the merge completed without conflicts
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// Merge two concurrently edited documents against their common ancestor
+///
+/// Items, customizations and options are matched by ID. Non-overlapping edits
+/// are merged automatically; overlapping edits are reported as conflicts and
+/// resolved in favour of `ours`.
+pub fn merge_documents(
+    base: &OmsDocument,
+    ours: &OmsDocument,
+    theirs: &OmsDocument,
+) -> OmsResult<MergeResult> {
+    let mut conflicts = Vec::new();
+
+    // Merge everything except the items, which are matched by ID below
+    let header = |document: &OmsDocument| -> OmsResult<Value> {
+        let mut value = serde_json::to_value(document)?;
+        if let Value::Object(map) = &mut value {
+            map.insert("items".to_string(), Value::Array(Vec::new()));
+        }
+        Ok(value)
+    };
+    let merged_header = merge_value(
+        "",
+        Some(&header(base)?),
+        Some(&header(ours)?),
+        Some(&header(theirs)?),
+        &mut conflicts,
+    );
+
+    let mut document: OmsDocument = match merged_header {
+        Some(value) => serde_json::from_value(value)?,
+        None => ours.clone(),
+    };
+    document.items = ours.items.clone();
+
+    // Our items keep their order, new items from their side are appended
+    let mut item_ids: Vec<&str> = Vec::new();
+    for item in ours.items.iter().chain(&theirs.items).chain(&base.items) {
+        if !item_ids.contains(&item.id.as_str()) {
+            item_ids.push(&item.id);
+        }
+    }
+
+    for item_id in item_ids {
+        let to_value = |item: Option<&Item>| item.map(serde_json::to_value).transpose();
+        let merged = merge_value(
+            &format!("items[{}]", item_id),
+            to_value(base.find_item(item_id))?.as_ref(),
+            to_value(ours.find_item(item_id))?.as_ref(),
+            to_value(theirs.find_item(item_id))?.as_ref(),
+            &mut conflicts,
+        );
+
+        match merged {
+            Some(value) => {
+                let item: Item = serde_json::from_value(value)?;
+                match document.find_item_mut(item_id) {
+                    Some(existing) => *existing = item,
+                    None => document.add_item(item),
+                }
+            },
+            None => {
+                document.remove_item(item_id);
+            },
+        }
+    }
+
+    Ok(MergeResult { document, conflicts })
+}
+
+/// Three-way merge a single value, recording conflicts along the way
+fn merge_value(
+    path: &str,
+    base: Option<&Value>,
+    ours: Option<&Value>,
+    theirs: Option<&Value>,
+    conflicts: &mut Vec<MergeConflict>,
+) -> Option<Value> {
+    // Trivial cases: no change on one side, or the same change on both
+    if ours == theirs || theirs == base {
+        return ours.cloned();
+    }
+    if ours == base {
+        return theirs.cloned();
+    }
+
+    match (ours, theirs) {
+        (Some(Value::Object(our_map)), Some(Value::Object(their_map))) => {
+            let base_map = base.and_then(Value::as_object);
+            Some(Value::Object(merge_object(path, base_map, our_map, their_map, conflicts)))
+        },
+        (Some(Value::Array(our_list)), Some(Value::Array(their_list)))
+            if is_keyed(our_list) && is_keyed(their_list) =>
+        {
+            let base_list = base.and_then(Value::as_array).filter(|list| is_keyed(list));
+            Some(Value::Array(merge_keyed_array(path, base_list, our_list, their_list, conflicts)))
+        },
+        _ => {
+            let kind = match (base, ours, theirs) {
+                (None, _, _) => MergeConflictKind::BothAdded,
+                (Some(_), Some(_), Some(_)) => MergeConflictKind::BothModified,
+                _ => MergeConflictKind::DeleteModify,
+            };
+            conflicts.push(MergeConflict {
+                path: path.to_string(),
+                kind,
+                base: base.cloned(),
+                ours: ours.cloned(),
+                theirs: theirs.cloned(),
+            });
+            ours.cloned()
+        },
+    }
+}
+
+/// Merge two JSON objects field by field
+fn merge_object(
+    path: &str,
+    base: Option<&Map<String, Value>>,
+    ours: &Map<String, Value>,
+    theirs: &Map<String, Value>,
+    conflicts: &mut Vec<MergeConflict>,
+) -> Map<String, Value> {
+    let mut keys: Vec<&String> = ours.keys().collect();
+    for key in theirs.keys().chain(base.into_iter().flat_map(|b| b.keys())) {
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+
+    let mut result = Map::new();
+    for key in keys {
+        let field_path = if path.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", path, key)
+        };
+
+        let merged = merge_value(
+            &field_path,
+            base.and_then(|b| b.get(key)),
+            ours.get(key),
+            theirs.get(key),
+            conflicts,
+        );
+
+        if let Some(value) = merged {
+            result.insert(key.clone(), value);
+        }
+    }
+
+    result
+}
+
+/// Merge two arrays whose elements are objects identified by an `id` field
+fn merge_keyed_array(
+    path: &str,
+    base: Option<&Vec<Value>>,
+    ours: &[Value],
+    theirs: &[Value],
+    conflicts: &mut Vec<MergeConflict>,
+) -> Vec<Value> {
+    let base: &[Value] = base.map(Vec::as_slice).unwrap_or(&[]);
+
+    let mut ids: Vec<&str> = Vec::new();
+    for element in ours.iter().chain(theirs).chain(base) {
+        if let Some(id) = element_id(element) {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+
+    let find = |list: &'_ [Value], id: &str| -> Option<Value> {
+        list.iter().find(|element| element_id(element) == Some(id)).cloned()
+    };
+
+    ids.into_iter()
+        .filter_map(|id| {
+            merge_value(
+                &format!("{}[{}]", path, id),
+                find(base, id).as_ref(),
+                find(ours, id).as_ref(),
+                find(theirs, id).as_ref(),
+                conflicts,
+            )
+        })
+        .collect()
+}
+
+/// Whether every element of the array is an object with a string `id`
+fn is_keyed(list: &[Value]) -> bool {
+    list.iter().all(|element| element_id(element).is_some())
+}
+
+/// Get the `id` field of an array element
+fn element_id(element: &Value) -> Option<&str> {
+    element.get("id").and_then(Value::as_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::create_template;
+
+    #[test]
+    fn test_merge_non_overlapping_edits() {
+        let base = create_template("coffee-shop").unwrap();
+
+        let mut ours = base.clone();
+        ours.find_item_mut("latte").unwrap().name = "Caffe Latte".into();
+
+        let mut theirs = base.clone();
+        theirs.find_item_mut("cappuccino").unwrap().base_price = Some(4.75);
+        theirs.vendor.location_id = Some("store-42".to_string());
+
+        let result = merge_documents(&base, &ours, &theirs).unwrap();
+        assert!(result.is_clean());
+        assert_eq!(result.document.find_item("latte").unwrap().name, "Caffe Latte");
+        assert_eq!(result.document.find_item("cappuccino").unwrap().base_price, Some(4.75));
+        assert_eq!(result.document.vendor.location_id, Some("store-42".to_string()));
+    }
+
+    #[test]
+    fn test_merge_conflicting_price() {
+        let base = create_template("coffee-shop").unwrap();
+
+        let mut ours = base.clone();
+        ours.find_item_mut("latte").unwrap().base_price = Some(4.75);
+
+        let mut theirs = base.clone();
+        theirs.find_item_mut("latte").unwrap().base_price = Some(4.95);
+
+        let result = merge_documents(&base, &ours, &theirs).unwrap();
+        assert_eq!(result.conflicts.len(), 1);
+
+        let conflict = &result.conflicts[0];
+        assert_eq!(conflict.path, "items[latte].base_price");
+        assert_eq!(conflict.kind, MergeConflictKind::BothModified);
+        assert_eq!(conflict.ours, Some(serde_json::json!(4.75)));
+        assert_eq!(conflict.theirs, Some(serde_json::json!(4.95)));
+
+        // Our side wins in the merged document
+        assert_eq!(result.document.find_item("latte").unwrap().base_price, Some(4.75));
+    }
+
+    #[test]
+    fn test_merge_customization_options_by_id() {
+        let base = create_template("coffee-shop").unwrap();
+
+        // We reprice the large size
+        let mut ours = base.clone();
+        let size = &mut ours.find_item_mut("latte").unwrap().customizations.as_mut().unwrap()[0];
+        size.options.as_mut().unwrap()[2].price_adjustment = Some(0.60);
+
+        // They add a new milk option
+        let mut theirs = base.clone();
+        let milk = &mut theirs.find_item_mut("latte").unwrap().customizations.as_mut().unwrap()[1];
+        milk.options.as_mut().unwrap().push(CustomizationOption {
+            id: "soy".to_string(),
+            name: "Soy Milk".into(),
+            price_adjustment: Some(0.75),
+            nutrition_adjustments: None,
+            allergens: Some(vec!["soy".to_string()]),
+            dietary_flags: None,
+            incompatible_with: None,
+            sub_customizations: None,
+            stock: None,
+            ingredients: None,
+        });
+
+        let result = merge_documents(&base, &ours, &theirs).unwrap();
+        assert!(result.is_clean());
+
+        let latte = result.document.find_item("latte").unwrap();
+        let customizations = latte.customizations.as_ref().unwrap();
+        assert_eq!(customizations[0].options.as_ref().unwrap()[2].price_adjustment, Some(0.60));
+
+        let milk_options = customizations[1].options.as_ref().unwrap();
+        assert_eq!(milk_options.len(), 5);
+        assert_eq!(milk_options[4].id, "soy");
+    }
+
+    #[test]
+    fn test_merge_added_and_removed_items() {
+        let base = create_template("coffee-shop").unwrap();
+
+        let mut ours = base.clone();
+        ours.remove_item("cappuccino");
+
+        let mut theirs = base.clone();
+        let mut mocha = base.find_item("latte").unwrap().clone();
+        mocha.id = "mocha".to_string();
+        mocha.name = "Mocha".into();
+        theirs.add_item(mocha);
+
+        let result = merge_documents(&base, &ours, &theirs).unwrap();
+        assert!(result.is_clean());
+
+        let ids: Vec<&str> = result.document.items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(ids, vec!["latte", "mocha"]);
+    }
+
+    #[test]
+    fn test_merge_delete_modify_conflict() {
+        let base = create_template("coffee-shop").unwrap();
+
+        let mut ours = base.clone();
+        ours.remove_item("cappuccino");
+
+        let mut theirs = base.clone();
+        theirs.find_item_mut("cappuccino").unwrap().base_price = Some(4.50);
+
+        let result = merge_documents(&base, &ours, &theirs).unwrap();
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].path, "items[cappuccino]");
+        assert_eq!(result.conflicts[0].kind, MergeConflictKind::DeleteModify);
+        assert!(result.document.find_item("cappuccino").is_none());
+    }
+}
@@ -0,0 +1,159 @@
+// src/orders.rs
+//
+// Order lifecycle: the order status machine and status reports exchanged with vendors
+
+use crate::types::*;
+use crate::{OmsError, OmsResult};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+impl OrderStatus {
+    /// Statuses an order in this status may move to
+    ///
+    /// Orders move forward through `Draft`, `Submitted`, `Confirmed`, `InProgress`,
+    /// `Ready` and `Completed`, and may be cancelled until they are ready.
+    pub fn next_statuses(&self) -> &'static [OrderStatus] {
+        match self {
+            OrderStatus::Draft => &[OrderStatus::Submitted, OrderStatus::Cancelled],
+            OrderStatus::Submitted => &[OrderStatus::Confirmed, OrderStatus::Cancelled],
+            OrderStatus::Confirmed => &[OrderStatus::InProgress, OrderStatus::Cancelled],
+            OrderStatus::InProgress => &[OrderStatus::Ready, OrderStatus::Cancelled],
+            OrderStatus::Ready => &[OrderStatus::Completed],
+            OrderStatus::Completed | OrderStatus::Cancelled => &[],
+        }
+    }
+
+    /// Whether an order in this status may move to `next`
+    pub fn can_transition_to(&self, next: &OrderStatus) -> bool {
+        self.next_statuses().contains(next)
+    }
+
+    /// Whether the order has reached a final status
+    pub fn is_final(&self) -> bool {
+        self.next_statuses().is_empty()
+    }
+}
+
+impl OmsDocument {
+    /// Move the order to a new status, following the order status machine
+    ///
+    /// An order without a status is treated as a draft.
+    pub fn transition_order_status(&mut self, status: OrderStatus) -> OmsResult<()> {
+        let order = self.order.as_mut()
+            .ok_or_else(|| OmsError::MissingRequiredField("order".to_string()))?;
+        let current = order.status.clone().unwrap_or(OrderStatus::Draft);
+        if !current.can_transition_to(&status) {
+            return Err(OmsError::InvalidStatusTransition(format!("{:?} to {:?}", current, status)));
+        }
+        order.status = Some(status);
+        Ok(())
+    }
+}
+
+/// Order status reported by a vendor endpoint
+///
+/// Endpoints may answer with this object or with an OMS document whose `order`
+/// carries the status.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OrderStatusResponse {
+    /// ID of the order as known to the vendor
+    #[serde(default, alias = "id", skip_serializing_if = "Option::is_none")]
+    pub order_id: Option<String>,
+
+    /// Current status of the order
+    pub status: OrderStatus,
+
+    /// Optional message from the vendor
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+
+    /// When the order is expected to be ready
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_ready_time: Option<DateTime<Utc>>,
+}
+
+impl OrderStatusResponse {
+    /// Parse a response body, either a status object or an OMS document
+    pub fn from_json(json: &str) -> OmsResult<Self> {
+        let value: serde_json::Value = serde_json::from_str(json)?;
+        if value.get("oms_version").is_none() {
+            return Ok(serde_json::from_value(value)?);
+        }
+
+        let document = OmsDocument::from_json(json)?;
+        Self::from_document(&document)
+    }
+
+    /// Read the status of a document's order
+    pub fn from_document(document: &OmsDocument) -> OmsResult<Self> {
+        let order = document.order.as_ref()
+            .ok_or_else(|| OmsError::MissingRequiredField("order".to_string()))?;
+        Ok(Self {
+            order_id: order.id.clone(),
+            status: order.status.clone().ok_or_else(|| OmsError::MissingRequiredField("order.status".to_string()))?,
+            message: None,
+            estimated_ready_time: order.pickup_time.or(order.delivery_time),
+        })
+    }
+}
+
+/// Header carrying the idempotency key of a submitted order
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// Idempotency key of an order: its vendor and order IDs
+///
+/// Resubmitting the same order, including after a retry, sends the same key so
+/// the vendor can ignore duplicates.
+pub fn idempotency_key(document: &OmsDocument) -> OmsResult<String> {
+    let order_id = document.order.as_ref()
+        .and_then(|order| order.id.as_deref())
+        .ok_or_else(|| OmsError::MissingRequiredField("order.id".to_string()))?;
+    Ok(format!("{}/{}", document.vendor.id, order_id))
+}
+
+/// Header carrying the ID of the last order status event a reconnecting subscriber received
+pub const LAST_EVENT_ID_HEADER: &str = "Last-Event-ID";
+
+/// Change of an order's status, as delivered to subscribers
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OrderStatusEvent {
+    /// Sequence number of the event, increasing across all orders of a publisher
+    pub id: u64,
+
+    /// ID of the order
+    pub order_id: String,
+
+    /// New status of the order
+    pub status: OrderStatus,
+
+    /// When the status changed
+    pub timestamp: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{create_template, generate_order};
+
+    #[test]
+    fn test_status_machine() {
+        let mut doc = create_template("cafe").unwrap();
+        assert!(matches!(doc.transition_order_status(OrderStatus::Submitted), Err(OmsError::MissingRequiredField(_))));
+
+        generate_order(&mut doc, None).unwrap();
+        for status in [OrderStatus::Submitted, OrderStatus::Confirmed, OrderStatus::InProgress, OrderStatus::Ready] {
+            doc.transition_order_status(status).unwrap();
+        }
+        assert!(matches!(
+            doc.transition_order_status(OrderStatus::Cancelled),
+            Err(OmsError::InvalidStatusTransition(_)),
+        ));
+        doc.transition_order_status(OrderStatus::Completed).unwrap();
+        assert!(OrderStatus::Completed.is_final());
+        assert!(!OrderStatus::Draft.can_transition_to(&OrderStatus::Ready));
+
+        let response = OrderStatusResponse::from_json(&doc.to_json().unwrap()).unwrap();
+        assert_eq!(response.status, OrderStatus::Completed);
+        assert_eq!(response.order_id, doc.order.unwrap().id);
+    }
+}
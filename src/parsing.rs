@@ -0,0 +1,379 @@
+// src/parsing.rs
+//
+// Parsing OMS documents with strict, lenient and repair modes
+
+use crate::currency::is_iso_currency;
+use crate::types::*;
+use crate::{OmsError, OmsResult, OMS_VERSION};
+use chrono::Utc;
+use serde_json::{Map, Value};
+
+/// How strictly a document is parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseMode {
+    /// Report unknown fields as warnings and fail on validation errors
+    #[default]
+    Standard,
+    /// Fail on unknown fields and validation errors
+    Strict,
+    /// Report unknown fields and validation errors as warnings
+    Lenient,
+    /// Fix trivial issues, then fail on remaining validation errors
+    Repair,
+}
+
+/// Options for parsing an OMS document
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParseOptions {
+    /// Parse mode
+    pub mode: ParseMode,
+}
+
+impl ParseOptions {
+    /// Options that reject unknown fields
+    pub fn strict() -> Self {
+        Self { mode: ParseMode::Strict }
+    }
+
+    /// Options that collect warnings instead of failing validation
+    pub fn lenient() -> Self {
+        Self { mode: ParseMode::Lenient }
+    }
+
+    /// Options that fix trivial issues such as a missing `oms_version`
+    pub fn repair() -> Self {
+        Self { mode: ParseMode::Repair }
+    }
+}
+
+/// Kind of issue found while parsing
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseIssueKind {
+    /// A field that is not part of the standard was ignored
+    UnknownField,
+    /// The document failed validation (lenient mode only)
+    Invalid,
+    /// A trivial issue was fixed (repair mode only)
+    Repaired,
+}
+
+/// An issue found while parsing
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseIssue {
+    /// Kind of issue
+    pub kind: ParseIssueKind,
+
+    /// Path to the affected value (e.g. `items.0.base_price`), empty for the whole document
+    pub path: String,
+
+    /// Description of the issue
+    pub message: String,
+}
+
+/// A parsed document together with the issues found while parsing it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedDocument {
+    /// Parsed document
+    pub document: OmsDocument,
+
+    /// Issues that did not prevent parsing
+    pub issues: Vec<ParseIssue>,
+}
+
+impl ParsedDocument {
+    /// Whether the document parsed without any issues
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Get the issues of one kind
+    pub fn issues_of(&self, kind: ParseIssueKind) -> Vec<&ParseIssue> {
+        self.issues.iter().filter(|issue| issue.kind == kind).collect()
+    }
+}
+
+/// Parse an OMS document from a JSON string with the given options
+pub fn parse_oms_document_with(json: &str, options: &ParseOptions) -> OmsResult<ParsedDocument> {
+    let mut value: Value = serde_json::from_str(json)?;
+    let mut issues = Vec::new();
+
+    if options.mode == ParseMode::Repair {
+        repair_document(&mut value, &mut issues);
+    }
+
+    let mut unknown_fields = Vec::new();
+    let document: OmsDocument = serde_ignored::deserialize(&value, |path| {
+        unknown_fields.push(format_path(&path));
+    })?;
+
+    // Untagged enums (`LocalizedText`, `IngredientEntry`, `NutrientValue`) buffer their
+    // input, so `serde_ignored` never sees keys they drop. Every known field serializes
+    // back, so anything missing from the re-serialized document was ignored as well.
+    let parsed = serde_json::to_value(&document)?;
+    collect_dropped_keys(&value, &parsed, "", &mut unknown_fields);
+
+    if options.mode == ParseMode::Strict && !unknown_fields.is_empty() {
+        return Err(OmsError::UnknownFields(unknown_fields));
+    }
+    issues.extend(unknown_fields.into_iter().map(|path| ParseIssue {
+        kind: ParseIssueKind::UnknownField,
+        message: format!("unknown field '{}' was ignored", path),
+        path,
+    }));
+
+    match (options.mode, document.validate()) {
+        (_, Ok(())) => {},
+        (ParseMode::Lenient, Err(error)) => issues.push(ParseIssue {
+            kind: ParseIssueKind::Invalid,
+            path: String::new(),
+            message: error.to_string(),
+        }),
+        (_, Err(error)) => return Err(error),
+    }
+
+    Ok(ParsedDocument { document, issues })
+}
+
+/// Collect keys of `raw` (other than nulls) that are missing from `parsed`
+fn collect_dropped_keys(raw: &Value, parsed: &Value, path: &str, dropped: &mut Vec<String>) {
+    let join = |segment: &str| if path.is_empty() { segment.to_string() } else { format!("{}.{}", path, segment) };
+
+    match (raw, parsed) {
+        (Value::Object(raw), Value::Object(parsed)) => {
+            for (key, value) in raw {
+                match parsed.get(key) {
+                    Some(parsed) => collect_dropped_keys(value, parsed, &join(key), dropped),
+                    None if value.is_null() => {},
+                    None => {
+                        let path = join(key);
+                        if !dropped.contains(&path) {
+                            dropped.push(path);
+                        }
+                    },
+                }
+            }
+        },
+        (Value::Array(raw), Value::Array(parsed)) => {
+            for (index, (value, parsed)) in raw.iter().zip(parsed).enumerate() {
+                collect_dropped_keys(value, parsed, &join(&index.to_string()), dropped);
+            }
+        },
+        _ => {},
+    }
+}
+
+/// Format an ignored field path as dotted keys and indices, skipping `Option` wrappers
+fn format_path(path: &serde_ignored::Path) -> String {
+    use serde_ignored::Path;
+
+    let (parent, segment) = match path {
+        Path::Root => return String::new(),
+        Path::Seq { parent, index } => (*parent, index.to_string()),
+        Path::Map { parent, key } => (*parent, key.clone()),
+        Path::Some { parent } | Path::NewtypeStruct { parent } | Path::NewtypeVariant { parent } => {
+            return format_path(parent);
+        },
+    };
+
+    let parent = format_path(parent);
+    if parent.is_empty() {
+        segment
+    } else {
+        format!("{}.{}", parent, segment)
+    }
+}
+
+/// Fix trivial issues in a raw document, recording each fix
+fn repair_document(value: &mut Value, issues: &mut Vec<ParseIssue>) {
+    let Some(root) = value.as_object_mut() else {
+        return;
+    };
+
+    let mut repaired = |path: &str, message: String| {
+        issues.push(ParseIssue {
+            kind: ParseIssueKind::Repaired,
+            path: path.to_string(),
+            message,
+        });
+    };
+
+    if root.get("oms_version").and_then(Value::as_str).is_none_or(str::is_empty) {
+        root.insert("oms_version".to_string(), Value::String(OMS_VERSION.to_string()));
+        repaired("oms_version", format!("set missing oms_version to {}", OMS_VERSION));
+    }
+
+    let metadata = root.entry("metadata").or_insert_with(|| Value::Object(Map::new()));
+    if let Some(metadata) = metadata.as_object_mut() {
+        let defaults = [
+            ("created", Value::String(Utc::now().to_rfc3339())),
+            ("source", Value::String("open_menu_standard".to_string())),
+            ("locale", Value::String("en-US".to_string())),
+        ];
+        for (field, default) in defaults {
+            if !metadata.contains_key(field) {
+                repaired(&format!("metadata.{}", field), format!("set missing metadata.{} to {}", field, default));
+                metadata.insert(field.to_string(), default);
+            }
+        }
+    }
+
+    if let Some(items) = root.get_mut("items").and_then(Value::as_array_mut) {
+        for (index, item) in items.iter_mut().enumerate() {
+            repair_item(item, &format!("items.{}", index), &mut repaired);
+        }
+    }
+
+    if let Some(payment) = root.get_mut("order").and_then(|order| order.get_mut("payment")) {
+        repair_currency(payment, "order.payment", &mut repaired);
+        for field in ["subtotal", "tax", "tip", "total"] {
+            repair_number(payment, field, "order.payment", &mut repaired);
+        }
+    }
+}
+
+/// Fix trivial issues in a raw item and its components
+fn repair_item(item: &mut Value, path: &str, repaired: &mut dyn FnMut(&str, String)) {
+    repair_currency(item, path, repaired);
+    repair_number(item, "base_price", path, repaired);
+
+    if let Some(components) = item.get_mut("components").and_then(Value::as_array_mut) {
+        for (index, component) in components.iter_mut().enumerate() {
+            repair_item(component, &format!("{}.components.{}", path, index), repaired);
+        }
+    }
+}
+
+/// Normalize a currency code written in lower case or with surrounding spaces
+fn repair_currency(object: &mut Value, path: &str, repaired: &mut dyn FnMut(&str, String)) {
+    if let Some(Value::String(currency)) = object.get_mut("currency") {
+        let normalized = currency.trim().to_ascii_uppercase();
+        if normalized != *currency && is_iso_currency(&normalized) {
+            repaired(&format!("{}.currency", path), format!("normalized currency '{}' to {}", currency, normalized));
+            *currency = normalized;
+        }
+    }
+}
+
+/// Convert a number written as a string (e.g. `"4.50"`) into a JSON number
+fn repair_number(object: &mut Value, field: &str, path: &str, repaired: &mut dyn FnMut(&str, String)) {
+    let Some(value) = object.get_mut(field) else {
+        return;
+    };
+
+    let parsed = value.as_str()
+        .and_then(|text| text.trim().parse::<f64>().ok())
+        .and_then(serde_json::Number::from_f64);
+    if let Some(number) = parsed {
+        repaired(&format!("{}.{}", path, field), format!("converted {} to a number", value));
+        *value = Value::Number(number);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::create_template;
+
+    fn menu_with_extras() -> Value {
+        let doc = create_template("coffee-shop").unwrap();
+        let mut value = serde_json::to_value(&doc).unwrap();
+        value["vendor"]["mascot"] = Value::from("Beany");
+        value["items"][1]["customizations"][0]["colour"] = Value::from("blue");
+        value
+    }
+
+    #[test]
+    fn test_standard_mode_reports_unknown_fields() {
+        let parsed = parse_oms_document_with(&menu_with_extras().to_string(), &ParseOptions::default()).unwrap();
+        let paths: Vec<&str> = parsed.issues.iter().map(|issue| issue.path.as_str()).collect();
+        assert_eq!(paths, vec!["items.1.customizations.0.colour", "vendor.mascot"]);
+        assert!(parsed.issues.iter().all(|issue| issue.kind == ParseIssueKind::UnknownField));
+
+        // Extensions are part of the standard and never reported
+        let mut doc = create_template("coffee-shop").unwrap();
+        doc.add_extension("loyalty", serde_json::json!({"points": 10}));
+        assert!(parse_oms_document_with(&doc.to_json().unwrap(), &ParseOptions::strict()).unwrap().is_clean());
+    }
+
+    #[test]
+    fn test_parse_oms_document_uses_standard_mode() {
+        let doc = create_template("coffee-shop").unwrap();
+        assert_eq!(crate::document::parse_oms_document(&doc.to_json().unwrap()).unwrap(), doc);
+        // Unknown fields are ignored, validation errors are not
+        let parsed = crate::document::parse_oms_document(&menu_with_extras().to_string()).unwrap();
+        assert_eq!(parsed.vendor, doc.vendor);
+        let mut invalid = menu_with_extras();
+        invalid["items"][0]["customizations"][0]["default"] = Value::from("venti");
+        assert!(crate::document::parse_oms_document(&invalid.to_string()).is_err());
+    }
+
+    #[test]
+    fn test_strict_mode_rejects_unknown_fields() {
+        match parse_oms_document_with(&menu_with_extras().to_string(), &ParseOptions::strict()) {
+            Err(OmsError::UnknownFields(paths)) => {
+                assert_eq!(paths, vec!["items.1.customizations.0.colour", "vendor.mascot"]);
+            },
+            other => panic!("expected unknown fields, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_strict_mode_sees_into_untagged_enums() {
+        let mut value = serde_json::to_value(create_template("coffee-shop").unwrap()).unwrap();
+        value["items"][0]["nutrition"] = serde_json::json!({
+            "ingredients": [{"name": "Drink", "ingredients": [
+                "espresso",
+                {"ingredient_id": "milk", "quantity": {"value": 200.0, "unit": "g"}, "organic": true},
+            ]}],
+        });
+        value["items"][0]["customizations"][0]["options"][0]["nutrition_adjustments"] = serde_json::json!({
+            "calories": {"value": 10.0, "unit": "kcal", "source": "estimate"},
+        });
+        value["ingredients"] = serde_json::json!([{"id": "milk", "name": "Milk"}]);
+
+        match parse_oms_document_with(&value.to_string(), &ParseOptions::strict()) {
+            Err(OmsError::UnknownFields(paths)) => assert_eq!(paths, vec![
+                "items.0.customizations.0.options.0.nutrition_adjustments.calories.source",
+                "items.0.nutrition.ingredients.0.ingredients.1.organic",
+            ]),
+            other => panic!("expected unknown fields, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_lenient_mode_skips_validation() {
+        let mut value = menu_with_extras();
+        value["items"][0]["customizations"][0]["default"] = Value::from("venti");
+        let json = value.to_string();
+
+        assert!(parse_oms_document_with(&json, &ParseOptions::default()).is_err());
+
+        let parsed = parse_oms_document_with(&json, &ParseOptions::lenient()).unwrap();
+        assert_eq!(parsed.issues_of(ParseIssueKind::UnknownField).len(), 2);
+        let invalid = parsed.issues_of(ParseIssueKind::Invalid);
+        assert_eq!(invalid.len(), 1);
+        assert!(invalid[0].message.contains("venti"));
+    }
+
+    #[test]
+    fn test_repair_mode_fixes_trivial_issues() {
+        let mut value = serde_json::to_value(create_template("coffee-shop").unwrap()).unwrap();
+        let root = value.as_object_mut().unwrap();
+        root.remove("oms_version");
+        root["metadata"].as_object_mut().unwrap().remove("locale");
+        value["items"][0]["currency"] = Value::from(" usd");
+        value["items"][0]["base_price"] = Value::from("4.75");
+        let json = value.to_string();
+
+        assert!(parse_oms_document_with(&json, &ParseOptions::default()).is_err());
+
+        let parsed = parse_oms_document_with(&json, &ParseOptions::repair()).unwrap();
+        assert_eq!(parsed.document.oms_version, OMS_VERSION);
+        assert_eq!(parsed.document.metadata.locale, "en-US");
+        assert_eq!(parsed.document.items[0].currency, Some("USD".to_string()));
+        assert_eq!(parsed.document.items[0].base_price, Some(4.75));
+
+        let paths: Vec<&str> = parsed.issues_of(ParseIssueKind::Repaired).iter().map(|i| i.path.as_str()).collect();
+        assert_eq!(paths, vec!["oms_version", "metadata.locale", "items.0.currency", "items.0.base_price"]);
+    }
+}
@@ -0,0 +1,730 @@
+// src/render.rs
+//
+// Static HTML and Markdown menus for vendor websites and printed menus
+//
+// Rendering uses `LocalizedText::text()`; call `OmsDocument::localize` first to
+// render a menu in a specific language.
+
+use crate::currency::{format_money, DEFAULT_CURRENCY};
+use crate::labels::{escape_html, format_number};
+use crate::types::*;
+use crate::utils::slugify;
+use crate::OmsResult;
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use std::collections::HashMap;
+
+/// Stylesheet embedded in rendered HTML menus unless the theme overrides it
+pub const DEFAULT_STYLESHEET: &str = "\
+body { font-family: system-ui, sans-serif; max-width: 48rem; margin: 0 auto; padding: 1rem; color: #222; }
+h2 { border-bottom: 2px solid currentColor; }
+.menu-item { margin: 1rem 0; }
+.menu-item header { display: flex; justify-content: space-between; align-items: baseline; gap: 1rem; }
+.menu-item header > * { margin: 0; }
+.price { font-weight: bold; white-space: nowrap; }
+.badge { border: 1px solid currentColor; border-radius: 0.25rem; padding: 0 0.25rem; font-size: 0.75rem; text-decoration: none; }
+.allergens, .customizations { list-style: none; padding: 0; font-size: 0.875rem; }
+.allergens li { display: inline; margin-right: 0.5rem; }
+.unavailable { opacity: 0.6; }
+.status { font-style: italic; }
+";
+
+/// Badge shown next to an item for a dietary flag
+#[derive(Debug, Clone, PartialEq)]
+pub struct DietaryBadge {
+    /// Short text of the badge, e.g. `VG`
+    pub short: String,
+
+    /// Full name of the flag, used by screen readers and in Markdown
+    pub label: String,
+}
+
+impl DietaryBadge {
+    /// Create a badge from its short text and full label
+    pub fn new(short: &str, label: &str) -> Self {
+        Self {
+            short: short.to_string(),
+            label: label.to_string(),
+        }
+    }
+}
+
+/// Look and wording of a rendered menu
+#[derive(Debug, Clone, PartialEq)]
+pub struct MenuTheme {
+    /// Page title, defaulting to the vendor name
+    pub title: Option<String>,
+
+    /// CSS embedded in HTML menus
+    pub stylesheet: String,
+
+    /// Icons keyed by normalized allergen name
+    pub allergen_icons: HashMap<String, String>,
+
+    /// Badges keyed by normalized dietary flag
+    pub dietary_badges: HashMap<String, DietaryBadge>,
+}
+
+impl Default for MenuTheme {
+    fn default() -> Self {
+        let theme = Self {
+            title: None,
+            stylesheet: DEFAULT_STYLESHEET.to_string(),
+            allergen_icons: HashMap::new(),
+            dietary_badges: HashMap::new(),
+        };
+
+        let icons = [
+            ("dairy", "🥛"), ("milk", "🥛"), ("eggs", "🥚"), ("egg", "🥚"), ("fish", "🐟"),
+            ("shellfish", "🦐"), ("crustaceans", "🦐"), ("tree-nuts", "🌰"), ("nuts", "🌰"),
+            ("peanuts", "🥜"), ("wheat", "🌾"), ("gluten", "🌾"), ("soy", "🫘"), ("sesame", "🌱"),
+        ];
+        let badges = [
+            ("vegan", "VG", "Vegan"), ("vegetarian", "V", "Vegetarian"), ("gluten_free", "GF", "Gluten free"),
+            ("dairy_free", "DF", "Dairy free"), ("nut_free", "NF", "Nut free"), ("halal", "H", "Halal"),
+            ("kosher", "K", "Kosher"),
+        ];
+
+        let theme = icons.iter()
+            .fold(theme, |theme, (allergen, icon)| theme.with_allergen_icon(allergen, icon));
+        badges.iter()
+            .fold(theme, |theme, (flag, short, label)| theme.with_dietary_badge(flag, DietaryBadge::new(short, label)))
+    }
+}
+
+impl MenuTheme {
+    /// Set the page title
+    pub fn with_title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    /// Replace the embedded stylesheet
+    pub fn with_stylesheet(mut self, stylesheet: &str) -> Self {
+        self.stylesheet = stylesheet.to_string();
+        self
+    }
+
+    /// Set the icon shown for an allergen
+    pub fn with_allergen_icon(mut self, allergen: &str, icon: &str) -> Self {
+        self.allergen_icons.insert(theme_key(allergen), icon.to_string());
+        self
+    }
+
+    /// Set the badge shown for a dietary flag
+    pub fn with_dietary_badge(mut self, flag: &str, badge: DietaryBadge) -> Self {
+        self.dietary_badges.insert(theme_key(flag), badge);
+        self
+    }
+
+    /// Icon for an allergen, if the theme has one
+    pub fn allergen_icon(&self, allergen: &str) -> Option<&str> {
+        self.allergen_icons.get(&theme_key(allergen)).map(String::as_str)
+    }
+
+    /// Badge for a dietary flag, falling back to the flag name
+    pub fn dietary_badge(&self, flag: &str) -> DietaryBadge {
+        self.dietary_badges.get(&theme_key(flag))
+            .cloned()
+            .unwrap_or_else(|| {
+                let label = heading(flag);
+                DietaryBadge::new(&label, &label)
+            })
+    }
+}
+
+/// Options for rendering a menu
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RenderOptions {
+    /// Theme of the rendered menu
+    pub theme: MenuTheme,
+
+    /// Local time at which item availability is evaluated; availability is not enforced when unset
+    pub at: Option<NaiveDateTime>,
+
+    /// Leave out unavailable and sold out items instead of marking them
+    pub hide_unavailable: bool,
+}
+
+impl RenderOptions {
+    /// Use a theme
+    pub fn with_theme(mut self, theme: MenuTheme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Evaluate item availability at a local time
+    pub fn with_time(mut self, at: NaiveDateTime) -> Self {
+        self.at = Some(at);
+        self
+    }
+
+    /// Leave out unavailable and sold out items
+    pub fn without_unavailable(mut self) -> Self {
+        self.hide_unavailable = true;
+        self
+    }
+}
+
+impl Availability {
+    /// Check whether the item is available at a local time
+    ///
+    /// Dates are read as `YYYY-MM-DD`, times of day as `HH:MM-HH:MM` ranges (which may
+    /// wrap past midnight) and days of week as weekday names. Entries in other formats
+    /// are not enforced.
+    pub fn is_available_at(&self, at: NaiveDateTime) -> bool {
+        let date = at.date();
+        if self.start_date.as_deref().and_then(parse_date).is_some_and(|start| date < start) {
+            return false;
+        }
+        if self.end_date.as_deref().and_then(parse_date).is_some_and(|end| date > end) {
+            return false;
+        }
+
+        if let Some(days) = &self.days_of_week {
+            let days: Vec<Weekday> = days.iter().filter_map(|day| day.trim().parse().ok()).collect();
+            if !days.is_empty() && !days.contains(&at.weekday()) {
+                return false;
+            }
+        }
+
+        if let Some(times) = &self.times_of_day {
+            let ranges: Vec<(NaiveTime, NaiveTime)> = times.iter().filter_map(|range| parse_time_range(range)).collect();
+            let time = at.time();
+            let within = |&(start, end): &(NaiveTime, NaiveTime)| if start <= end {
+                time >= start && time < end
+            } else {
+                time >= start || time < end
+            };
+            if !ranges.is_empty() && !ranges.iter().any(within) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Describe the availability, e.g. `saturday, sunday · 10:00-14:00 · until 2024-12-31`
+    pub fn summary(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if let Some(days) = self.days_of_week.as_ref().filter(|days| !days.is_empty()) {
+            parts.push(days.join(", "));
+        }
+        if let Some(times) = self.times_of_day.as_ref().filter(|times| !times.is_empty()) {
+            parts.push(times.join(", "));
+        }
+        match (&self.start_date, &self.end_date) {
+            (Some(start), Some(end)) => parts.push(format!("{} to {}", start, end)),
+            (Some(start), None) => parts.push(format!("from {}", start)),
+            (None, Some(end)) => parts.push(format!("until {}", end)),
+            (None, None) => {},
+        }
+
+        if parts.is_empty() {
+            None
+        } else {
+            Some(parts.join(" · "))
+        }
+    }
+}
+
+impl OmsDocument {
+    /// Render the menu as a standalone, accessible HTML page
+    pub fn render_html(&self, options: &RenderOptions) -> OmsResult<String> {
+        let title = self.menu_title(&options.theme);
+        let sections = self.menu_sections(options)?;
+
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n");
+        html.push_str(&format!("<html lang=\"{}\">\n", escape_html(&self.metadata.locale)));
+        html.push_str("<head>\n");
+        html.push_str("  <meta charset=\"utf-8\">\n");
+        html.push_str("  <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n");
+        html.push_str(&format!("  <title>{}</title>\n", escape_html(&title)));
+        html.push_str("  <style>\n");
+        html.push_str(&options.theme.stylesheet.replace("</", "<\\/"));
+        html.push_str("  </style>\n");
+        html.push_str("</head>\n");
+        html.push_str("<body>\n");
+        html.push_str("  <main class=\"menu\">\n");
+        html.push_str(&format!("    <h1>{}</h1>\n", escape_html(&title)));
+
+        for section in &sections {
+            let category_id = format!("category-{}", slugify(section.category, "other"));
+            html.push_str(&format!("    <section class=\"menu-category\" aria-labelledby=\"{}\">\n", category_id));
+            html.push_str(&format!("      <h2 id=\"{}\">{}</h2>\n", category_id, escape_html(&heading(section.category))));
+
+            for (subcategory, items) in &section.groups {
+                match subcategory {
+                    Some(subcategory) => {
+                        let subcategory_id = format!("{}-{}", category_id, slugify(subcategory, "other"));
+                        html.push_str(&format!("      <section class=\"menu-subcategory\" aria-labelledby=\"{}\">\n", subcategory_id));
+                        html.push_str(&format!("        <h3 id=\"{}\">{}</h3>\n", subcategory_id, escape_html(&heading(subcategory))));
+                        for item in items {
+                            push_item_html(&mut html, item, 4, "        ");
+                        }
+                        html.push_str("      </section>\n");
+                    },
+                    None => {
+                        for item in items {
+                            push_item_html(&mut html, item, 3, "      ");
+                        }
+                    },
+                }
+            }
+            html.push_str("    </section>\n");
+        }
+
+        html.push_str("  </main>\n");
+        html.push_str("</body>\n");
+        html.push_str("</html>\n");
+        Ok(html)
+    }
+
+    /// Render the menu as Markdown
+    pub fn render_markdown(&self, options: &RenderOptions) -> OmsResult<String> {
+        let sections = self.menu_sections(options)?;
+
+        let mut markdown = format!("# {}\n", escape_markdown(&self.menu_title(&options.theme)));
+        for section in &sections {
+            markdown.push_str(&format!("\n## {}\n", escape_markdown(&heading(section.category))));
+            for (subcategory, items) in &section.groups {
+                let level = match subcategory {
+                    Some(subcategory) => {
+                        markdown.push_str(&format!("\n### {}\n", escape_markdown(&heading(subcategory))));
+                        "####"
+                    },
+                    None => "###",
+                };
+                for item in items {
+                    push_item_markdown(&mut markdown, item, level);
+                }
+            }
+        }
+        Ok(markdown)
+    }
+
+    /// Title of the rendered menu
+    fn menu_title(&self, theme: &MenuTheme) -> String {
+        theme.title.clone().unwrap_or_else(|| self.vendor.name.clone())
+    }
+
+    /// Group the displayed items by category and subcategory, in menu order
+    fn menu_sections(&self, options: &RenderOptions) -> OmsResult<Vec<Section<'_>>> {
+        let mut sections: Vec<Section> = Vec::new();
+
+        for item in &self.items {
+            let status = ItemStatus::of(item, options.at);
+            if options.hide_unavailable && status != ItemStatus::Available {
+                continue;
+            }
+            let view = self.item_view(item, status, &options.theme)?;
+
+            let index = match sections.iter().position(|section| section.category == item.category) {
+                Some(index) => index,
+                None => {
+                    sections.push(Section { category: &item.category, groups: Vec::new() });
+                    sections.len() - 1
+                },
+            };
+            let groups = &mut sections[index].groups;
+            let subcategory = item.subcategory.as_deref();
+            match groups.iter_mut().find(|(name, _)| *name == subcategory) {
+                Some((_, items)) => items.push(view),
+                // Items without a subcategory are listed before the subcategories
+                None if subcategory.is_none() => groups.insert(0, (None, vec![view])),
+                None => groups.push((subcategory, vec![view])),
+            }
+        }
+
+        Ok(sections)
+    }
+
+    /// Resolve the displayed text of an item
+    fn item_view(&self, item: &Item, status: ItemStatus, theme: &MenuTheme) -> OmsResult<ItemView> {
+        let currency = item.currency.as_deref()
+            .or_else(|| self.currency())
+            .unwrap_or(DEFAULT_CURRENCY);
+        let money = Money { currency, locale: &self.metadata.locale };
+
+        let nutrition = item.nutrition.as_ref();
+        let badges = nutrition.and_then(|nutrition| nutrition.dietary_flags.as_ref())
+            .map(|flags| flags.iter().map(|flag| theme.dietary_badge(flag)).collect())
+            .unwrap_or_default();
+        let allergens = nutrition.and_then(|nutrition| nutrition.allergens.as_ref())
+            .map(|allergens| allergens.iter()
+                .map(|allergen| (theme.allergen_icon(allergen).map(str::to_string), allergen.clone()))
+                .collect())
+            .unwrap_or_default();
+        let customizations = item.customizations.iter()
+            .flatten()
+            .map(|customization| customization_summary(customization, &money))
+            .collect::<OmsResult<Vec<_>>>()?;
+
+        Ok(ItemView {
+            id: format!("item-{}", slugify(&item.id, "item")),
+            name: item.name.text().to_string(),
+            description: item.description.as_ref().map(|description| description.text().to_string()),
+            price: self.format_item_price(item)?,
+            badges,
+            allergens,
+            customizations,
+            availability: item.availability.as_ref().and_then(Availability::summary),
+            status,
+        })
+    }
+}
+
+/// Items of one category, grouped by subcategory
+struct Section<'a> {
+    category: &'a str,
+    groups: Vec<(Option<&'a str>, Vec<ItemView>)>,
+}
+
+/// Whether an item can be ordered at the rendered time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ItemStatus {
+    Available,
+    Unavailable,
+    SoldOut,
+}
+
+impl ItemStatus {
+    fn of(item: &Item, at: Option<NaiveDateTime>) -> Self {
+        let sold_out = item.stock.as_ref().is_some_and(|stock| stock.status == StockStatus::SoldOut);
+        let available = match (&item.availability, at) {
+            (Some(availability), Some(at)) => availability.is_available_at(at),
+            _ => true,
+        };
+
+        if sold_out {
+            ItemStatus::SoldOut
+        } else if !available {
+            ItemStatus::Unavailable
+        } else {
+            ItemStatus::Available
+        }
+    }
+
+    fn label(&self) -> Option<&'static str> {
+        match self {
+            ItemStatus::Available => None,
+            ItemStatus::Unavailable => Some("Currently unavailable"),
+            ItemStatus::SoldOut => Some("Sold out"),
+        }
+    }
+}
+
+/// Displayed text of an item
+struct ItemView {
+    id: String,
+    name: String,
+    description: Option<String>,
+    price: Option<String>,
+    badges: Vec<DietaryBadge>,
+    allergens: Vec<(Option<String>, String)>,
+    customizations: Vec<String>,
+    availability: Option<String>,
+    status: ItemStatus,
+}
+
+/// Currency and locale used to format an item's prices
+struct Money<'a> {
+    currency: &'a str,
+    locale: &'a str,
+}
+
+impl Money<'_> {
+    /// Format a price adjustment with an explicit sign, or nothing for no adjustment
+    fn adjustment(&self, amount: Option<f64>) -> OmsResult<Option<String>> {
+        match amount {
+            Some(amount) if amount != 0.0 => {
+                let formatted = format_money(amount, self.currency, self.locale)?;
+                Ok(Some(if amount > 0.0 { format!("+{}", formatted) } else { formatted }))
+            },
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Summarize a customization, e.g. `Milk: Whole, Oat (+$0.75)`
+fn customization_summary(customization: &Customization, money: &Money) -> OmsResult<String> {
+    let options = customization.options.as_deref().unwrap_or_default();
+    let details = match customization.r#type {
+        CustomizationType::SingleSelect | CustomizationType::MultiSelect | CustomizationType::Boolean if !options.is_empty() => {
+            options.iter()
+                .map(|option| {
+                    let mut text = option.name.text().to_string();
+                    if let Some(adjustment) = money.adjustment(option.price_adjustment)? {
+                        text.push_str(&format!(" ({})", adjustment));
+                    }
+                    if option.stock.as_ref().is_some_and(|stock| stock.status == StockStatus::SoldOut) {
+                        text.push_str(" (sold out)");
+                    }
+                    Ok(text)
+                })
+                .collect::<OmsResult<Vec<_>>>()?
+                .join(", ")
+        },
+        CustomizationType::Quantity | CustomizationType::Range => {
+            let mut text = match (customization.min, customization.max) {
+                (Some(min), Some(max)) => format!("{}–{}", format_number(min), format_number(max)),
+                (Some(min), None) => format!("{} or more", format_number(min)),
+                (None, Some(max)) => format!("up to {}", format_number(max)),
+                (None, None) => "any amount".to_string(),
+            };
+            if let Some(adjustment) = money.adjustment(customization.unit_price_adjustment)? {
+                text.push_str(&format!(" ({} each)", adjustment));
+            }
+            text
+        },
+        CustomizationType::Text => "special request".to_string(),
+        _ => {
+            let mut text = "optional".to_string();
+            if let Some(adjustment) = money.adjustment(customization.unit_price_adjustment)? {
+                text.push_str(&format!(" ({})", adjustment));
+            }
+            text
+        },
+    };
+
+    let required = if customization.required { " (required)" } else { "" };
+    Ok(format!("{}: {}{}", customization.name.text(), details, required))
+}
+
+/// Append an item as an HTML article
+fn push_item_html(html: &mut String, item: &ItemView, level: u8, indent: &str) {
+    let class = if item.status == ItemStatus::Available { "menu-item" } else { "menu-item unavailable" };
+    html.push_str(&format!("{}<article class=\"{}\" id=\"{}\" aria-labelledby=\"{}-name\">\n", indent, class, item.id, item.id));
+    html.push_str(&format!("{}  <header>\n", indent));
+    html.push_str(&format!("{}    <h{} id=\"{}-name\">{}</h{}>\n", indent, level, item.id, escape_html(&item.name), level));
+    if let Some(price) = &item.price {
+        html.push_str(&format!("{}    <p class=\"price\">{}</p>\n", indent, escape_html(price)));
+    }
+    html.push_str(&format!("{}  </header>\n", indent));
+
+    if !item.badges.is_empty() {
+        let badges: Vec<String> = item.badges.iter()
+            .map(|badge| format!("<abbr class=\"badge\" title=\"{}\">{}</abbr>", escape_html(&badge.label), escape_html(&badge.short)))
+            .collect();
+        html.push_str(&format!("{}  <p class=\"badges\">{}</p>\n", indent, badges.join(" ")));
+    }
+    if let Some(description) = &item.description {
+        html.push_str(&format!("{}  <p class=\"description\">{}</p>\n", indent, escape_html(description)));
+    }
+    if !item.allergens.is_empty() {
+        html.push_str(&format!("{}  <ul class=\"allergens\" aria-label=\"Allergens\">\n", indent));
+        for (icon, allergen) in &item.allergens {
+            let icon = icon.as_ref()
+                .map(|icon| format!("<span aria-hidden=\"true\">{}</span> ", escape_html(icon)))
+                .unwrap_or_default();
+            html.push_str(&format!("{}    <li>{}{}</li>\n", indent, icon, escape_html(allergen)));
+        }
+        html.push_str(&format!("{}  </ul>\n", indent));
+    }
+    if !item.customizations.is_empty() {
+        html.push_str(&format!("{}  <ul class=\"customizations\" aria-label=\"Customizations\">\n", indent));
+        for customization in &item.customizations {
+            html.push_str(&format!("{}    <li>{}</li>\n", indent, escape_html(customization)));
+        }
+        html.push_str(&format!("{}  </ul>\n", indent));
+    }
+    if let Some(availability) = &item.availability {
+        html.push_str(&format!("{}  <p class=\"availability\">Available {}</p>\n", indent, escape_html(availability)));
+    }
+    if let Some(status) = item.status.label() {
+        html.push_str(&format!("{}  <p class=\"status\">{}</p>\n", indent, status));
+    }
+    html.push_str(&format!("{}</article>\n", indent));
+}
+
+/// Append an item as a Markdown heading and list
+fn push_item_markdown(markdown: &mut String, item: &ItemView, level: &str) {
+    let mut title = escape_markdown(&item.name);
+    if let Some(price) = &item.price {
+        title.push_str(&format!(" — {}", escape_markdown(price)));
+    }
+    markdown.push_str(&format!("\n{} {}\n\n", level, title));
+
+    if !item.badges.is_empty() {
+        let badges: Vec<String> = item.badges.iter()
+            .map(|badge| format!("`{}`", badge.label.replace('`', "'")))
+            .collect();
+        markdown.push_str(&format!("{}\n\n", badges.join(" ")));
+    }
+    if let Some(description) = &item.description {
+        markdown.push_str(&format!("{}\n\n", escape_markdown(description)));
+    }
+
+    let mut details = Vec::new();
+    if !item.allergens.is_empty() {
+        let allergens: Vec<String> = item.allergens.iter()
+            .map(|(icon, allergen)| match icon {
+                Some(icon) => format!("{} {}", icon, escape_markdown(allergen)),
+                None => escape_markdown(allergen),
+            })
+            .collect();
+        details.push(format!("Allergens: {}", allergens.join(", ")));
+    }
+    details.extend(item.customizations.iter().map(|customization| escape_markdown(customization)));
+    if let Some(availability) = &item.availability {
+        details.push(format!("Available {}", escape_markdown(availability)));
+    }
+    if let Some(status) = item.status.label() {
+        details.push(format!("*{}*", status));
+    }
+    for detail in details {
+        markdown.push_str(&format!("- {}\n", detail));
+    }
+}
+
+/// Turn a category name such as `hot_drinks` into a heading
+fn heading(name: &str) -> String {
+    let words = name.replace(['_', '-'], " ");
+    let mut chars = words.trim().chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Normalize an allergen or dietary flag for theme lookups
+fn theme_key(name: &str) -> String {
+    name.trim().to_lowercase().replace(['-', ' '], "_")
+}
+
+/// Escape characters with a meaning in Markdown
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '#' | '<' | '>' | '|' | '~') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn parse_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").ok()
+}
+
+fn parse_time_range(range: &str) -> Option<(NaiveTime, NaiveTime)> {
+    let (start, end) = range.split_once('-')?;
+    let start = NaiveTime::parse_from_str(start.trim(), "%H:%M").ok()?;
+    let end = NaiveTime::parse_from_str(end.trim(), "%H:%M").ok()?;
+    Some((start, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::create_template;
+    use serde_json::json;
+
+    fn at(date: &str, time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn menu() -> OmsDocument {
+        let mut doc = create_template("restaurant").unwrap();
+        let mut pie = doc.items[0].clone();
+        pie.id = "mud-pie".to_string();
+        pie.name = "Mud <Pie> & *Co*".into();
+        pie.category = "dessert".to_string();
+        pie.subcategory = Some("cakes".to_string());
+        pie.description = None;
+        pie.base_price = Some(6.5);
+        pie.customizations = None;
+        pie.nutrition = Some(serde_json::from_value(json!({
+            "allergens": ["dairy", "sulphites"],
+            "dietary_flags": ["vegetarian", "low_sugar"],
+        })).unwrap());
+        pie.availability = Some(Availability {
+            start_date: None,
+            end_date: Some("2024-12-31".to_string()),
+            times_of_day: Some(vec!["17:00-22:00".to_string()]),
+            days_of_week: Some(vec!["friday".to_string(), "saturday".to_string()]),
+        });
+        doc.items.push(pie);
+        doc
+    }
+
+    #[test]
+    fn test_availability() {
+        let late = Availability {
+            start_date: Some("2024-06-01".to_string()),
+            end_date: None,
+            times_of_day: Some(vec!["22:00-02:00".to_string(), "brunch".to_string()]),
+            days_of_week: None,
+        };
+        assert!(late.is_available_at(at("2024-06-01", "23:30")));
+        assert!(late.is_available_at(at("2024-06-02", "01:59")));
+        assert!(!late.is_available_at(at("2024-06-02", "12:00")));
+        assert!(!late.is_available_at(at("2024-05-31", "23:30")));
+        assert_eq!(late.summary().unwrap(), "22:00-02:00, brunch · from 2024-06-01");
+
+        let pie = menu().items[1].availability.clone().unwrap();
+        assert!(pie.is_available_at(at("2024-11-01", "18:00")));
+        assert!(!pie.is_available_at(at("2024-11-04", "18:00")));
+        assert!(!pie.is_available_at(at("2025-01-03", "18:00")));
+    }
+
+    #[test]
+    fn test_render_html() {
+        let doc = menu();
+        let html = doc.render_html(&RenderOptions::default()).unwrap();
+
+        assert!(html.starts_with("<!DOCTYPE html>\n<html lang=\"en-US\">"));
+        assert!(html.find("<h2 id=\"category-entree\">Entree</h2>").unwrap() < html.find("Dessert</h2>").unwrap());
+        assert!(html.contains("<h3 id=\"category-dessert-cakes\">Cakes</h3>"));
+        assert!(html.contains("<h4 id=\"item-mud-pie-name\">Mud &lt;Pie&gt; &amp; *Co*</h4>"));
+        assert!(html.contains(&format!("<p class=\"price\">{}</p>", doc.format_price(6.5, "USD").unwrap())));
+        assert!(html.contains("<abbr class=\"badge\" title=\"Vegetarian\">V</abbr> <abbr class=\"badge\" title=\"Low sugar\">Low sugar</abbr>"));
+        assert!(html.contains("<li><span aria-hidden=\"true\">🥛</span> dairy</li>"));
+        assert!(html.contains("<li>sulphites</li>"));
+        assert!(html.contains("<li>Cooking Preference: Rare, Medium Rare, Medium, Medium Well, Well Done (required)</li>"));
+        assert!(html.contains("Available friday, saturday · 17:00-22:00 · until 2024-12-31"));
+        assert!(!html.contains("class=\"status\""));
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let markdown = menu().render_markdown(&RenderOptions::default()).unwrap();
+
+        assert!(markdown.starts_with("# Restaurant Template\n\n## Entree\n\n### New York Strip Steak — $29.99\n"));
+        assert!(markdown.contains("\n## Dessert\n\n### Cakes\n\n#### Mud \\<Pie\\> & \\*Co\\* — $6.50\n"));
+        assert!(markdown.contains("\n`Vegetarian` `Low sugar`\n"));
+        assert!(markdown.contains("\n- Allergens: 🥛 dairy, sulphites\n"));
+    }
+
+    #[test]
+    fn test_render_respects_availability_and_theme() {
+        let mut doc = menu();
+        doc.items[0].stock = Some(StockInfo {
+            status: StockStatus::SoldOut,
+            quantity: Some(0),
+            restock_time: None,
+        });
+        let theme = MenuTheme::default()
+            .with_title("Late <Night>")
+            .with_allergen_icon("Dairy", "(D)")
+            .with_dietary_badge("vegetarian", DietaryBadge::new("VEG", "Meat free"));
+        let monday = RenderOptions::default().with_theme(theme).with_time(at("2024-11-04", "18:00"));
+
+        let html = doc.render_html(&monday).unwrap();
+        assert!(html.contains("<title>Late &lt;Night&gt;</title>"));
+        assert!(html.contains("<article class=\"menu-item unavailable\" id=\"item-steak\""));
+        assert!(html.contains("<p class=\"status\">Sold out</p>"));
+        assert!(html.contains("<p class=\"status\">Currently unavailable</p>"));
+        assert!(html.contains("<abbr class=\"badge\" title=\"Meat free\">VEG</abbr>"));
+        assert!(html.contains("<span aria-hidden=\"true\">(D)</span> dairy"));
+
+        let hidden = monday.clone().without_unavailable();
+        assert!(!doc.render_html(&hidden).unwrap().contains("<article"));
+
+        let friday = hidden.with_time(at("2024-11-01", "18:00"));
+        let markdown = doc.render_markdown(&friday).unwrap();
+        assert!(!markdown.contains("Steak"));
+        assert!(markdown.contains("#### Mud"));
+    }
+}
@@ -0,0 +1,285 @@
+// src/resolver.rs
+//
+// Resolving vendor menus to HTTP endpoints, and the on-disk cache of fetched menus
+
+use crate::types::OmsDocument;
+use crate::url::parse_oms_url;
+use crate::utils::slugify;
+use crate::{OmsError, OmsResult, OMS_FILE_EXTENSION};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+/// A vendor location whose menu can be fetched
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MenuLocation {
+    /// ID of the vendor
+    pub vendor_id: String,
+
+    /// Optional ID of the vendor location
+    pub location_id: Option<String>,
+}
+
+impl MenuLocation {
+    /// Create a menu location
+    pub fn new(vendor_id: &str, location_id: Option<&str>) -> Self {
+        Self {
+            vendor_id: vendor_id.to_string(),
+            location_id: location_id.map(str::to_string),
+        }
+    }
+
+    /// Read the menu location of an `omenu://view?v=...&l=...` URL
+    pub fn from_oms_url(url: &str) -> OmsResult<Self> {
+        let params = parse_oms_url(url)?;
+        if params.get("action").map(String::as_str) != Some("view") {
+            return Err(OmsError::InvalidOmsUrl(format!("{} is not a view URL", url)));
+        }
+        let vendor_id = params.get("v")
+            .filter(|vendor_id| !vendor_id.is_empty())
+            .ok_or_else(|| OmsError::InvalidOmsUrl(format!("{} has no vendor", url)))?;
+        Ok(Self::new(vendor_id, params.get("l").map(String::as_str).filter(|location| !location.is_empty())))
+    }
+}
+
+/// Maps a vendor location to the HTTP endpoint serving its menu
+pub trait MenuResolver {
+    /// Endpoint of the location's menu document
+    fn menu_endpoint(&self, location: &MenuLocation) -> OmsResult<Url>;
+}
+
+/// Resolver filling `{vendor}` and `{location}` placeholders of a URL template
+///
+/// For example `https://menus.example.com/{vendor}/{location}.omenu`. Vendors without
+/// a location use the vendor template if one is set; otherwise `{location}` is left empty.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UrlTemplateResolver {
+    template: String,
+    without_location: Option<String>,
+}
+
+impl UrlTemplateResolver {
+    /// Create a resolver from a URL template
+    pub fn new(template: &str) -> Self {
+        Self {
+            template: template.to_string(),
+            without_location: None,
+        }
+    }
+
+    /// Use a different template for vendors without a location
+    pub fn with_vendor_template(mut self, template: &str) -> Self {
+        self.without_location = Some(template.to_string());
+        self
+    }
+}
+
+impl MenuResolver for UrlTemplateResolver {
+    fn menu_endpoint(&self, location: &MenuLocation) -> OmsResult<Url> {
+        let template = match (&location.location_id, &self.without_location) {
+            (None, Some(template)) => template,
+            _ => &self.template,
+        };
+        let url = template
+            .replace("{vendor}", &encode_segment(&location.vendor_id))
+            .replace("{location}", &encode_segment(location.location_id.as_deref().unwrap_or_default()));
+        Url::parse(&url).map_err(|e| OmsError::UrlError(format!("Invalid menu endpoint {}: {}", url, e)))
+    }
+}
+
+/// Percent-encode an ID for use as a URL path segment or query value
+fn encode_segment(id: &str) -> String {
+    // Form encoding writes spaces as `+`, which is literal in paths; `+` itself is escaped
+    url::form_urlencoded::byte_serialize(id.as_bytes()).collect::<String>().replace('+', "%20")
+}
+
+/// A menu stored in a [`MenuCache`] with its HTTP validators
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedMenu {
+    /// The cached menu
+    pub document: OmsDocument,
+
+    /// Validators and provenance of the cached menu
+    pub entry: CacheEntry,
+}
+
+/// HTTP validators and provenance of a cached menu
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CacheEntry {
+    /// URL the menu was fetched from
+    pub url: String,
+
+    /// `ETag` of the fetched menu
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+
+    /// `Last-Modified` of the fetched menu
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+
+    /// When the menu was last fetched or revalidated
+    pub fetched: DateTime<Utc>,
+}
+
+/// Directory of fetched menus, one `.omenu` file per vendor location
+///
+/// Each menu is stored as received next to a `.omenu.cache.json` file holding its
+/// cache entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MenuCache {
+    dir: PathBuf,
+}
+
+impl MenuCache {
+    /// Use a directory as the cache, creating it when menus are stored
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Directory of the cache
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Path of the cached menu of a location
+    ///
+    /// The file name is a readable slug of the IDs followed by a hash of the exact
+    /// IDs, so locations whose IDs slugify alike (`Cafe` and `cafe`) never share a file.
+    pub fn menu_path(&self, location: &MenuLocation) -> PathBuf {
+        let mut name = slugify(&location.vendor_id, "vendor");
+        if let Some(location_id) = &location.location_id {
+            name.push_str("--");
+            name.push_str(&slugify(location_id, "location"));
+        }
+        let ids = serde_json::to_string(&(&location.vendor_id, &location.location_id))
+            .expect("strings always serialize");
+        let hash = hex::encode(&Sha256::digest(ids.as_bytes())[..8]);
+        self.dir.join(format!("{}-{}.{}", name, hash, OMS_FILE_EXTENSION))
+    }
+
+    /// Load the cached menu of a location
+    ///
+    /// Returns `None` if nothing is cached; unreadable or invalid entries are errors.
+    pub fn load(&self, location: &MenuLocation) -> OmsResult<Option<CachedMenu>> {
+        let menu_path = self.menu_path(location);
+        if !menu_path.exists() {
+            return Ok(None);
+        }
+
+        let document = OmsDocument::from_json(&fs::read_to_string(&menu_path)?)?;
+        let entry = serde_json::from_str(&fs::read_to_string(entry_path(&menu_path))?)?;
+        Ok(Some(CachedMenu { document, entry }))
+    }
+
+    /// Store a fetched menu body with its cache entry
+    pub fn store(&self, location: &MenuLocation, body: &str, entry: &CacheEntry) -> OmsResult<()> {
+        fs::create_dir_all(&self.dir)?;
+        let menu_path = self.menu_path(location);
+        write_atomically(&menu_path, body)?;
+        write_atomically(&entry_path(&menu_path), &serde_json::to_string_pretty(entry)?)
+    }
+
+    /// Update the cache entry of a stored menu, e.g. after revalidation
+    pub fn update_entry(&self, location: &MenuLocation, entry: &CacheEntry) -> OmsResult<()> {
+        write_atomically(&entry_path(&self.menu_path(location)), &serde_json::to_string_pretty(entry)?)
+    }
+
+    /// Remove the cached menu of a location, returning whether one was cached
+    pub fn remove(&self, location: &MenuLocation) -> OmsResult<bool> {
+        let menu_path = self.menu_path(location);
+        if !menu_path.exists() {
+            return Ok(false);
+        }
+        fs::remove_file(&menu_path)?;
+        let entry_path = entry_path(&menu_path);
+        if entry_path.exists() {
+            fs::remove_file(entry_path)?;
+        }
+        Ok(true)
+    }
+}
+
+fn entry_path(menu_path: &Path) -> PathBuf {
+    let mut path = menu_path.as_os_str().to_owned();
+    path.push(".cache.json");
+    PathBuf::from(path)
+}
+
+/// Write a file through a temporary file so readers never see partial contents
+fn write_atomically(path: &Path, contents: &str) -> OmsResult<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    fs::write(&temp, contents)?;
+    fs::rename(&temp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::create_template;
+
+    #[test]
+    fn test_resolve_view_url() {
+        let location = MenuLocation::from_oms_url("omenu://view?v=joe%27s+cafe&l=downtown").unwrap();
+        assert_eq!(location, MenuLocation::new("joe's cafe", Some("downtown")));
+        assert_eq!(MenuLocation::from_oms_url("omenu://view?v=cafe").unwrap().location_id, None);
+        assert!(MenuLocation::from_oms_url("omenu://order?v=cafe").is_err());
+        assert!(MenuLocation::from_oms_url("omenu://view?l=downtown").is_err());
+
+        let resolver = UrlTemplateResolver::new("https://menus.example.com/{vendor}/{location}.omenu")
+            .with_vendor_template("https://menus.example.com/{vendor}.omenu");
+        assert_eq!(
+            resolver.menu_endpoint(&location).unwrap().as_str(),
+            "https://menus.example.com/joe%27s%20cafe/downtown.omenu",
+        );
+        assert_eq!(
+            resolver.menu_endpoint(&MenuLocation::new("cafe", None)).unwrap().as_str(),
+            "https://menus.example.com/cafe.omenu",
+        );
+    }
+
+    #[test]
+    fn test_cache_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = MenuCache::new(dir.path().join("menus"));
+        let location = MenuLocation::new("Cafe", Some("Main St"));
+        let file_name = cache.menu_path(&location).file_name().unwrap().to_string_lossy().into_owned();
+        assert!(file_name.starts_with("cafe--main-st-") && file_name.ends_with(".omenu"), "{}", file_name);
+        assert_eq!(cache.menu_path(&location).parent(), Some(dir.path().join("menus").as_path()));
+        assert!(cache.load(&location).unwrap().is_none());
+
+        // IDs that slugify alike still get their own files
+        let alike = [
+            MenuLocation::new("cafe", Some("Main St")),
+            MenuLocation::new("Cafe", Some("main-st")),
+            MenuLocation::new("Cafe--Main St", None),
+            MenuLocation::new("Cafe", Some("")),
+            MenuLocation::new("Cafe", None),
+        ];
+        for other in &alike {
+            assert_ne!(cache.menu_path(other), cache.menu_path(&location), "{:?}", other);
+        }
+        assert_ne!(cache.menu_path(&alike[3]), cache.menu_path(&alike[4]));
+
+        let doc = create_template("cafe").unwrap();
+        let entry = CacheEntry {
+            url: "https://menus.example.com/cafe".to_string(),
+            etag: Some("\"v1\"".to_string()),
+            last_modified: None,
+            fetched: Utc::now(),
+        };
+        cache.store(&location, &doc.to_json().unwrap(), &entry).unwrap();
+        let cached = cache.load(&location).unwrap().unwrap();
+        assert_eq!(cached.document, doc);
+        assert_eq!(cached.entry, entry);
+
+        assert!(cache.remove(&location).unwrap());
+        assert!(cache.load(&location).unwrap().is_none());
+    }
+}
@@ -0,0 +1,149 @@
+// src/schema.rs
+//
+// JSON Schema generation and schema-based validation of raw OMS JSON
+
+use crate::types::*;
+use crate::{OmsError, OmsResult};
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::fmt;
+
+/// JSON Schema dialect used for generated schemas
+pub const JSON_SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Compiled validator for the OMS document schema
+static VALIDATOR: Lazy<jsonschema::Validator> = Lazy::new(|| {
+    jsonschema::draft202012::new(&OmsDocument::json_schema())
+        .expect("generated OMS schema is a valid draft 2020-12 schema")
+});
+
+/// A single place where a JSON value does not match the OMS schema
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaViolation {
+    /// JSON Pointer to the offending value (e.g. `/items/0/base_price`)
+    pub instance_path: String,
+
+    /// JSON Pointer to the schema keyword that failed
+    pub schema_path: String,
+
+    /// Human-readable description of the problem
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.instance_path.is_empty() { "/" } else { &self.instance_path };
+        write!(f, "{}: {}", path, self.message)
+    }
+}
+
+impl OmsDocument {
+    /// Get the JSON Schema (draft 2020-12) describing OMS documents
+    pub fn json_schema() -> Value {
+        let mut schema = serde_json::to_value(schemars::schema_for!(OmsDocument))
+            .expect("JSON schemas always serialize");
+        if let Value::Object(map) = &mut schema {
+            map.insert("$schema".to_string(), Value::String(JSON_SCHEMA_DIALECT.to_string()));
+            map.insert("title".to_string(), Value::String("OpenMenuStandard document".to_string()));
+        }
+        schema
+    }
+
+    /// Deserialize an OMS document from a raw JSON value
+    ///
+    /// The value is checked against the JSON Schema first, so structural
+    /// problems are reported with JSON Pointer paths before typed
+    /// deserialization and document validation run.
+    pub fn from_json_value(value: Value) -> OmsResult<Self> {
+        validate_json_value(&value)?;
+        let document: Self = serde_json::from_value(value)?;
+        document.validate()?;
+        Ok(document)
+    }
+}
+
+/// Collect every place where a raw JSON value does not match the OMS schema
+pub fn schema_violations(value: &Value) -> Vec<SchemaViolation> {
+    VALIDATOR.iter_errors(value)
+        .map(|error| SchemaViolation {
+            instance_path: error.instance_path.to_string(),
+            schema_path: error.schema_path.to_string(),
+            message: error.to_string(),
+        })
+        .collect()
+}
+
+/// Validate a raw JSON value against the OMS schema
+pub fn validate_json_value(value: &Value) -> OmsResult<()> {
+    let violations = schema_violations(value);
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(OmsError::SchemaViolation(violations))
+    }
+}
+
+/// Join schema violations into a single message
+pub(crate) fn describe_violations(violations: &[SchemaViolation]) -> String {
+    violations.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{create_template, generate_order};
+    use serde_json::json;
+
+    #[test]
+    fn test_schema_describes_enums() {
+        let schema = OmsDocument::json_schema();
+        assert_eq!(schema["$schema"], JSON_SCHEMA_DIALECT);
+        assert_eq!(schema["type"], "object");
+
+        let required = schema["required"].as_array().unwrap();
+        for field in ["oms_version", "metadata", "vendor", "items"] {
+            assert!(required.contains(&json!(field)), "{} should be required", field);
+        }
+
+        let customization_type = serde_json::to_string(&schema["$defs"]["CustomizationType"]).unwrap();
+        for variant in ["single_select", "multi_select", "quantity", "boolean"] {
+            assert!(customization_type.contains(variant), "missing {}", variant);
+        }
+
+        let order_status = serde_json::to_string(&schema["$defs"]["OrderStatus"]).unwrap();
+        for variant in ["draft", "submitted", "confirmed", "inprogress", "cancelled"] {
+            assert!(order_status.contains(variant), "missing {}", variant);
+        }
+    }
+
+    #[test]
+    fn test_templates_match_schema() {
+        for template in ["restaurant", "cafe", "fast-food", "coffee-shop", "pizzeria"] {
+            let mut doc = create_template(template).unwrap();
+            generate_order(&mut doc, Some("customer-1")).unwrap();
+            let value = serde_json::to_value(&doc).unwrap();
+            assert_eq!(schema_violations(&value), Vec::new(), "template {}", template);
+            assert_eq!(OmsDocument::from_json_value(value).unwrap(), doc);
+        }
+    }
+
+    #[test]
+    fn test_schema_violations_have_paths() {
+        let doc = create_template("coffee-shop").unwrap();
+        let mut value = serde_json::to_value(&doc).unwrap();
+        value["items"][0]["base_price"] = json!("4.50");
+        value["items"][1]["customizations"][0]["type"] = json!("triple_select");
+        value.as_object_mut().unwrap().remove("vendor");
+
+        let violations = schema_violations(&value);
+        let paths: Vec<&str> = violations.iter().map(|v| v.instance_path.as_str()).collect();
+        assert!(paths.contains(&"/items/0/base_price"), "{:?}", paths);
+        assert!(paths.contains(&"/items/1/customizations/0/type"), "{:?}", paths);
+        assert!(paths.contains(&""), "{:?}", paths);
+
+        match OmsDocument::from_json_value(value) {
+            Err(OmsError::SchemaViolation(found)) => assert_eq!(found, violations),
+            other => panic!("expected schema violations, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,728 @@
+// src/schema_org.rs
+//
+// Conversion between OMS documents and schema.org Menu JSON-LD
+
+use crate::currency::DEFAULT_CURRENCY;
+use crate::types::*;
+use crate::units::Unit;
+use crate::utils::slugify;
+use crate::OmsResult;
+use chrono::Utc;
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+
+/// JSON-LD context for schema.org vocabulary
+pub const SCHEMA_ORG_CONTEXT: &str = "https://schema.org";
+
+/// Category for menu items that are not inside a section
+const DEFAULT_CATEGORY: &str = "menu";
+
+/// schema.org `RestrictedDiet` values and the dietary flags they map to
+const DIETS: &[(&str, &str)] = &[
+    ("DiabeticDiet", "diabetic"),
+    ("GlutenFreeDiet", "gluten_free"),
+    ("HalalDiet", "halal"),
+    ("HinduDiet", "hindu"),
+    ("KosherDiet", "kosher"),
+    ("LowCalorieDiet", "low_calorie"),
+    ("LowFatDiet", "low_fat"),
+    ("LowLactoseDiet", "low_lactose"),
+    ("LowSaltDiet", "low_salt"),
+    ("VeganDiet", "vegan"),
+    ("VegetarianDiet", "vegetarian"),
+];
+
+/// `NutritionInformation` properties, with the OMS nutrient and detail key they map to
+const NUTRIENTS: &[(&str, &str, Option<&str>)] = &[
+    ("fatContent", "fat", None),
+    ("saturatedFatContent", "fat", Some("saturated")),
+    ("transFatContent", "fat", Some("trans")),
+    ("carbohydrateContent", "carbohydrates", None),
+    ("fiberContent", "carbohydrates", Some("fiber")),
+    ("sugarContent", "carbohydrates", Some("sugars")),
+    ("proteinContent", "protein", None),
+    ("sodiumContent", "sodium", None),
+    ("cholesterolContent", "cholesterol", None),
+];
+
+/// Result of importing schema.org JSON-LD
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaOrgImport {
+    /// Validated document built from the menu
+    pub document: OmsDocument,
+
+    /// JSON-LD properties that could not be mapped, as paths into the input
+    pub unmapped: Vec<String>,
+}
+
+/// Result of exporting a document as schema.org JSON-LD
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaOrgExport {
+    /// schema.org `Menu` JSON-LD
+    pub json_ld: Value,
+
+    /// OMS fields that have no schema.org equivalent, as paths into the document
+    pub unmapped: Vec<String>,
+}
+
+/// Import schema.org `Menu` JSON-LD, including menus nested in a `@graph` or
+/// a `FoodEstablishment`'s `hasMenu`
+///
+/// The vendor is supplied by the caller, so menu and establishment names
+/// are not reported as unmapped.
+pub fn import_schema_org(json_ld: &str, vendor: Vendor) -> OmsResult<SchemaOrgImport> {
+    let value: Value = serde_json::from_str(json_ld)?;
+    let mut importer = Importer::default();
+    importer.visit(&value, "");
+    importer.unmapped.sort();
+
+    let metadata = Metadata {
+        created: Utc::now(),
+        source: "open_menu_standard".to_string(),
+        locale: "en-US".to_string(),
+    };
+    let document = OmsDocument::new(metadata, vendor, importer.items);
+    document.validate()?;
+
+    Ok(SchemaOrgImport {
+        document,
+        unmapped: importer.unmapped,
+    })
+}
+
+impl OmsDocument {
+    /// Export the document as a schema.org `Menu`, with a section per category
+    /// and nested sections per subcategory
+    pub fn to_schema_org(&self) -> SchemaOrgExport {
+        let mut unmapped = Vec::new();
+        for (field, present) in [
+            ("order", self.order.is_some()),
+            ("extensions", self.extensions.is_some()),
+            ("ingredients", self.ingredients.is_some()),
+        ] {
+            if present {
+                unmapped.push(field.to_string());
+            }
+        }
+
+        // Sections in order of first appearance
+        let mut sections: Vec<Section> = Vec::new();
+        for item in &self.items {
+            let menu_item = export_item(item, self.currency().unwrap_or(DEFAULT_CURRENCY), &mut unmapped);
+            let section = match sections.iter().position(|section| section.name == item.category) {
+                Some(index) => &mut sections[index],
+                None => {
+                    sections.push(Section { name: &item.category, items: Vec::new(), subsections: Vec::new() });
+                    sections.last_mut().expect("section was just added")
+                },
+            };
+            match item.subcategory.as_deref() {
+                None => section.items.push(menu_item),
+                Some(subcategory) => match section.subsections.iter_mut().find(|(name, _)| *name == subcategory) {
+                    Some((_, items)) => items.push(menu_item),
+                    None => section.subsections.push((subcategory, vec![menu_item])),
+                },
+            }
+        }
+
+        let sections: Vec<Value> = sections.into_iter().map(|Section { name, items, subsections }| {
+            let mut section = json!({"@type": "MenuSection", "name": name});
+            if !items.is_empty() {
+                section["hasMenuItem"] = Value::Array(items);
+            }
+            if !subsections.is_empty() {
+                section["hasMenuSection"] = subsections.into_iter()
+                    .map(|(name, items)| json!({"@type": "MenuSection", "name": name, "hasMenuItem": items}))
+                    .collect();
+            }
+            section
+        }).collect();
+
+        SchemaOrgExport {
+            json_ld: json!({
+                "@context": SCHEMA_ORG_CONTEXT,
+                "@type": "Menu",
+                "name": self.vendor.name,
+                "hasMenuSection": sections,
+            }),
+            unmapped,
+        }
+    }
+}
+
+/// A `MenuSection` being exported: a category with its items and subcategories
+struct Section<'a> {
+    name: &'a str,
+    items: Vec<Value>,
+    subsections: Vec<(&'a str, Vec<Value>)>,
+}
+
+/// Convert an item into a schema.org `MenuItem`, recording fields that are left out
+fn export_item(item: &Item, currency: &str, unmapped: &mut Vec<String>) -> Value {
+    let path = format!("items[{}]", item.id);
+    let mut menu_item = json!({
+        "@type": "MenuItem",
+        "identifier": item.id,
+        "name": item.name.text(),
+    });
+
+    if let Some(description) = &item.description {
+        menu_item["description"] = Value::from(description.text());
+        if matches!(description, LocalizedText::Translations(_)) {
+            unmapped.push(format!("{}.description.translations", path));
+        }
+    }
+    if matches!(item.name, LocalizedText::Translations(_)) {
+        unmapped.push(format!("{}.name.translations", path));
+    }
+    if let Some(image_url) = &item.image_url {
+        menu_item["image"] = Value::from(image_url.as_str());
+    }
+    if let Some(price) = item.base_price {
+        menu_item["offers"] = json!({
+            "@type": "Offer",
+            "price": price,
+            "priceCurrency": item.currency.as_deref().unwrap_or(currency),
+        });
+    }
+    if let Some(nutrition) = &item.nutrition {
+        export_nutrition(nutrition, &mut menu_item, &format!("{}.nutrition", path), unmapped);
+    }
+
+    for (field, present) in [
+        ("vendor_id", item.vendor_id.is_some()),
+        ("customizations", item.customizations.is_some()),
+        ("selected_customizations", item.selected_customizations.is_some()),
+        ("quantity", item.quantity.is_some()),
+        ("line_id", item.line_id.is_some()),
+        ("item_note", item.item_note.is_some()),
+        ("calculated", item.calculated.is_some()),
+        ("components", item.components.is_some()),
+        ("availability", item.availability.is_some()),
+        ("popularity", item.popularity.is_some()),
+        ("stock", item.stock.is_some()),
+    ] {
+        if present {
+            unmapped.push(format!("{}.{}", path, field));
+        }
+    }
+
+    menu_item
+}
+
+/// Add `suitableForDiet` and `nutrition` to a `MenuItem`
+fn export_nutrition(nutrition: &Nutrition, menu_item: &mut Value, path: &str, unmapped: &mut Vec<String>) {
+    let mut diets = Vec::new();
+    for flag in nutrition.dietary_flags.iter().flatten() {
+        match DIETS.iter().find(|(_, f)| f == flag) {
+            Some((diet, _)) => diets.push(Value::from(format!("{}/{}", SCHEMA_ORG_CONTEXT, diet))),
+            None => unmapped.push(format!("{}.dietary_flags[{}]", path, flag)),
+        }
+    }
+    if !diets.is_empty() {
+        menu_item["suitableForDiet"] = Value::Array(diets);
+    }
+
+    let mut information = Map::new();
+    if let Some(calories) = nutrition.calories {
+        information.insert("calories".to_string(), Value::from(format!("{} calories", calories)));
+    }
+    if let Some(serving_size) = &nutrition.serving_size {
+        information.insert("servingSize".to_string(), Value::from(format_measurement(serving_size.value, &serving_size.unit)));
+    }
+
+    let simple = [
+        ("protein", nutrition.protein.as_ref()),
+        ("sodium", nutrition.sodium.as_ref()),
+        ("cholesterol", nutrition.cholesterol.as_ref()),
+    ];
+    let detailed = [("fat", nutrition.fat.as_ref()), ("carbohydrates", nutrition.carbohydrates.as_ref())];
+    for (property, nutrient, detail) in NUTRIENTS {
+        let measurement = match detail {
+            None => simple.iter().find(|(name, _)| name == nutrient)
+                .and_then(|(_, value)| value.map(|m| (m.value, m.unit.as_str())))
+                .or_else(|| detailed.iter().find(|(name, _)| name == nutrient)
+                    .and_then(|(_, value)| value.map(|n| (n.value, n.unit.as_str())))),
+            Some(detail) => detailed.iter().find(|(name, _)| name == nutrient)
+                .and_then(|(_, value)| value.and_then(|n| n.details.as_ref()?.get(*detail)))
+                .map(|value| match value {
+                    NutrientValue::Simple(m) => (m.value, m.unit.as_str()),
+                    NutrientValue::Detailed(n) => (n.value, n.unit.as_str()),
+                }),
+        };
+        if let Some((value, unit)) = measurement {
+            information.insert(property.to_string(), Value::from(format_measurement(value, unit)));
+        }
+    }
+    for (nutrient, value) in detailed {
+        for key in value.and_then(|n| n.details.as_ref()).into_iter().flat_map(HashMap::keys) {
+            if !NUTRIENTS.iter().any(|(_, n, detail)| n == &nutrient && *detail == Some(key.as_str())) {
+                unmapped.push(format!("{}.{}.details.{}", path, nutrient, key));
+            }
+        }
+    }
+    if !information.is_empty() {
+        information.insert("@type".to_string(), Value::from("NutritionInformation"));
+        menu_item["nutrition"] = Value::Object(information);
+    }
+
+    for (field, present) in [
+        ("vitamins", nutrition.vitamins.is_some()),
+        ("minerals", nutrition.minerals.is_some()),
+        ("allergens", nutrition.allergens.is_some()),
+        ("health_claims", nutrition.health_claims.is_some()),
+        ("ingredients", nutrition.ingredients.is_some()),
+        ("nutrition_standards", nutrition.nutrition_standards.is_some()),
+    ] {
+        if present {
+            unmapped.push(format!("{}.{}", path, field));
+        }
+    }
+}
+
+fn format_measurement(value: f64, unit: &str) -> String {
+    format!("{} {}", value, unit)
+}
+
+/// Walks JSON-LD collecting items and the paths of properties it cannot map
+#[derive(Default)]
+struct Importer {
+    items: Vec<Item>,
+    ids: HashSet<String>,
+    unmapped: Vec<String>,
+}
+
+impl Importer {
+    /// Find menus in a JSON-LD value
+    fn visit(&mut self, value: &Value, path: &str) {
+        match value {
+            Value::Array(values) => {
+                for (index, value) in values.iter().enumerate() {
+                    self.visit(value, &format!("{}[{}]", path, index));
+                }
+            },
+            Value::Object(object) if object.contains_key("@graph") => {
+                self.visit(&object["@graph"], &join(path, "@graph"));
+            },
+            Value::Object(object) if has_type(object, "Menu") => self.menu(object, path),
+            Value::Object(object) if object.contains_key("hasMenu") => {
+                // A FoodEstablishment: only its menus are imported
+                for key in object.keys().filter(|key| !is_keyword(key) && !["hasMenu", "name"].contains(&key.as_str())) {
+                    self.unmapped.push(join(path, key));
+                }
+                self.visit(&object["hasMenu"], &join(path, "hasMenu"));
+            },
+            _ => self.unmapped.push(if path.is_empty() { "@".to_string() } else { path.to_string() }),
+        }
+    }
+
+    fn menu(&mut self, menu: &Map<String, Value>, path: &str) {
+        for (key, value) in menu {
+            match key.as_str() {
+                "hasMenuSection" => self.sections(value, &join(path, key), None),
+                "hasMenuItem" => self.menu_items(value, &join(path, key), DEFAULT_CATEGORY, None),
+                "name" => {},
+                key if is_keyword(key) => {},
+                _ => self.unmapped.push(join(path, key)),
+            }
+        }
+    }
+
+    /// Import sections; top-level section names become categories and nested ones subcategories
+    fn sections(&mut self, value: &Value, path: &str, category: Option<&str>) {
+        for (path, section) in objects(value, path) {
+            let name = section.get("name").and_then(Value::as_str).unwrap_or(DEFAULT_CATEGORY);
+            let (category, subcategory) = match category {
+                None => (name, None),
+                Some(category) => (category, Some(name)),
+            };
+
+            for (key, value) in section {
+                match key.as_str() {
+                    "name" => {},
+                    "hasMenuItem" => self.menu_items(value, &join(&path, key), category, subcategory),
+                    "hasMenuSection" => self.sections(value, &join(&path, key), Some(category)),
+                    key if is_keyword(key) => {},
+                    _ => self.unmapped.push(join(&path, key)),
+                }
+            }
+        }
+    }
+
+    fn menu_items(&mut self, value: &Value, path: &str, category: &str, subcategory: Option<&str>) {
+        for (path, menu_item) in objects(value, path) {
+            match self.menu_item(menu_item, &path, category, subcategory) {
+                Some(item) => self.items.push(item),
+                None => self.unmapped.push(path),
+            }
+        }
+    }
+
+    /// Convert a `MenuItem`, or `None` if it has no name
+    fn menu_item(&mut self, menu_item: &Map<String, Value>, path: &str, category: &str, subcategory: Option<&str>) -> Option<Item> {
+        let name = menu_item.get("name").and_then(Value::as_str)?;
+        let identifier = menu_item.get("identifier").and_then(|id| match id {
+            Value::String(id) => Some(id.clone()),
+            Value::Number(id) => Some(id.to_string()),
+            _ => None,
+        });
+        let id = self.unique_id(identifier.unwrap_or_else(|| slugify(name, "item")));
+
+        let mut item = Item {
+            id,
+            name: name.into(),
+            category: category.to_string(),
+            vendor_id: None,
+            description: menu_item.get("description").and_then(Value::as_str).map(Into::into),
+            subcategory: subcategory.map(str::to_string),
+            image_url: None,
+            base_price: None,
+            currency: None,
+            nutrition: None,
+            customizations: None,
+            selected_customizations: None,
+            quantity: None,
+            line_id: None,
+            item_note: None,
+            calculated: None,
+            components: None,
+            availability: None,
+            popularity: None,
+            stock: None,
+        };
+        let mut dietary_flags = Vec::new();
+        let mut nutrition = None;
+
+        for (key, value) in menu_item {
+            let key_path = join(path, key);
+            match key.as_str() {
+                "name" | "description" | "identifier" => {},
+                "image" => match image_url(value) {
+                    Some(url) => item.image_url = Some(url),
+                    None => self.unmapped.push(key_path),
+                },
+                "offers" => self.offers(value, &key_path, &mut item),
+                "suitableForDiet" => {
+                    for (index, diet) in one_or_many(value).enumerate() {
+                        let flag = diet.as_str()
+                            .map(|diet| diet.rsplit(['/', ':']).next().unwrap_or(diet))
+                            .and_then(|diet| DIETS.iter().find(|(name, _)| *name == diet));
+                        match flag {
+                            Some((_, flag)) => dietary_flags.push(flag.to_string()),
+                            None => self.unmapped.push(format!("{}[{}]", key_path, index)),
+                        }
+                    }
+                },
+                "nutrition" => match value.as_object() {
+                    Some(information) => nutrition = Some(self.nutrition(information, &key_path)),
+                    None => self.unmapped.push(key_path),
+                },
+                key if is_keyword(key) => {},
+                _ => self.unmapped.push(key_path),
+            }
+        }
+
+        if nutrition.is_some() || !dietary_flags.is_empty() {
+            let mut nutrition = nutrition.unwrap_or_else(empty_nutrition);
+            if !dietary_flags.is_empty() {
+                nutrition.dietary_flags = Some(dietary_flags);
+            }
+            item.nutrition = Some(nutrition);
+        }
+        Some(item)
+    }
+
+    /// Use the first offer's price and currency
+    fn offers(&mut self, value: &Value, path: &str, item: &mut Item) {
+        for (index, (offer_path, offer)) in objects(value, path).into_iter().enumerate() {
+            if index > 0 {
+                self.unmapped.push(offer_path);
+                continue;
+            }
+            for (key, value) in offer {
+                match key.as_str() {
+                    "price" => match price(value) {
+                        Some(price) => item.base_price = Some(price),
+                        None => self.unmapped.push(join(&offer_path, key)),
+                    },
+                    "priceCurrency" => match value.as_str() {
+                        Some(currency) => item.currency = Some(currency.trim().to_ascii_uppercase()),
+                        None => self.unmapped.push(join(&offer_path, key)),
+                    },
+                    key if is_keyword(key) => {},
+                    _ => self.unmapped.push(join(&offer_path, key)),
+                }
+            }
+        }
+    }
+
+    fn nutrition(&mut self, information: &Map<String, Value>, path: &str) -> Nutrition {
+        let mut nutrition = empty_nutrition();
+        let mut details: HashMap<&str, HashMap<String, NutrientValue>> = HashMap::new();
+
+        for (key, value) in information {
+            let key_path = join(path, key);
+            let measurement = value.as_str().and_then(|text| MeasurementValue::parse(text).ok())
+                .or_else(|| value.as_f64().map(|number| MeasurementValue::new(number, Unit::Gram)));
+
+            match (key.as_str(), measurement) {
+                (key, _) if is_keyword(key) => {},
+                ("calories", Some(calories)) => match calories.convert_to(Unit::Kilocalorie) {
+                    Ok(calories) => nutrition.calories = Some(calories.value),
+                    Err(_) => self.unmapped.push(key_path),
+                },
+                ("calories", None) => match value.as_f64() {
+                    Some(calories) => nutrition.calories = Some(calories),
+                    None => self.unmapped.push(key_path),
+                },
+                ("servingSize", Some(serving_size)) => nutrition.serving_size = Some(serving_size),
+                (key, Some(measurement)) => match NUTRIENTS.iter().find(|(property, _, _)| *property == key) {
+                    Some((_, nutrient, Some(detail))) => {
+                        details.entry(nutrient).or_default().insert(detail.to_string(), NutrientValue::Simple(measurement));
+                    },
+                    Some((_, "fat", None)) => nutrition.fat = Some(with_details(measurement)),
+                    Some((_, "carbohydrates", None)) => nutrition.carbohydrates = Some(with_details(measurement)),
+                    Some((_, "protein", None)) => nutrition.protein = Some(measurement),
+                    Some((_, "sodium", None)) => nutrition.sodium = Some(measurement),
+                    Some((_, "cholesterol", None)) => nutrition.cholesterol = Some(measurement),
+                    _ => self.unmapped.push(key_path),
+                },
+                _ => self.unmapped.push(key_path),
+            }
+        }
+
+        for (nutrient, nutrient_details) in details {
+            let parent = match nutrient {
+                "fat" => nutrition.fat.as_mut(),
+                _ => nutrition.carbohydrates.as_mut(),
+            };
+            match parent {
+                Some(parent) => parent.details = Some(nutrient_details),
+                None => {
+                    for detail in nutrient_details.keys() {
+                        let property = NUTRIENTS.iter()
+                            .find(|(_, n, d)| *n == nutrient && *d == Some(detail.as_str()))
+                            .map(|(property, _, _)| *property)
+                            .unwrap_or(nutrient);
+                        self.unmapped.push(join(path, property));
+                    }
+                },
+            }
+        }
+
+        nutrition
+    }
+
+    /// Make an item id unique by appending a counter
+    fn unique_id(&mut self, id: String) -> String {
+        let mut candidate = id.clone();
+        let mut counter = 2;
+        while !self.ids.insert(candidate.clone()) {
+            candidate = format!("{}-{}", id, counter);
+            counter += 1;
+        }
+        candidate
+    }
+}
+
+fn empty_nutrition() -> Nutrition {
+    Nutrition {
+        serving_size: None,
+        calories: None,
+        protein: None,
+        fat: None,
+        carbohydrates: None,
+        sodium: None,
+        cholesterol: None,
+        vitamins: None,
+        minerals: None,
+        allergens: None,
+        dietary_flags: None,
+        health_claims: None,
+        ingredients: None,
+        nutrition_standards: None,
+    }
+}
+
+fn with_details(measurement: MeasurementValue) -> NutrientWithDetails {
+    NutrientWithDetails {
+        value: measurement.value,
+        unit: measurement.unit,
+        details: None,
+    }
+}
+
+/// Whether a JSON-LD node has a schema.org type, written plainly or as a prefixed IRI
+fn has_type(object: &Map<String, Value>, name: &str) -> bool {
+    object.get("@type").map(one_or_many).into_iter().flatten()
+        .filter_map(Value::as_str)
+        .any(|t| t.rsplit(['/', ':']).next() == Some(name))
+}
+
+/// JSON-LD keywords such as `@type` and `@id`, which carry no menu data
+fn is_keyword(key: &str) -> bool {
+    key.starts_with('@')
+}
+
+/// Iterate over a value that may be a single value or an array
+fn one_or_many(value: &Value) -> impl Iterator<Item = &Value> {
+    match value {
+        Value::Array(values) => values.iter(),
+        value => std::slice::from_ref(value).iter(),
+    }
+}
+
+/// Objects in a value that may be a single object or an array, with their paths
+fn objects<'a>(value: &'a Value, path: &str) -> Vec<(String, &'a Map<String, Value>)> {
+    match value {
+        Value::Array(values) => values.iter().enumerate()
+            .filter_map(|(index, value)| Some((format!("{}[{}]", path, index), value.as_object()?)))
+            .collect(),
+        Value::Object(object) => vec![(path.to_string(), object)],
+        _ => Vec::new(),
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+fn image_url(value: &Value) -> Option<String> {
+    match value {
+        Value::String(url) => Some(url.clone()),
+        Value::Array(values) => values.first().and_then(image_url),
+        Value::Object(image) => image.get("url").and_then(Value::as_str).map(str::to_string),
+        _ => None,
+    }
+}
+
+/// Read a price written as a number or a string such as `"$4.50"`
+fn price(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(price) => price.as_f64(),
+        Value::String(price) => price.trim()
+            .trim_start_matches(|c: char| !c.is_ascii_digit())
+            .parse()
+            .ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::create_template;
+
+    const RESTAURANT_JSON_LD: &str = r#"{
+        "@context": "https://schema.org",
+        "@type": "Restaurant",
+        "name": "Green Bowl",
+        "servesCuisine": "Salads",
+        "hasMenu": {
+            "@type": "Menu",
+            "name": "Lunch",
+            "hasMenuSection": [{
+                "@type": "MenuSection",
+                "name": "Bowls",
+                "description": "Served all day",
+                "hasMenuItem": [{
+                    "@type": "MenuItem",
+                    "name": "Harvest Bowl",
+                    "description": "Kale, squash and quinoa",
+                    "image": {"@type": "ImageObject", "url": "https://example.com/harvest.jpg"},
+                    "offers": [
+                        {"@type": "Offer", "price": "$12.50", "priceCurrency": "usd", "availability": "InStock"},
+                        {"@type": "Offer", "price": 9.5, "priceCurrency": "USD"}
+                    ],
+                    "suitableForDiet": ["https://schema.org/VeganDiet", "schema:GlutenFreeDiet", "PaleoDiet"],
+                    "nutrition": {
+                        "@type": "NutritionInformation",
+                        "calories": "540 calories",
+                        "fatContent": "18 g",
+                        "saturatedFatContent": "3 g",
+                        "sugarContent": "9 g",
+                        "proteinContent": "14 g",
+                        "sodiumContent": "620 mg"
+                    },
+                    "menuAddOn": {"@type": "MenuSection", "name": "Extras"}
+                }],
+                "hasMenuSection": {
+                    "@type": "MenuSection",
+                    "name": "Seasonal",
+                    "hasMenuItem": {"@type": "MenuItem", "name": "Harvest Bowl", "offers": {"price": 13}}
+                }
+            }]
+        }
+    }"#;
+
+    #[test]
+    fn test_import_maps_menu_items() {
+        let vendor = create_template("restaurant").unwrap().vendor;
+        let imported = import_schema_org(RESTAURANT_JSON_LD, vendor).unwrap();
+        let items = &imported.document.items;
+        assert_eq!(items.len(), 2);
+
+        let bowl = &items[0];
+        assert_eq!(bowl.id, "harvest-bowl");
+        assert_eq!(bowl.category, "Bowls");
+        assert_eq!(bowl.base_price, Some(12.5));
+        assert_eq!(bowl.currency.as_deref(), Some("USD"));
+        assert_eq!(bowl.image_url.as_deref(), Some("https://example.com/harvest.jpg"));
+
+        let nutrition = bowl.nutrition.as_ref().unwrap();
+        assert_eq!(nutrition.calories, Some(540.0));
+        assert_eq!(nutrition.dietary_flags, Some(vec!["vegan".to_string(), "gluten_free".to_string()]));
+        let fat = nutrition.fat.as_ref().unwrap();
+        assert_eq!((fat.value, fat.unit.as_str()), (18.0, "g"));
+        assert!(fat.details.as_ref().unwrap().contains_key("saturated"));
+        assert_eq!(nutrition.sodium.as_ref().unwrap().unit, "mg");
+
+        let seasonal = &items[1];
+        assert_eq!(seasonal.id, "harvest-bowl-2");
+        assert_eq!((seasonal.category.as_str(), seasonal.subcategory.as_deref()), ("Bowls", Some("Seasonal")));
+        assert_eq!(seasonal.base_price, Some(13.0));
+
+        let menu = "hasMenu.hasMenuSection[0]";
+        let item = format!("{}.hasMenuItem[0]", menu);
+        // Sugar is reported because there is no carbohydrate total to attach it to
+        assert_eq!(imported.unmapped, vec![
+            format!("{}.description", menu),
+            format!("{}.menuAddOn", item),
+            format!("{}.nutrition.sugarContent", item),
+            format!("{}.offers[0].availability", item),
+            format!("{}.offers[1]", item),
+            format!("{}.suitableForDiet[2]", item),
+            "servesCuisine".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn test_export_round_trips_through_import() {
+        let doc = create_template("coffee-shop").unwrap();
+        let export = doc.to_schema_org();
+        assert_eq!(export.json_ld["@type"], "Menu");
+        assert_eq!(export.json_ld["hasMenuSection"][0]["hasMenuItem"][0]["offers"]["priceCurrency"], "USD");
+        assert!(export.unmapped.iter().any(|path| path.ends_with(".customizations")));
+
+        let imported = import_schema_org(&export.json_ld.to_string(), doc.vendor.clone()).unwrap();
+        assert_eq!(imported.unmapped, Vec::<String>::new());
+        let mut originals: Vec<&Item> = doc.items.iter().collect();
+        originals.sort_by_key(|item| &item.category);
+        let mut items: Vec<&Item> = imported.document.items.iter().collect();
+        items.sort_by_key(|item| &item.category);
+        for (item, original) in items.iter().zip(originals) {
+            assert_eq!(item.id, original.id);
+            assert_eq!(item.base_price, original.base_price);
+            assert_eq!(item.category, original.category);
+            assert_eq!(item.subcategory, original.subcategory);
+            assert_eq!(
+                item.nutrition.as_ref().and_then(|n| n.calories),
+                original.nutrition.as_ref().and_then(|n| n.calories),
+            );
+        }
+    }
+}
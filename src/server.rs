@@ -0,0 +1,550 @@
+// src/server.rs
+//
+// Embeddable HTTP server serving menus and accepting orders
+//
+// Routes:
+// - `GET /vendors/{vendor_id}/menu` serves a vendor's menu document
+// - `POST /orders` validates and accepts an order document
+// - `GET /orders/{order_id}` reports an order's status
+// - `POST /orders/{order_id}/status` moves an order along the status machine
+// - `GET /orders/{order_id}/events` streams status events as Server-Sent Events
+// - `GET /orders/{order_id}/ws` streams status events over a WebSocket
+
+use crate::events::{forward_to_websocket, sse_response, OrderEvents};
+use crate::orders::{idempotency_key, OrderStatusResponse, IDEMPOTENCY_KEY_HEADER, LAST_EVENT_ID_HEADER};
+use crate::types::*;
+use crate::validation::validate_document;
+use crate::{OmsError, OmsResult, OMS_MIME_TYPE};
+use axum::extract::ws::WebSocketUpgrade;
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chrono::Utc;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// Storage of menus and orders behind the server
+pub trait OmsStore: Send + Sync + 'static {
+    /// Menu of a vendor
+    fn menu(&self, vendor_id: &str) -> OmsResult<Option<OmsDocument>>;
+
+    /// Add or replace the menu of the document's vendor
+    fn put_menu(&self, menu: OmsDocument) -> OmsResult<()>;
+
+    /// Order document by order ID
+    fn order(&self, order_id: &str) -> OmsResult<Option<OmsDocument>>;
+
+    /// Store a new order, or return the order already stored under the idempotency key
+    ///
+    /// Keys have the form `vendor_id/order_id`, so each vendor has its own namespace.
+    /// `inserted` is called with the new order before any change to it can be made.
+    fn insert_order<F>(&self, idempotency_key: &str, order_id: &str, document: OmsDocument, inserted: F) -> OmsResult<Option<OmsDocument>>
+    where
+        F: FnOnce(&OmsDocument);
+
+    /// Change a stored order atomically, returning the changed order
+    ///
+    /// Returns `None` if no order has the ID. If `update` fails, the stored order
+    /// is left unchanged and the error is returned. Changes to one order run one
+    /// at a time, so side effects of `update` happen in the order changes are stored.
+    fn update_order_with<F>(&self, order_id: &str, update: F) -> OmsResult<Option<OmsDocument>>
+    where
+        F: FnOnce(&mut OmsDocument) -> OmsResult<()>;
+}
+
+/// Store keeping menus and orders in memory
+#[derive(Debug, Default)]
+pub struct InMemoryStore {
+    menus: RwLock<HashMap<String, OmsDocument>>,
+    orders: RwLock<StoredOrders>,
+}
+
+/// Orders of an [`InMemoryStore`], kept under one lock
+#[derive(Debug, Default)]
+struct StoredOrders {
+    /// Order documents by order ID
+    documents: HashMap<String, OmsDocument>,
+
+    /// Order IDs by idempotency key
+    keys: HashMap<String, String>,
+}
+
+impl InMemoryStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a menu to the store
+    pub fn with_menu(self, menu: OmsDocument) -> Self {
+        self.menus.write().expect("menu lock poisoned").insert(menu.vendor.id.clone(), menu);
+        self
+    }
+}
+
+impl OmsStore for InMemoryStore {
+    fn menu(&self, vendor_id: &str) -> OmsResult<Option<OmsDocument>> {
+        Ok(self.menus.read().map_err(poisoned)?.get(vendor_id).cloned())
+    }
+
+    fn put_menu(&self, menu: OmsDocument) -> OmsResult<()> {
+        self.menus.write().map_err(poisoned)?.insert(menu.vendor.id.clone(), menu);
+        Ok(())
+    }
+
+    fn order(&self, order_id: &str) -> OmsResult<Option<OmsDocument>> {
+        Ok(self.orders.read().map_err(poisoned)?.documents.get(order_id).cloned())
+    }
+
+    fn insert_order<F>(&self, idempotency_key: &str, order_id: &str, document: OmsDocument, inserted: F) -> OmsResult<Option<OmsDocument>>
+    where
+        F: FnOnce(&OmsDocument),
+    {
+        let mut orders = self.orders.write().map_err(poisoned)?;
+        if let Some(existing) = orders.keys.get(idempotency_key).and_then(|id| orders.documents.get(id)) {
+            return Ok(Some(existing.clone()));
+        }
+        inserted(&document);
+        orders.keys.insert(idempotency_key.to_string(), order_id.to_string());
+        orders.documents.insert(order_id.to_string(), document);
+        Ok(None)
+    }
+
+    fn update_order_with<F>(&self, order_id: &str, update: F) -> OmsResult<Option<OmsDocument>>
+    where
+        F: FnOnce(&mut OmsDocument) -> OmsResult<()>,
+    {
+        let mut orders = self.orders.write().map_err(poisoned)?;
+        let Some(stored) = orders.documents.get_mut(order_id) else {
+            return Ok(None);
+        };
+        let mut document = stored.clone();
+        update(&mut document)?;
+        *stored = document.clone();
+        Ok(Some(document))
+    }
+}
+
+fn poisoned<T>(_: std::sync::PoisonError<T>) -> OmsError {
+    OmsError::Unknown("store lock poisoned".to_string())
+}
+
+/// Build the OMS router on top of a store
+pub fn router<S: OmsStore>(store: Arc<S>) -> Router {
+    router_with_events(store, OrderEvents::new())
+}
+
+/// Build the OMS router, publishing order status changes to `events`
+///
+/// Share the hub with the rest of the application to publish status changes made
+/// outside the router.
+pub fn router_with_events<S: OmsStore>(store: Arc<S>, events: OrderEvents) -> Router {
+    Router::new()
+        .route("/vendors/{vendor_id}/menu", get(get_menu::<S>))
+        .route("/orders", post(post_order::<S>))
+        .route("/orders/{order_id}", get(get_order::<S>))
+        .route("/orders/{order_id}/status", post(post_order_status::<S>))
+        .route("/orders/{order_id}/events", get(get_order_events::<S>))
+        .route("/orders/{order_id}/ws", get(get_order_websocket::<S>))
+        .with_state(AppState { store, events })
+}
+
+/// Shared state of the router's handlers
+struct AppState<S> {
+    store: Arc<S>,
+    events: OrderEvents,
+}
+
+impl<S> Clone for AppState<S> {
+    fn clone(&self) -> Self {
+        Self {
+            store: Arc::clone(&self.store),
+            events: self.events.clone(),
+        }
+    }
+}
+
+/// Body of a status change request
+#[derive(Debug, Deserialize)]
+struct StatusChange {
+    status: OrderStatus,
+}
+
+/// Query of an event subscription
+#[derive(Debug, Deserialize)]
+struct ResumeQuery {
+    last_event_id: Option<u64>,
+}
+
+async fn get_menu<S: OmsStore>(State(state): State<AppState<S>>, Path(vendor_id): Path<String>) -> Result<Response, ApiError> {
+    let menu = state.store.menu(&vendor_id)?
+        .ok_or_else(|| ApiError::not_found(format!("vendor {} has no menu", vendor_id)))?;
+    Ok(([(header::CONTENT_TYPE, OMS_MIME_TYPE)], menu.to_json()?).into_response())
+}
+
+/// Accept an order: validate it, assign its ID and submit it
+///
+/// The server assigns every order its ID. Resubmissions are recognised by the
+/// `Idempotency-Key` header, or by the document's vendor and `order.id` without
+/// one, and return the stored order's status.
+async fn post_order<S: OmsStore>(State(state): State<AppState<S>>, headers: HeaderMap, body: String) -> Result<Response, ApiError> {
+    let mut document: OmsDocument = serde_json::from_str(&body)?;
+    validate_document(&document)?;
+    if state.store.menu(&document.vendor.id)?.is_none() {
+        return Err(ApiError::not_found(format!("unknown vendor {}", document.vendor.id)));
+    }
+
+    let order_id = format!("order-{}", uuid::Uuid::new_v4());
+    let order = document.order.as_mut()
+        .ok_or_else(|| OmsError::MissingRequiredField("order".to_string()))?;
+    order.id.get_or_insert_with(|| order_id.clone());
+    let key = match headers.get(IDEMPOTENCY_KEY_HEADER) {
+        Some(value) => vendor_idempotency_key(value, &document.vendor.id)?,
+        None => idempotency_key(&document)?,
+    };
+
+    // A client's own order ID only identifies resubmissions
+    let order = document.order.as_mut().expect("order checked above");
+    order.id = Some(order_id.clone());
+    order.created.get_or_insert_with(Utc::now);
+    if order.status != Some(OrderStatus::Submitted) {
+        document.transition_order_status(OrderStatus::Submitted)?;
+    }
+
+    // Publishing while the store holds the order keeps events in the order of changes
+    let publish = |_: &OmsDocument| {
+        state.events.publish(&order_id, OrderStatus::Submitted);
+    };
+    if let Some(existing) = state.store.insert_order(&key, &order_id, document.clone(), publish)? {
+        return Ok(Json(OrderStatusResponse::from_document(&existing)?).into_response());
+    }
+    let location = format!("/orders/{}", order_id);
+    Ok((
+        StatusCode::CREATED,
+        [(header::LOCATION, location)],
+        Json(OrderStatusResponse::from_document(&document)?),
+    ).into_response())
+}
+
+async fn get_order<S: OmsStore>(State(state): State<AppState<S>>, Path(order_id): Path<String>) -> Result<Json<OrderStatusResponse>, ApiError> {
+    let document = find_order(&state, &order_id)?;
+    Ok(Json(OrderStatusResponse::from_document(&document)?))
+}
+
+async fn post_order_status<S: OmsStore>(
+    State(state): State<AppState<S>>,
+    Path(order_id): Path<String>,
+    Json(change): Json<StatusChange>,
+) -> Result<Json<OrderStatusResponse>, ApiError> {
+    // Publishing while the store holds the order keeps events in the order of changes,
+    // and only transitions that succeed are published
+    let document = state.store
+        .update_order_with(&order_id, |document| {
+            document.transition_order_status(change.status.clone())?;
+            state.events.publish(&order_id, change.status);
+            Ok(())
+        })?
+        .ok_or_else(|| ApiError::not_found(format!("order {} not found", order_id)))?;
+    Ok(Json(OrderStatusResponse::from_document(&document)?))
+}
+
+async fn get_order_events<S: OmsStore>(
+    State(state): State<AppState<S>>,
+    Path(order_id): Path<String>,
+    Query(query): Query<ResumeQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    find_order(&state, &order_id)?;
+    let last_event_id = headers.get(LAST_EVENT_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+        .or(query.last_event_id);
+    Ok(sse_response(state.events.subscribe(&order_id, last_event_id)).into_response())
+}
+
+async fn get_order_websocket<S: OmsStore>(
+    State(state): State<AppState<S>>,
+    Path(order_id): Path<String>,
+    Query(query): Query<ResumeQuery>,
+    upgrade: WebSocketUpgrade,
+) -> Result<Response, ApiError> {
+    find_order(&state, &order_id)?;
+    let subscription = state.events.subscribe(&order_id, query.last_event_id);
+    Ok(upgrade.on_upgrade(move |socket| forward_to_websocket(socket, subscription)))
+}
+
+/// Read an `Idempotency-Key` header, which must belong to the order's vendor
+fn vendor_idempotency_key(value: &HeaderValue, vendor_id: &str) -> OmsResult<String> {
+    let key = value.to_str().unwrap_or_default();
+    match key.strip_prefix(vendor_id).and_then(|rest| rest.strip_prefix('/')) {
+        Some(order_key) if !order_key.is_empty() => Ok(key.to_string()),
+        _ => Err(OmsError::InvalidFieldValue(format!(
+            "{} must have the form {}/<order id>",
+            IDEMPOTENCY_KEY_HEADER, vendor_id
+        ))),
+    }
+}
+
+fn find_order<S: OmsStore>(state: &AppState<S>, order_id: &str) -> Result<OmsDocument, ApiError> {
+    state.store.order(order_id)?
+        .ok_or_else(|| ApiError::not_found(format!("order {} not found", order_id)))
+}
+
+/// Error response with a JSON `{"error": ...}` body
+#[derive(Debug)]
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn not_found(message: String) -> Self {
+        Self { status: StatusCode::NOT_FOUND, message }
+    }
+}
+
+impl From<OmsError> for ApiError {
+    fn from(error: OmsError) -> Self {
+        let status = match &error {
+            OmsError::SerializationError(_) => StatusCode::BAD_REQUEST,
+            OmsError::InvalidStatusTransition(_) => StatusCode::CONFLICT,
+            OmsError::IoError(_) | OmsError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::UNPROCESSABLE_ENTITY,
+        };
+        Self { status, message: error.to_string() }
+    }
+}
+
+impl From<serde_json::Error> for ApiError {
+    fn from(error: serde_json::Error) -> Self {
+        OmsError::from(error).into()
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(serde_json::json!({ "error": self.message }))).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{create_template, generate_order};
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        router(Arc::new(InMemoryStore::new().with_menu(create_template("cafe").unwrap())))
+    }
+
+    fn order() -> OmsDocument {
+        let mut doc = create_template("cafe").unwrap();
+        generate_order(&mut doc, Some("customer-1")).unwrap();
+        doc.order.as_mut().unwrap().id = None;
+        doc
+    }
+
+    async fn send(app: &Router, method: &str, uri: &str, body: Option<String>) -> (StatusCode, Response) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, OMS_MIME_TYPE)
+            .body(body.map(Body::from).unwrap_or_else(Body::empty))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        (response.status(), response)
+    }
+
+    async fn json(response: Response) -> serde_json::Value {
+        serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_get_menu() {
+        let app = app();
+        let (status, response) = send(&app, "GET", "/vendors/cafe-template/menu", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], OMS_MIME_TYPE);
+        let menu = OmsDocument::from_json(&json(response).await.to_string()).unwrap();
+        assert_eq!(menu.vendor.id, "cafe-template");
+
+        let (status, _) = send(&app, "GET", "/vendors/nobody/menu", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_order_lifecycle() {
+        let app = app();
+        let (status, response) = send(&app, "POST", "/orders", Some(order().to_json().unwrap())).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let location = response.headers()[header::LOCATION].to_str().unwrap().to_string();
+        let created = json(response).await;
+        let order_id = created["order_id"].as_str().unwrap().to_string();
+        assert_eq!(location, format!("/orders/{}", order_id));
+        assert_eq!(created["status"], "submitted");
+
+        let status_uri = format!("{}/status", location);
+        let (status, response) = send(&app, "POST", &status_uri, Some(r#"{"status":"confirmed"}"#.to_string())).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json(response).await["status"], "confirmed");
+        let (status, response) = send(&app, "POST", &status_uri, Some(r#"{"status":"completed"}"#.to_string())).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert!(json(response).await["error"].as_str().unwrap().contains("Confirmed to Completed"));
+
+        let (status, response) = send(&app, "GET", &location, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json(response).await["status"], "confirmed");
+
+        // Resubmitting the same order ID is idempotent
+        let mut resubmitted = order();
+        resubmitted.order.as_mut().unwrap().id = Some(order_id);
+        let (status, response) = send(&app, "POST", "/orders", Some(resubmitted.to_json().unwrap())).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json(response).await["status"], "confirmed");
+    }
+
+    #[tokio::test]
+    async fn test_orders_are_scoped_by_vendor() {
+        let store = InMemoryStore::new()
+            .with_menu(create_template("cafe").unwrap())
+            .with_menu(create_template("pizzeria").unwrap());
+        let app = router(Arc::new(store));
+        let submit = |template: &str, key: Option<&str>| {
+            let mut doc = create_template(template).unwrap();
+            generate_order(&mut doc, Some("customer-1")).unwrap();
+            doc.order.as_mut().unwrap().id = Some("order-7".to_string());
+            let mut request = Request::builder()
+                .method("POST")
+                .uri("/orders")
+                .header(header::CONTENT_TYPE, OMS_MIME_TYPE);
+            if let Some(key) = key {
+                request = request.header(IDEMPOTENCY_KEY_HEADER, key);
+            }
+            let request = request.body(Body::from(doc.to_json().unwrap())).unwrap();
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                (response.status(), json(response).await)
+            }
+        };
+
+        // The same client order ID at two vendors gives two orders
+        let (status, cafe) = submit("cafe", None).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let (status, pizzeria) = submit("pizzeria", None).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_ne!(cafe["order_id"], pizzeria["order_id"]);
+        let (status, again) = submit("cafe", None).await;
+        assert_eq!((status, &again["order_id"]), (StatusCode::OK, &cafe["order_id"]));
+
+        // The idempotency key header takes precedence over the document
+        let (status, keyed) = submit("cafe", Some("cafe-template/checkout-1")).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_ne!(keyed["order_id"], cafe["order_id"]);
+        let (status, again) = submit("cafe", Some("cafe-template/checkout-1")).await;
+        assert_eq!((status, &again["order_id"]), (StatusCode::OK, &keyed["order_id"]));
+
+        let (status, _) = submit("cafe", Some("pizzeria-template/checkout-1")).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_status_changes_publish_once() {
+        let events = OrderEvents::new();
+        let store = Arc::new(InMemoryStore::new().with_menu(create_template("cafe").unwrap()));
+        let app = router_with_events(store, events.clone());
+        let (_, response) = send(&app, "POST", "/orders", Some(order().to_json().unwrap())).await;
+        let order_id = json(response).await["order_id"].as_str().unwrap().to_string();
+
+        let status_uri = format!("/orders/{}/status", order_id);
+        let requests = (0..8).map(|_| {
+            let app = app.clone();
+            let status_uri = status_uri.clone();
+            tokio::spawn(async move {
+                send(&app, "POST", &status_uri, Some(r#"{"status":"confirmed"}"#.to_string())).await.0
+            })
+        });
+        let mut statuses = Vec::new();
+        for request in requests.collect::<Vec<_>>() {
+            statuses.push(request.await.unwrap());
+        }
+        assert_eq!(statuses.iter().filter(|status| **status == StatusCode::OK).count(), 1);
+        assert_eq!(statuses.iter().filter(|status| **status == StatusCode::CONFLICT).count(), 7);
+
+        let (_, response) = send(&app, "POST", &status_uri, Some(r#"{"status":"cancelled"}"#.to_string())).await;
+        assert_eq!(json(response).await["status"], "cancelled");
+        let mut subscription = events.subscribe(&order_id, None);
+        let published: Vec<OrderStatus> = vec![
+            subscription.next().await.unwrap().status,
+            subscription.next().await.unwrap().status,
+            subscription.next().await.unwrap().status,
+        ];
+        assert_eq!(published, vec![OrderStatus::Submitted, OrderStatus::Confirmed, OrderStatus::Cancelled]);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_events_follow_the_order_of_status_changes() {
+        let events = OrderEvents::new();
+        let store = Arc::new(InMemoryStore::new().with_menu(create_template("cafe").unwrap()));
+        let app = router_with_events(store, events.clone());
+        let change = |order_id: &str, status: &str| {
+            let app = app.clone();
+            let uri = format!("/orders/{}/status", order_id);
+            let body = format!(r#"{{"status":"{}"}}"#, status);
+            tokio::spawn(async move { send(&app, "POST", &uri, Some(body)).await.0 })
+        };
+
+        let mut order_ids = Vec::new();
+        for _ in 0..20 {
+            let (_, response) = send(&app, "POST", "/orders", Some(order().to_json().unwrap())).await;
+            let order_id = json(response).await["order_id"].as_str().unwrap().to_string();
+            assert_eq!(change(&order_id, "confirmed").await.unwrap(), StatusCode::OK);
+            // Starting and cancelling race each other; either way the events must replay legally
+            let start = change(&order_id, "inprogress");
+            let cancel = change(&order_id, "cancelled");
+            start.await.unwrap();
+            assert_eq!(cancel.await.unwrap(), StatusCode::OK);
+            order_ids.push(order_id);
+        }
+
+        for order_id in order_ids {
+            let mut subscription = events.subscribe(&order_id, None);
+            let mut statuses = vec![subscription.next().await.unwrap().status];
+            while !statuses.last().unwrap().is_final() {
+                let status = subscription.next().await.unwrap().status;
+                assert!(statuses.last().unwrap().can_transition_to(&status), "{:?} then {:?}", statuses, status);
+                statuses.push(status);
+            }
+            assert_eq!(statuses[0], OrderStatus::Submitted);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invalid_orders_are_rejected() {
+        let app = app();
+        let (status, _) = send(&app, "POST", "/orders", Some("{".to_string())).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let menu_only = create_template("cafe").unwrap();
+        let (status, _) = send(&app, "POST", "/orders", Some(menu_only.to_json().unwrap())).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let mut invalid = order();
+        invalid.items[0].currency = Some("ZZZ".to_string());
+        let (status, _) = send(&app, "POST", "/orders", Some(invalid.to_json().unwrap())).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let mut elsewhere = order();
+        elsewhere.vendor.id = "elsewhere".to_string();
+        let (status, _) = send(&app, "POST", "/orders", Some(elsewhere.to_json().unwrap())).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, _) = send(&app, "GET", "/orders/missing", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,679 @@
+// src/spreadsheet.rs
+//
+// CSV import and export of menu items for spreadsheet-based editing
+
+use crate::currency::require_currency;
+use crate::types::*;
+use crate::validation::validate_item;
+use crate::{OmsError, OmsResult};
+use chrono::Utc;
+use csv::StringRecord;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Columns of the items sheet, in export order
+pub const ITEM_COLUMNS: &[&str] = &[
+    "id", "name", "category", "subcategory", "base_price", "currency", "description", "allergens",
+];
+
+/// Columns of the options sheet, in export order
+///
+/// Each row is one option of a customization, linked to its item by
+/// `item_id`. Customization columns repeat on every row of the
+/// customization; the first row wins. A customization without options
+/// (boolean, quantity, range, text) is a single row with no `option_id`
+/// whose `default` column holds its default value.
+pub const OPTION_COLUMNS: &[&str] = &[
+    "item_id", "customization_id", "customization_name", "type", "required", "min_selections",
+    "max_selections", "min", "max", "step", "unit_price_adjustment", "option_id", "option_name",
+    "price_adjustment", "default",
+];
+
+/// Separator for lists such as allergens within a single cell
+const LIST_SEPARATOR: char = ';';
+
+/// The two CSV sheets describing a menu
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CsvSheets {
+    /// Items sheet, one item per row
+    pub items: String,
+
+    /// Options sheet, one customization option per row (may be empty)
+    pub options: String,
+}
+
+/// Which sheet a row belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvSheet {
+    Items,
+    Options,
+}
+
+impl fmt::Display for CsvSheet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvSheet::Items => write!(f, "items"),
+            CsvSheet::Options => write!(f, "options"),
+        }
+    }
+}
+
+/// A problem with a single spreadsheet row
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvRowError {
+    /// Sheet containing the row
+    pub sheet: CsvSheet,
+
+    /// Line number in the sheet, counting the header as line 1
+    pub line: u64,
+
+    /// Column with the problem, if it is limited to one cell
+    pub column: Option<String>,
+
+    /// Description of the problem
+    pub message: String,
+}
+
+impl fmt::Display for CsvRowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} line {}", self.sheet, self.line)?;
+        if let Some(column) = &self.column {
+            write!(f, " ({})", column)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Result of importing a menu from CSV
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvImport {
+    /// Document built from every row that could be imported
+    pub document: OmsDocument,
+
+    /// Rows that were skipped and why
+    pub errors: Vec<CsvRowError>,
+
+    /// Why the document failed validation as a whole, if it did
+    pub document_error: Option<String>,
+}
+
+impl CsvImport {
+    /// Whether every row was imported and the document is valid
+    pub fn is_clean(&self) -> bool {
+        self.errors.is_empty() && self.document_error.is_none()
+    }
+}
+
+impl OmsDocument {
+    /// Export the document's items and customization options as CSV sheets
+    ///
+    /// Only the columns in `ITEM_COLUMNS` and `OPTION_COLUMNS` are written;
+    /// localized text is exported in its default language, and nested
+    /// sub-customizations, components and nutrition other than allergens
+    /// are left out.
+    pub fn to_csv(&self) -> OmsResult<CsvSheets> {
+        let mut items = csv::Writer::from_writer(Vec::new());
+        items.write_record(ITEM_COLUMNS)?;
+        let mut options = csv::Writer::from_writer(Vec::new());
+        options.write_record(OPTION_COLUMNS)?;
+
+        for item in &self.items {
+            let allergens = item.nutrition.as_ref()
+                .and_then(|nutrition| nutrition.allergens.as_ref())
+                .map(|allergens| allergens.join(&format!("{} ", LIST_SEPARATOR)));
+            items.write_record([
+                item.id.clone(),
+                item.name.text().to_string(),
+                item.category.clone(),
+                item.subcategory.clone().unwrap_or_default(),
+                format_optional(item.base_price),
+                item.currency.clone().unwrap_or_default(),
+                item.description.as_ref().map(|d| d.text().to_string()).unwrap_or_default(),
+                allergens.unwrap_or_default(),
+            ])?;
+
+            for customization in item.customizations.iter().flatten() {
+                let columns = [
+                    item.id.clone(),
+                    customization.id.clone(),
+                    customization.name.text().to_string(),
+                    customization.r#type.to_string(),
+                    customization.required.to_string(),
+                    format_optional(customization.min_selections),
+                    format_optional(customization.max_selections),
+                    format_optional(customization.min),
+                    format_optional(customization.max),
+                    format_optional(customization.step),
+                    format_optional(customization.unit_price_adjustment),
+                ];
+
+                match customization.options.as_deref() {
+                    Some(customization_options) if !customization_options.is_empty() => {
+                        for option in customization_options {
+                            let is_default = match &customization.default {
+                                CustomizationDefault::String(id) => *id == option.id,
+                                CustomizationDefault::StringArray(ids) => ids.contains(&option.id),
+                                _ => false,
+                            };
+                            options.write_record(columns.iter().cloned().chain([
+                                option.id.clone(),
+                                option.name.text().to_string(),
+                                format_optional(option.price_adjustment),
+                                if is_default { "true".to_string() } else { String::new() },
+                            ]))?;
+                        }
+                    },
+                    _ => {
+                        let default = match &customization.default {
+                            CustomizationDefault::String(value) => value.clone(),
+                            CustomizationDefault::StringArray(values) => values.join(&LIST_SEPARATOR.to_string()),
+                            CustomizationDefault::Number(value) => value.to_string(),
+                            CustomizationDefault::Boolean(value) => value.to_string(),
+                        };
+                        options.write_record(columns.iter().cloned().chain([
+                            String::new(),
+                            String::new(),
+                            String::new(),
+                            default,
+                        ]))?;
+                    },
+                }
+            }
+        }
+
+        Ok(CsvSheets {
+            items: into_string(items)?,
+            options: into_string(options)?,
+        })
+    }
+}
+
+/// Import a menu from CSV sheets
+///
+/// Rows that cannot be imported are skipped and reported, as is an options
+/// sheet without a required column. When the remaining items do not form a
+/// valid document, the report is still returned with the reason in
+/// `document_error`. An items sheet without a required column is an error.
+pub fn import_csv(vendor: Vendor, sheets: &CsvSheets) -> OmsResult<CsvImport> {
+    let mut errors = Vec::new();
+    let mut items: Vec<(u64, Item)> = Vec::new();
+
+    let mut reader = csv_reader(&sheets.items);
+    let columns = header_columns(&mut reader, CsvSheet::Items, &["id", "name", "category"])?;
+    for (index, record) in reader.records().enumerate() {
+        let row = match record {
+            Ok(record) => Row::new(CsvSheet::Items, record, &columns),
+            Err(error) => {
+                errors.push(csv_row_error(CsvSheet::Items, index, &error));
+                continue;
+            },
+        };
+        match item_from_row(&row) {
+            Ok(item) if items.iter().any(|(_, existing)| existing.id == item.id) => {
+                errors.push(row.error("id", format!("duplicate item id '{}'", item.id)));
+            },
+            Ok(item) => items.push((row.line, item)),
+            Err(error) => errors.push(error),
+        }
+    }
+
+    if !sheets.options.trim().is_empty() {
+        if let Err(error) = import_options(&sheets.options, &mut items, &mut errors) {
+            errors.push(CsvRowError {
+                sheet: CsvSheet::Options,
+                line: 1,
+                column: None,
+                message: error.to_string(),
+            });
+        }
+    }
+
+    // Items that are invalid on their own are dropped rather than failing the import
+    items.retain(|(line, item)| match validate_item(item, None) {
+        Ok(()) => true,
+        Err(error) => {
+            errors.push(CsvRowError {
+                sheet: CsvSheet::Items,
+                line: *line,
+                column: None,
+                message: error.to_string(),
+            });
+            false
+        },
+    });
+    errors.sort_by_key(|error| (error.sheet == CsvSheet::Options, error.line));
+
+    let metadata = Metadata {
+        created: Utc::now(),
+        source: "open_menu_standard".to_string(),
+        locale: "en-US".to_string(),
+    };
+    let document = OmsDocument::new(metadata, vendor, items.into_iter().map(|(_, item)| item).collect());
+    let document_error = document.validate().err().map(|error| error.to_string());
+
+    Ok(CsvImport { document, errors, document_error })
+}
+
+/// Add the customizations in the options sheet to the imported items
+fn import_options(sheet: &str, items: &mut [(u64, Item)], errors: &mut Vec<CsvRowError>) -> OmsResult<()> {
+    let mut reader = csv_reader(sheet);
+    let columns = header_columns(&mut reader, CsvSheet::Options, &["item_id", "customization_id", "type"])?;
+
+    // Default option ids and raw default values, keyed by item index and customization id
+    let mut default_options: HashMap<(usize, String), Vec<String>> = HashMap::new();
+    let mut raw_defaults: HashMap<(usize, String), (u64, String)> = HashMap::new();
+
+    for (index, record) in reader.records().enumerate() {
+        let row = match record {
+            Ok(record) => Row::new(CsvSheet::Options, record, &columns),
+            Err(error) => {
+                errors.push(csv_row_error(CsvSheet::Options, index, &error));
+                continue;
+            },
+        };
+        if let Err(error) = option_from_row(&row, items, &mut default_options, &mut raw_defaults) {
+            errors.push(error);
+        }
+    }
+
+    for (item_index, (_, item)) in items.iter_mut().enumerate() {
+        for customization in item.customizations.iter_mut().flatten() {
+            let key = (item_index, customization.id.clone());
+            let raw = raw_defaults.get(&key);
+            let defaults = default_options.remove(&key).unwrap_or_default();
+            match customization_default(customization, defaults, raw.map(|(_, value)| value.as_str())) {
+                Ok(default) => customization.default = default,
+                Err(message) => errors.push(CsvRowError {
+                    sheet: CsvSheet::Options,
+                    line: raw.map(|(line, _)| *line).unwrap_or_default(),
+                    column: Some("default".to_string()),
+                    message,
+                }),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Build an item from a row of the items sheet
+fn item_from_row(row: &Row) -> Result<Item, CsvRowError> {
+    let currency = match row.get("currency") {
+        Some(currency) => {
+            let currency = currency.to_ascii_uppercase();
+            require_currency(&currency).map_err(|error| row.error("currency", error.to_string()))?;
+            Some(currency)
+        },
+        None => None,
+    };
+
+    let allergens = row.get("allergens").map(split_list);
+
+    Ok(Item {
+        id: row.required("id")?.to_string(),
+        name: row.required("name")?.into(),
+        category: row.required("category")?.to_string(),
+        vendor_id: None,
+        description: row.get("description").map(Into::into),
+        subcategory: row.get("subcategory").map(str::to_string),
+        image_url: None,
+        base_price: row.number("base_price")?,
+        currency,
+        nutrition: allergens.map(|allergens| Nutrition {
+            serving_size: None,
+            calories: None,
+            protein: None,
+            fat: None,
+            carbohydrates: None,
+            sodium: None,
+            cholesterol: None,
+            vitamins: None,
+            minerals: None,
+            allergens: Some(allergens),
+            dietary_flags: None,
+            health_claims: None,
+            ingredients: None,
+            nutrition_standards: None,
+        }),
+        customizations: None,
+        selected_customizations: None,
+        quantity: None,
+        line_id: None,
+        item_note: None,
+        calculated: None,
+        components: None,
+        availability: None,
+        popularity: None,
+        stock: None,
+    })
+}
+
+/// Add the customization option in a row of the options sheet to its item
+fn option_from_row(
+    row: &Row,
+    items: &mut [(u64, Item)],
+    default_options: &mut HashMap<(usize, String), Vec<String>>,
+    raw_defaults: &mut HashMap<(usize, String), (u64, String)>,
+) -> Result<(), CsvRowError> {
+    let item_id = row.required("item_id")?;
+    let item_index = items.iter().position(|(_, item)| item.id == item_id)
+        .ok_or_else(|| row.error("item_id", format!("no item with id '{}'", item_id)))?;
+    let customization_id = row.required("customization_id")?;
+    let customization_type = CustomizationType::from_str(row.required("type")?)
+        .map_err(|error| row.error("type", error.to_string()))?;
+
+    let customizations = items[item_index].1.customizations.get_or_insert_with(Vec::new);
+    let position = match customizations.iter().position(|c| c.id == customization_id) {
+        Some(position) if customizations[position].r#type != customization_type => {
+            return Err(row.error("type", format!(
+                "customization '{}' is already {}",
+                customization_id, customizations[position].r#type
+            )));
+        },
+        Some(position) => position,
+        None => {
+            customizations.push(Customization {
+                id: customization_id.to_string(),
+                name: row.get("customization_name").unwrap_or(customization_id).into(),
+                r#type: customization_type,
+                required: row.flag("required")?,
+                default: CustomizationDefault::Boolean(false),
+                min_selections: row.integer("min_selections")?,
+                max_selections: row.integer("max_selections")?,
+                min: row.number("min")?,
+                max: row.number("max")?,
+                step: row.number("step")?,
+                unit_price_adjustment: row.number("unit_price_adjustment")?,
+                unit_nutrition_adjustments: None,
+                options: None,
+                visible_when: None,
+            });
+            customizations.len() - 1
+        },
+    };
+    let customization = &mut customizations[position];
+    let key = (item_index, customization.id.clone());
+
+    let Some(option_id) = row.get("option_id") else {
+        if let Some(default) = row.get("default") {
+            raw_defaults.insert(key, (row.line, default.to_string()));
+        }
+        return Ok(());
+    };
+
+    let options = customization.options.get_or_insert_with(Vec::new);
+    if options.iter().any(|option| option.id == option_id) {
+        return Err(row.error("option_id", format!("duplicate option id '{}'", option_id)));
+    }
+    options.push(CustomizationOption {
+        id: option_id.to_string(),
+        name: row.get("option_name").unwrap_or(option_id).into(),
+        price_adjustment: row.number("price_adjustment")?,
+        nutrition_adjustments: None,
+        allergens: None,
+        dietary_flags: None,
+        incompatible_with: None,
+        sub_customizations: None,
+        stock: None,
+        ingredients: None,
+    });
+    if row.flag("default")? {
+        default_options.entry(key).or_default().push(option_id.to_string());
+    }
+    Ok(())
+}
+
+/// Work out a customization's default from its default options or raw default cell
+fn customization_default(
+    customization: &Customization,
+    default_options: Vec<String>,
+    raw: Option<&str>,
+) -> Result<CustomizationDefault, String> {
+    let first_option = customization.options.iter().flatten().next().map(|option| option.id.clone());
+    Ok(match customization.r#type {
+        CustomizationType::SingleSelect => CustomizationDefault::String(
+            default_options.into_iter().next()
+                .or_else(|| raw.map(str::to_string))
+                .or(first_option)
+                .unwrap_or_default(),
+        ),
+        CustomizationType::MultiSelect if default_options.is_empty() => {
+            CustomizationDefault::StringArray(raw.map(split_list).unwrap_or_default())
+        },
+        CustomizationType::MultiSelect => CustomizationDefault::StringArray(default_options),
+        CustomizationType::Boolean => CustomizationDefault::Boolean(match raw {
+            Some(raw) => parse_flag(raw).ok_or_else(|| format!("'{}' is not true or false", raw))?,
+            None => false,
+        }),
+        CustomizationType::Quantity | CustomizationType::Range => CustomizationDefault::Number(match raw {
+            Some(raw) => raw.parse().map_err(|_| format!("'{}' is not a number", raw))?,
+            None => customization.min.unwrap_or(0.0),
+        }),
+        CustomizationType::Text => CustomizationDefault::String(raw.unwrap_or_default().to_string()),
+    })
+}
+
+/// A data row with cells looked up by column name
+struct Row<'a> {
+    sheet: CsvSheet,
+    line: u64,
+    record: StringRecord,
+    columns: &'a HashMap<String, usize>,
+}
+
+impl<'a> Row<'a> {
+    fn new(sheet: CsvSheet, record: StringRecord, columns: &'a HashMap<String, usize>) -> Self {
+        let line = record.position().map(|position| position.line()).unwrap_or_default();
+        Self { sheet, line, record, columns }
+    }
+
+    fn error(&self, column: &str, message: String) -> CsvRowError {
+        CsvRowError {
+            sheet: self.sheet,
+            line: self.line,
+            column: Some(column.to_string()),
+            message,
+        }
+    }
+
+    /// Non-empty cell in a column
+    fn get(&self, column: &str) -> Option<&str> {
+        self.columns.get(column)
+            .and_then(|&index| self.record.get(index))
+            .filter(|value| !value.is_empty())
+    }
+
+    fn required(&self, column: &str) -> Result<&str, CsvRowError> {
+        self.get(column).ok_or_else(|| self.error(column, "value is required".to_string()))
+    }
+
+    fn number(&self, column: &str) -> Result<Option<f64>, CsvRowError> {
+        self.get(column)
+            .map(|value| value.parse().map_err(|_| self.error(column, format!("'{}' is not a number", value))))
+            .transpose()
+    }
+
+    fn integer(&self, column: &str) -> Result<Option<u32>, CsvRowError> {
+        self.get(column)
+            .map(|value| value.parse().map_err(|_| self.error(column, format!("'{}' is not a whole number", value))))
+            .transpose()
+    }
+
+    fn flag(&self, column: &str) -> Result<bool, CsvRowError> {
+        match self.get(column) {
+            Some(value) => parse_flag(value).ok_or_else(|| self.error(column, format!("'{}' is not true or false", value))),
+            None => Ok(false),
+        }
+    }
+}
+
+fn csv_reader(sheet: &str) -> csv::Reader<&[u8]> {
+    csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(sheet.as_bytes())
+}
+
+/// Map column names to positions, checking that required columns are present
+fn header_columns(reader: &mut csv::Reader<&[u8]>, sheet: CsvSheet, required: &[&str]) -> OmsResult<HashMap<String, usize>> {
+    let columns: HashMap<String, usize> = reader.headers()?.iter()
+        .enumerate()
+        .map(|(index, name)| (name.to_ascii_lowercase(), index))
+        .collect();
+
+    for column in required {
+        if !columns.contains_key(*column) {
+            return Err(OmsError::MissingRequiredField(format!("{} column in {} sheet", column, sheet)));
+        }
+    }
+    Ok(columns)
+}
+
+fn csv_row_error(sheet: CsvSheet, index: usize, error: &csv::Error) -> CsvRowError {
+    CsvRowError {
+        sheet,
+        line: error.position().map(|position| position.line()).unwrap_or(index as u64 + 2),
+        column: None,
+        message: error.to_string(),
+    }
+}
+
+fn into_string(writer: csv::Writer<Vec<u8>>) -> OmsResult<String> {
+    let bytes = writer.into_inner().map_err(|error| OmsError::IoError(error.into_error()))?;
+    String::from_utf8(bytes).map_err(|error| OmsError::InvalidFieldValue(error.to_string()))
+}
+
+fn format_optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value.split(LIST_SEPARATOR)
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn parse_flag(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "y" | "1" | "x" => Some(true),
+        "false" | "no" | "n" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::create_template;
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let doc = create_template("coffee-shop").unwrap();
+        let sheets = doc.to_csv().unwrap();
+        assert!(sheets.items.starts_with("id,name,category,subcategory,base_price"));
+
+        let imported = import_csv(doc.vendor.clone(), &sheets).unwrap();
+        assert!(imported.is_clean());
+        assert_eq!(imported.document.items.len(), doc.items.len());
+
+        for (item, original) in imported.document.items.iter().zip(&doc.items) {
+            assert_eq!(item.id, original.id);
+            assert_eq!(item.name.text(), original.name.text());
+            assert_eq!(item.base_price, original.base_price);
+            assert_eq!(item.currency, original.currency);
+
+            let customizations = item.customizations.iter().flatten();
+            let originals = original.customizations.iter().flatten();
+            for (customization, original) in customizations.zip(originals) {
+                assert_eq!(customization.id, original.id);
+                assert_eq!(customization.r#type, original.r#type);
+                assert_eq!(customization.default, original.default);
+                assert_eq!(customization.max, original.max);
+                let ids = |c: &Customization| c.options.iter().flatten().map(|o| o.id.clone()).collect::<Vec<_>>();
+                assert_eq!(ids(customization), ids(original));
+            }
+        }
+    }
+
+    #[test]
+    fn test_import_reports_row_errors() {
+        let sheets = CsvSheets {
+            items: [
+                "id,name,category,base_price,currency,allergens,notes",
+                "latte,Latte,coffee,4.50,usd,milk; soy,popular",
+                "mocha,Mocha,coffee,four,USD,,",
+                ",Nameless,coffee,3.00,USD,,",
+                "latte,Latte again,coffee,5.00,USD,,",
+                "tea,Tea,tea,2.00,XYZ,,",
+                "scone,Scone,bakery,3.25,USD,\"wheat; eggs\",",
+            ].join("\n"),
+            options: [
+                "item_id,customization_id,customization_name,type,option_id,option_name,price_adjustment,default",
+                "latte,milk,Milk,single_select,whole,Whole,,",
+                "latte,milk,Milk,single_select,oat,Oat,0.75,yes",
+                "latte,size,Size,single_select,small,Small,,",
+                "latte,size,Size,multi_select,large,Large,0.5,",
+                "latte,extra_shot,Extra shot,boolean,,,,false",
+                "cookie,chips,Chips,boolean,,,,",
+                "scone,warm,Warm,boolean,,,,maybe",
+            ].join("\n"),
+        };
+
+        let imported = import_csv(create_template("cafe").unwrap().vendor, &sheets).unwrap();
+        let report: Vec<String> = imported.errors.iter().map(ToString::to_string).collect();
+        assert_eq!(report, vec![
+            "items line 3 (base_price): 'four' is not a number",
+            "items line 4 (id): value is required",
+            "items line 5 (id): duplicate item id 'latte'",
+            "items line 6 (currency): Invalid currency: XYZ",
+            "options line 5 (type): customization 'size' is already single_select",
+            "options line 7 (item_id): no item with id 'cookie'",
+            "options line 8 (default): 'maybe' is not true or false",
+        ]);
+
+        assert_eq!(imported.document_error, None);
+        let ids: Vec<&str> = imported.document.items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(ids, vec!["latte", "scone"]);
+
+        let latte = &imported.document.items[0];
+        assert_eq!(latte.currency.as_deref(), Some("USD"));
+        assert_eq!(latte.nutrition.as_ref().unwrap().allergens, Some(vec!["milk".to_string(), "soy".to_string()]));
+        let customizations = latte.customizations.as_ref().unwrap();
+        assert_eq!(customizations[0].default, CustomizationDefault::String("oat".to_string()));
+        assert_eq!(customizations[1].default, CustomizationDefault::String("small".to_string()));
+        assert_eq!(customizations[2].default, CustomizationDefault::Boolean(false));
+    }
+
+    #[test]
+    fn test_import_requires_columns() {
+        let sheets = CsvSheets {
+            items: "id,title\nlatte,Latte".to_string(),
+            options: String::new(),
+        };
+        let vendor = create_template("cafe").unwrap().vendor;
+        assert!(matches!(import_csv(vendor, &sheets), Err(OmsError::MissingRequiredField(_))));
+    }
+
+    #[test]
+    fn test_import_reports_invalid_documents() {
+        let sheets = CsvSheets {
+            items: "id,name,category,base_price
+latte,Latte,coffee,free".to_string(),
+            options: "item_id,type
+latte,boolean".to_string(),
+        };
+
+        let imported = import_csv(create_template("cafe").unwrap().vendor, &sheets).unwrap();
+        let report: Vec<String> = imported.errors.iter().map(ToString::to_string).collect();
+        assert_eq!(report, vec![
+            "items line 2 (base_price): 'free' is not a number",
+            "options line 1: Missing required field: customization_id column in options sheet",
+        ]);
+        assert!(imported.document.items.is_empty());
+        assert!(imported.document_error.is_some());
+        assert!(!imported.is_clean());
+    }
+}
@@ -0,0 +1,505 @@
+// src/streaming.rs
+//
+// Streaming reader for large OMS documents that yields items one at a time
+
+use crate::types::*;
+use crate::validation::validate_item;
+use crate::{OmsError, OmsResult};
+use serde::de::DeserializeOwned;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::Path;
+
+/// Reads the `items` array of an OMS document one item at a time
+///
+/// Only the raw JSON of the current item is held in memory. `oms_version`,
+/// `metadata` and `vendor` are available as soon as the stream is opened;
+/// when they come after `items`, the items are skipped over once to find
+/// them. The ingredient catalog, which documents written by this crate put
+/// before `items`, is used to check ingredient references; a catalog that
+/// only follows the items cannot be, so their references go unchecked.
+/// `order` and `extensions`, which follow `items`, are available once every
+/// item has been read.
+pub struct ItemStream<R> {
+    scanner: JsonScanner<R>,
+    state: StreamState,
+    validate: bool,
+    oms_version: String,
+    metadata: Metadata,
+    vendor: Vendor,
+    sections: Sections,
+}
+
+/// Position of the stream within the document
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum StreamState {
+    FirstItem,
+    NextItem,
+    Finished,
+}
+
+/// Top-level values other than the required header
+#[derive(Default)]
+struct Sections {
+    oms_version: Option<String>,
+    metadata: Option<Metadata>,
+    vendor: Option<Vendor>,
+    ingredients: Option<Vec<Ingredient>>,
+    order: Option<Order>,
+    extensions: Option<Extensions>,
+}
+
+impl ItemStream<BufReader<File>> {
+    /// Open a document file for streaming
+    pub fn open(path: &Path) -> OmsResult<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: BufRead + Seek> ItemStream<R> {
+    /// Start streaming a document, reading everything before `items`
+    pub fn new(reader: R) -> OmsResult<Self> {
+        let mut scanner = JsonScanner::new(reader)?;
+        let mut sections = Sections::default();
+
+        scanner.expect(b'{')?;
+        let mut first = true;
+        let mut items_offset = None;
+        while let Some(key) = scanner.next_key(&mut first, b'}')? {
+            if key != "items" {
+                sections.read(&key, &mut scanner)?;
+            } else if sections.has_header() {
+                items_offset = Some(scanner.offset);
+                break;
+            } else {
+                // Come back for the items once the header has been found
+                items_offset = Some(scanner.offset);
+                scanner.skip_value()?;
+            }
+        }
+
+        let mut state = StreamState::Finished;
+        if let Some(offset) = items_offset {
+            scanner.seek_to(offset)?;
+            scanner.expect(b'[')?;
+            state = StreamState::FirstItem;
+        }
+
+        let oms_version = sections.oms_version.take()
+            .ok_or_else(|| OmsError::MissingRequiredField("oms_version before items".to_string()))?;
+        let metadata = sections.metadata.take()
+            .ok_or_else(|| OmsError::MissingRequiredField("metadata before items".to_string()))?;
+        let vendor = sections.vendor.take()
+            .ok_or_else(|| OmsError::MissingRequiredField("vendor before items".to_string()))?;
+
+        Ok(Self {
+            scanner,
+            state,
+            validate: true,
+            oms_version,
+            metadata,
+            vendor,
+            sections,
+        })
+    }
+
+    /// Skip per-item validation
+    pub fn without_validation(mut self) -> Self {
+        self.validate = false;
+        self
+    }
+
+    /// OMS version of the document
+    pub fn oms_version(&self) -> &str {
+        &self.oms_version
+    }
+
+    /// Document metadata
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    /// Vendor information
+    pub fn vendor(&self) -> &Vendor {
+        &self.vendor
+    }
+
+    /// Ingredient catalog, if it has been read
+    pub fn ingredients(&self) -> Option<&[Ingredient]> {
+        self.sections.ingredients.as_deref()
+    }
+
+    /// Order, available once every item has been read
+    pub fn order(&self) -> Option<&Order> {
+        self.sections.order.as_ref()
+    }
+
+    /// Extensions, available once every item has been read
+    pub fn extensions(&self) -> Option<&Extensions> {
+        self.sections.extensions.as_ref()
+    }
+
+    /// Whether every item and the rest of the document have been read
+    pub fn is_finished(&self) -> bool {
+        self.state == StreamState::Finished
+    }
+
+    /// Read the raw JSON of the next item, or the rest of the document once `items` ends
+    fn read_next_item(&mut self) -> OmsResult<bool> {
+        let mut first = self.state == StreamState::FirstItem;
+        match self.state {
+            StreamState::Finished => return Ok(false),
+            StreamState::FirstItem | StreamState::NextItem => {},
+        }
+
+        if self.scanner.next_element(&mut first, b']')? {
+            self.state = StreamState::NextItem;
+            self.scanner.read_value()?;
+            return Ok(true);
+        }
+
+        // The items array is done, so read whatever follows it
+        self.state = StreamState::Finished;
+        let mut first = false;
+        while let Some(key) = self.scanner.next_key(&mut first, b'}')? {
+            self.sections.read(&key, &mut self.scanner)?;
+        }
+        Ok(false)
+    }
+
+    /// Deserialize and validate the item that was just read
+    fn parse_item(&self) -> OmsResult<Item> {
+        let item: Item = serde_json::from_slice(self.scanner.value())?;
+        if self.validate {
+            validate_item(&item, self.sections.ingredients.as_deref())?;
+        }
+        Ok(item)
+    }
+}
+
+impl<R: BufRead + Seek> Iterator for ItemStream<R> {
+    type Item = OmsResult<Item>;
+
+    /// Yield the next item; an invalid item is reported without ending the stream
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.read_next_item() {
+            Ok(true) => Some(self.parse_item()),
+            Ok(false) => None,
+            Err(error) => {
+                self.state = StreamState::Finished;
+                Some(Err(error))
+            },
+        }
+    }
+}
+
+impl Sections {
+    /// Whether everything needed before the items has been read
+    fn has_header(&self) -> bool {
+        self.oms_version.is_some() && self.metadata.is_some() && self.vendor.is_some()
+    }
+
+    /// Read the value of a top-level key, skipping keys that are not part of the standard
+    fn read<R: BufRead + Seek>(&mut self, key: &str, scanner: &mut JsonScanner<R>) -> OmsResult<()> {
+        match key {
+            "oms_version" => self.oms_version = Some(scanner.parse_value()?),
+            "metadata" => self.metadata = Some(scanner.parse_value()?),
+            "vendor" => self.vendor = Some(scanner.parse_value()?),
+            "ingredients" => self.ingredients = scanner.parse_value()?,
+            "order" => self.order = scanner.parse_value()?,
+            "extensions" => self.extensions = scanner.parse_value()?,
+            _ => scanner.skip_value()?,
+        }
+        Ok(())
+    }
+}
+
+/// Splits a JSON byte stream into the raw text of individual values
+struct JsonScanner<R> {
+    reader: R,
+    buffer: Vec<u8>,
+    recording: bool,
+    start: u64,
+    offset: u64,
+}
+
+impl<R: BufRead + Seek> JsonScanner<R> {
+    fn new(mut reader: R) -> OmsResult<Self> {
+        let start = reader.stream_position()?;
+        Ok(Self {
+            reader,
+            buffer: Vec::new(),
+            recording: true,
+            start,
+            offset: 0,
+        })
+    }
+
+    /// Continue reading from an earlier offset
+    fn seek_to(&mut self, offset: u64) -> OmsResult<()> {
+        self.reader.seek(SeekFrom::Start(self.start + offset))?;
+        self.offset = offset;
+        Ok(())
+    }
+
+    /// Raw JSON of the last value read
+    fn value(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    fn syntax_error(&self, message: &str) -> OmsError {
+        OmsError::InvalidFieldValue(format!("malformed JSON at byte {}: {}", self.offset, message))
+    }
+
+    fn peek(&mut self) -> OmsResult<Option<u8>> {
+        Ok(self.reader.fill_buf()?.first().copied())
+    }
+
+    fn bump(&mut self) -> OmsResult<u8> {
+        let byte = self.peek()?.ok_or_else(|| self.syntax_error("unexpected end of input"))?;
+        self.reader.consume(1);
+        self.offset += 1;
+        Ok(byte)
+    }
+
+    /// Skip whitespace and return the next byte without consuming it
+    fn peek_token(&mut self) -> OmsResult<u8> {
+        loop {
+            match self.peek()? {
+                Some(byte) if byte.is_ascii_whitespace() => self.bump()?,
+                Some(byte) => return Ok(byte),
+                None => return Err(self.syntax_error("unexpected end of input")),
+            };
+        }
+    }
+
+    fn expect(&mut self, expected: u8) -> OmsResult<()> {
+        if self.peek_token()? != expected {
+            return Err(self.syntax_error(&format!("expected '{}'", expected as char)));
+        }
+        self.bump()?;
+        Ok(())
+    }
+
+    /// Move to the next element of an array or object, returning false at its closing bracket
+    fn next_element(&mut self, first: &mut bool, close: u8) -> OmsResult<bool> {
+        if self.peek_token()? == close {
+            self.bump()?;
+            return Ok(false);
+        }
+        if !*first {
+            self.expect(b',')?;
+        }
+        *first = false;
+        Ok(true)
+    }
+
+    /// Read the next object key and its colon, returning `None` at the closing brace
+    fn next_key(&mut self, first: &mut bool, close: u8) -> OmsResult<Option<String>> {
+        if !self.next_element(first, close)? {
+            return Ok(None);
+        }
+        if self.peek_token()? != b'"' {
+            return Err(self.syntax_error("expected an object key"));
+        }
+        self.buffer.clear();
+        self.copy_string()?;
+        let key = serde_json::from_slice(&self.buffer)?;
+        self.expect(b':')?;
+        Ok(Some(key))
+    }
+
+    /// Read one value and deserialize it
+    fn parse_value<T: DeserializeOwned>(&mut self) -> OmsResult<T> {
+        self.read_value()?;
+        Ok(serde_json::from_slice(&self.buffer)?)
+    }
+
+    /// Skip one value without keeping its text
+    fn skip_value(&mut self) -> OmsResult<()> {
+        self.recording = false;
+        let result = self.read_value();
+        self.recording = true;
+        result
+    }
+
+    /// Copy the raw text of one value into the buffer
+    fn read_value(&mut self) -> OmsResult<()> {
+        self.buffer.clear();
+        self.peek_token()?;
+        let start = self.offset;
+
+        let mut depth = 0usize;
+        loop {
+            let Some(byte) = self.peek()? else {
+                return Err(self.syntax_error("unexpected end of input"));
+            };
+            match byte {
+                b'"' => self.copy_string()?,
+                b'{' | b'[' => {
+                    depth += 1;
+                    self.copy_byte()?;
+                    continue;
+                },
+                b'}' | b']' if depth > 0 => {
+                    depth -= 1;
+                    self.copy_byte()?;
+                },
+                b',' | b'}' | b']' if depth == 0 => break,
+                byte if depth == 0 && byte.is_ascii_whitespace() => break,
+                _ => {
+                    self.copy_byte()?;
+                    continue;
+                },
+            }
+            if depth == 0 {
+                break;
+            }
+        }
+
+        if self.offset == start {
+            return Err(self.syntax_error("expected a value"));
+        }
+        Ok(())
+    }
+
+    fn copy_byte(&mut self) -> OmsResult<()> {
+        let byte = self.bump()?;
+        self.record(byte);
+        Ok(())
+    }
+
+    fn record(&mut self, byte: u8) {
+        if self.recording {
+            self.buffer.push(byte);
+        }
+    }
+
+    /// Copy a string literal, including its quotes and escapes
+    fn copy_string(&mut self) -> OmsResult<()> {
+        self.copy_byte()?;
+        loop {
+            match self.bump()? {
+                b'\\' => {
+                    self.record(b'\\');
+                    self.copy_byte()?;
+                },
+                b'"' => {
+                    self.record(b'"');
+                    return Ok(());
+                },
+                byte => self.record(byte),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{create_template, generate_order, save_document_to_file};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_stream_yields_items_in_order() {
+        let mut doc = create_template("coffee-shop").unwrap();
+        doc.items[0].name = "Latte [large] \"house\" {oat}".into();
+        generate_order(&mut doc, Some("customer-1")).unwrap();
+        doc.add_extension("loyalty", serde_json::json!({"points": 10}));
+        let json = doc.to_json().unwrap();
+
+        let mut stream = ItemStream::new(Cursor::new(json)).unwrap();
+        assert_eq!(stream.oms_version(), doc.oms_version);
+        assert_eq!(stream.metadata(), &doc.metadata);
+        assert_eq!(stream.vendor(), &doc.vendor);
+        assert!(stream.order().is_none());
+
+        let items: Vec<Item> = stream.by_ref().collect::<OmsResult<_>>().unwrap();
+        assert_eq!(items, doc.items);
+        assert!(stream.is_finished());
+        assert_eq!(stream.order(), doc.order.as_ref());
+        assert_eq!(stream.extensions(), doc.extensions.as_ref());
+    }
+
+    #[test]
+    fn test_catalog_is_read_before_items() {
+        let mut doc = create_template("coffee-shop").unwrap();
+        doc.ingredients = Some(vec![Ingredient {
+            id: "milk".to_string(),
+            name: "Milk".to_string(),
+            allergens: Some(vec!["dairy".to_string()]),
+            dietary_flags: None,
+            nutrition_per_100g: None,
+        }]);
+        let usage = |ingredient_id: &str| IngredientUsage {
+            ingredient_id: ingredient_id.to_string(),
+            quantity: MeasurementValue { value: 200.0, unit: "g".to_string() },
+        };
+        doc.items[0].customizations.as_mut().unwrap()[0].options.as_mut().unwrap()[0].ingredients = Some(vec![usage("oat-milk")]);
+        doc.items[1].customizations.as_mut().unwrap()[0].options.as_mut().unwrap()[0].ingredients = Some(vec![usage("milk")]);
+        let json = doc.to_json().unwrap();
+        assert!(json.find("\"ingredients\"").unwrap() < json.find("\"items\"").unwrap());
+
+        let mut stream = ItemStream::new(Cursor::new(json)).unwrap();
+        assert_eq!(stream.ingredients(), doc.ingredients.as_deref());
+        let results: Vec<OmsResult<Item>> = stream.by_ref().collect();
+        assert!(matches!(&results[0], Err(OmsError::InvalidFieldValue(message)) if message.contains("oat-milk")));
+        assert_eq!(results[1].as_ref().unwrap(), &doc.items[1]);
+    }
+
+    #[test]
+    fn test_invalid_items_do_not_end_the_stream() {
+        let doc = create_template("coffee-shop").unwrap();
+        let mut value = serde_json::to_value(&doc).unwrap();
+        value["items"][0]["currency"] = serde_json::json!("XYZ");
+        value["items"][1]["base_price"] = serde_json::json!("free");
+        let json = serde_json::to_string(&value).unwrap();
+
+        let results: Vec<OmsResult<Item>> = ItemStream::new(Cursor::new(&json)).unwrap().collect();
+        assert_eq!(results.len(), doc.items.len());
+        assert!(matches!(results[0], Err(OmsError::InvalidCurrency(_))));
+        assert!(matches!(results[1], Err(OmsError::SerializationError(_))));
+        assert!(results[2..].iter().all(Result::is_ok));
+
+        let unchecked: Vec<OmsResult<Item>> = ItemStream::new(Cursor::new(&json)).unwrap().without_validation().collect();
+        assert!(unchecked[0].is_ok());
+    }
+
+    #[test]
+    fn test_malformed_json_ends_the_stream() {
+        let doc = create_template("coffee-shop").unwrap();
+        let json = doc.to_compact_json().unwrap();
+        let truncated = &json[..json.find("\"customizations\"").unwrap()];
+
+        let results: Vec<OmsResult<Item>> = ItemStream::new(Cursor::new(truncated)).unwrap().collect();
+        assert_eq!(results.len(), 1);
+        assert!(matches!(results[0], Err(OmsError::InvalidFieldValue(_))));
+    }
+
+    #[test]
+    fn test_header_after_items() {
+        // Keys sorted alphabetically put items before metadata and vendor
+        let doc = create_template("cafe").unwrap();
+        let json = serde_json::to_string(&serde_json::to_value(&doc).unwrap()).unwrap();
+        assert!(json.find("\"items\"").unwrap() < json.find("\"vendor\"").unwrap());
+
+        let mut stream = ItemStream::new(Cursor::new(json)).unwrap();
+        assert_eq!(stream.vendor(), &doc.vendor);
+        assert_eq!(stream.by_ref().collect::<OmsResult<Vec<_>>>().unwrap(), doc.items);
+
+        let json = r#"{"oms_version": "1.0.0", "items": [], "metadata": {"created": "2024-01-01T00:00:00Z", "source": "test", "locale": "en-US"}}"#;
+        assert!(matches!(ItemStream::new(Cursor::new(json)), Err(OmsError::MissingRequiredField(_))));
+    }
+
+    #[test]
+    fn test_open_streams_from_file() {
+        let doc = create_template("pizzeria").unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("menu.omenu");
+        save_document_to_file(&doc, &path).unwrap();
+
+        let stream = ItemStream::open(&path).unwrap();
+        let ids: Vec<String> = stream.map(|item| item.unwrap().id).collect();
+        assert_eq!(ids, doc.items.iter().map(|item| item.id.clone()).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,439 @@
+// src/subscriber.rs
+//
+// Client receiving order status events over Server-Sent Events or WebSocket
+//
+// Dropped connections are reopened with the retry policy's backoff, resuming after
+// the last received event.
+
+use crate::client::{is_retryable_status, OmsClient, RetryPolicy};
+use crate::orders::{OrderStatusEvent, LAST_EVENT_ID_HEADER};
+use crate::{OmsError, OmsResult};
+use futures_util::StreamExt;
+use reqwest::header::ACCEPT;
+use reqwest::StatusCode;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::{self, Message};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use url::Url;
+
+/// Transport of order status events
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventTransport {
+    /// Server-Sent Events from `{endpoint}/{order_id}/events`
+    ServerSentEvents,
+
+    /// WebSocket text messages from `{endpoint}/{order_id}/ws`
+    WebSocket,
+}
+
+/// Receives the status events of one order, reconnecting when the connection drops
+///
+/// Reconnects resume after the last received event, and events received twice are
+/// skipped. The subscriber gives up once the retry policy's retries fail in a row
+/// without an event arriving, and finishes after a final status.
+#[derive(Debug)]
+pub struct OrderEventSubscriber {
+    http: reqwest::Client,
+    url: Url,
+    transport: EventTransport,
+    retry: RetryPolicy,
+    last_event_id: Option<u64>,
+    connection: Option<Connection>,
+    failures: u32,
+    finished: bool,
+}
+
+/// An open event stream
+#[derive(Debug)]
+enum Connection {
+    ServerSentEvents {
+        response: reqwest::Response,
+        buffer: Vec<u8>,
+    },
+    WebSocket(Box<WebSocketStream<MaybeTlsStream<TcpStream>>>),
+}
+
+impl OrderEventSubscriber {
+    /// Subscribe to an order's events from an order endpoint such as `https://vendor.example/orders`
+    pub fn new(endpoint: &str, order_id: &str, transport: EventTransport) -> OmsResult<Self> {
+        let endpoint = Url::parse(endpoint)
+            .map_err(|e| OmsError::UrlError(format!("Invalid endpoint {}: {}", endpoint, e)))?;
+        Self::from_parts(reqwest::Client::new(), endpoint, order_id, transport, RetryPolicy::default())
+    }
+
+    fn from_parts(
+        http: reqwest::Client,
+        endpoint: Url,
+        order_id: &str,
+        transport: EventTransport,
+        retry: RetryPolicy,
+    ) -> OmsResult<Self> {
+        let mut url = endpoint.clone();
+        url.path_segments_mut()
+            .map_err(|_| OmsError::UrlError(format!("{} cannot have a path", endpoint)))?
+            .pop_if_empty()
+            .push(order_id);
+        Ok(Self {
+            http,
+            url,
+            transport,
+            retry,
+            last_event_id: None,
+            connection: None,
+            failures: 0,
+            finished: false,
+        })
+    }
+
+    /// Use a preconfigured HTTP client for Server-Sent Events
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Set how dropped connections are retried
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Resume after an event received earlier
+    pub fn with_last_event_id(mut self, last_event_id: u64) -> Self {
+        self.last_event_id = Some(last_event_id);
+        self
+    }
+
+    /// ID of the last received event
+    pub fn last_event_id(&self) -> Option<u64> {
+        self.last_event_id
+    }
+
+    /// Wait for the next event, returning `None` after a final status
+    pub async fn next_event(&mut self) -> OmsResult<Option<OrderStatusEvent>> {
+        loop {
+            if self.finished {
+                return Ok(None);
+            }
+
+            let connection = match &mut self.connection {
+                Some(connection) => connection,
+                None => match self.connect().await {
+                    Ok(connection) => self.connection.insert(connection),
+                    Err(error) => {
+                        self.wait_to_retry(error).await?;
+                        continue;
+                    },
+                },
+            };
+
+            match connection.next().await {
+                Ok(Some(event)) => {
+                    self.failures = 0;
+                    if self.last_event_id.is_some_and(|last_id| event.id <= last_id) {
+                        continue;
+                    }
+                    self.last_event_id = Some(event.id);
+                    if event.status.is_final() {
+                        self.finished = true;
+                        if let Some(connection) = self.connection.take() {
+                            connection.close().await;
+                        }
+                    }
+                    return Ok(Some(event));
+                },
+                Ok(None) => {
+                    self.connection = None;
+                    self.wait_to_retry(OmsError::EventStreamError("connection closed".to_string())).await?;
+                },
+                Err(error) => {
+                    self.connection = None;
+                    self.wait_to_retry(error).await?;
+                },
+            }
+        }
+    }
+
+    /// Back off before reconnecting, or give up on permanent errors and exhausted retries
+    async fn wait_to_retry(&mut self, error: OmsError) -> OmsResult<()> {
+        let transient = match &error {
+            OmsError::NetworkError(_) | OmsError::EventStreamError(_) => true,
+            OmsError::HttpStatus(status, _) => StatusCode::from_u16(*status).is_ok_and(is_retryable_status),
+            _ => false,
+        };
+        if !transient || self.failures >= self.retry.max_retries {
+            return Err(error);
+        }
+        tokio::time::sleep(self.retry.backoff(self.failures)).await;
+        self.failures += 1;
+        Ok(())
+    }
+
+    async fn connect(&self) -> OmsResult<Connection> {
+        let mut url = self.url.clone();
+        match self.transport {
+            EventTransport::ServerSentEvents => {
+                url.path_segments_mut().expect("order URL has a path").push("events");
+                let mut request = self.http.get(url).header(ACCEPT, "text/event-stream");
+                if let Some(last_event_id) = self.last_event_id {
+                    request = request.header(LAST_EVENT_ID_HEADER, last_event_id.to_string());
+                }
+                let response = request.send().await?;
+                let status = response.status();
+                if !status.is_success() {
+                    return Err(OmsError::HttpStatus(status.as_u16(), response.text().await.unwrap_or_default()));
+                }
+                Ok(Connection::ServerSentEvents { response, buffer: Vec::new() })
+            },
+            EventTransport::WebSocket => {
+                url.path_segments_mut().expect("order URL has a path").push("ws");
+                let scheme = if url.scheme() == "https" { "wss" } else { "ws" };
+                url.set_scheme(scheme)
+                    .map_err(|_| OmsError::UrlError(format!("{} cannot use WebSocket", self.url)))?;
+                if let Some(last_event_id) = self.last_event_id {
+                    url.query_pairs_mut().append_pair("last_event_id", &last_event_id.to_string());
+                }
+                let (socket, _) = tokio_tungstenite::connect_async(url.as_str()).await.map_err(websocket_error)?;
+                Ok(Connection::WebSocket(Box::new(socket)))
+            },
+        }
+    }
+}
+
+impl Connection {
+    /// Read the next event, returning `None` when the server closes the stream
+    async fn next(&mut self) -> OmsResult<Option<OrderStatusEvent>> {
+        match self {
+            Connection::ServerSentEvents { response, buffer } => loop {
+                if let Some(end) = buffer.windows(2).position(|window| window == b"\n\n") {
+                    let block: Vec<u8> = buffer.drain(..end + 2).collect();
+                    let block = std::str::from_utf8(&block)
+                        .map_err(|_| OmsError::EventStreamError("event is not valid UTF-8".to_string()))?;
+                    match parse_sse_event(block)? {
+                        Some(event) => return Ok(Some(event)),
+                        None => continue,
+                    }
+                }
+                match response.chunk().await? {
+                    // Line endings are normalized so that blank lines are always `\n\n`
+                    Some(chunk) => buffer.extend(chunk.iter().filter(|byte| **byte != b'\r')),
+                    None => return Ok(None),
+                }
+            },
+            Connection::WebSocket(socket) => {
+                while let Some(message) = socket.next().await {
+                    match message.map_err(websocket_error)? {
+                        Message::Text(text) => return Ok(Some(serde_json::from_str(text.as_str())?)),
+                        Message::Close(_) => return Ok(None),
+                        _ => continue,
+                    }
+                }
+                Ok(None)
+            },
+        }
+    }
+
+    async fn close(self) {
+        if let Connection::WebSocket(mut socket) = self {
+            // The stream is finished, so a failed close handshake does not matter
+            let _ = socket.close(None).await;
+        }
+    }
+}
+
+/// Parse one Server-Sent Events block, skipping comments and other event types
+fn parse_sse_event(block: &str) -> OmsResult<Option<OrderStatusEvent>> {
+    let mut event_type = None;
+    let mut data = Vec::new();
+    for line in block.lines() {
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "event" => event_type = Some(value),
+            "data" => data.push(value),
+            _ => {},
+        }
+    }
+    if data.is_empty() || event_type.is_some_and(|event_type| event_type != "status") {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_str(&data.join("\n"))?))
+}
+
+fn websocket_error(error: tungstenite::Error) -> OmsError {
+    match error {
+        tungstenite::Error::Http(response) => {
+            let body = response.body().as_deref().map(String::from_utf8_lossy).unwrap_or_default();
+            OmsError::HttpStatus(response.status().as_u16(), body.into_owned())
+        },
+        error => OmsError::EventStreamError(error.to_string()),
+    }
+}
+
+impl OmsClient {
+    /// Subscribe to the status events of a submitted order
+    ///
+    /// The subscriber shares the client's HTTP client and retry policy.
+    pub fn subscribe_order_events(&self, order_id: &str, transport: EventTransport) -> OmsResult<OrderEventSubscriber> {
+        OrderEventSubscriber::from_parts(self.http.clone(), self.endpoint.clone(), order_id, transport, self.retry.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::OrderStatus;
+    use chrono::Utc;
+    use futures_util::SinkExt;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn fast_retries() -> RetryPolicy {
+        RetryPolicy::default().with_backoff(Duration::from_millis(1), Duration::from_millis(5))
+    }
+
+    fn event(id: u64, status: OrderStatus) -> String {
+        serde_json::to_string(&OrderStatusEvent {
+            id,
+            order_id: "order-7".to_string(),
+            status,
+            timestamp: Utc::now(),
+        }).unwrap()
+    }
+
+    async fn next_status(subscriber: &mut OrderEventSubscriber) -> OrderStatus {
+        subscriber.next_event().await.unwrap().expect("an event").status
+    }
+
+    /// Read an HTTP request head
+    async fn read_request(stream: &mut TcpStream) -> String {
+        let mut request = Vec::new();
+        let mut byte = [0; 1];
+        while !request.ends_with(b"\r\n\r\n") {
+            stream.read_exact(&mut byte).await.unwrap();
+            request.push(byte[0]);
+        }
+        String::from_utf8(request).unwrap().to_lowercase()
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_order_events_over_both_transports() {
+        use crate::server::{router, InMemoryStore};
+        use crate::utils::{create_template, generate_order};
+
+        let store = Arc::new(InMemoryStore::new().with_menu(create_template("cafe").unwrap()));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/orders", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router(store)).await.unwrap() });
+
+        let mut order = create_template("cafe").unwrap();
+        generate_order(&mut order, Some("customer-1")).unwrap();
+        order.order.as_mut().unwrap().id = Some("order-7".to_string());
+        let client = OmsClient::new(&endpoint).unwrap().with_retry_policy(fast_retries());
+        // The server assigns its own order ID
+        let order_id = client.submit_order(&order).await.unwrap().order_id.unwrap();
+        assert_ne!(order_id, "order-7");
+
+        let mut subscribers = [
+            client.subscribe_order_events(&order_id, EventTransport::ServerSentEvents).unwrap(),
+            client.subscribe_order_events(&order_id, EventTransport::WebSocket).unwrap(),
+        ];
+        // Subscribers catch up on the submission, then receive changes as they happen
+        for subscriber in &mut subscribers {
+            assert_eq!(next_status(subscriber).await, OrderStatus::Submitted);
+        }
+        for status in [OrderStatus::Confirmed, OrderStatus::InProgress, OrderStatus::Ready, OrderStatus::Completed] {
+            reqwest::Client::new()
+                .post(format!("{}/{}/status", endpoint, order_id))
+                .json(&serde_json::json!({ "status": status }))
+                .send().await.unwrap()
+                .error_for_status().unwrap();
+            for subscriber in &mut subscribers {
+                assert_eq!(next_status(subscriber).await, status);
+            }
+        }
+        for subscriber in &mut subscribers {
+            assert!(subscriber.next_event().await.unwrap().is_none());
+            assert_eq!(subscriber.last_event_id(), Some(5));
+        }
+
+        for transport in [EventTransport::ServerSentEvents, EventTransport::WebSocket] {
+            let mut resumed = client.subscribe_order_events(&order_id, transport).unwrap().with_last_event_id(3);
+            assert_eq!(next_status(&mut resumed).await, OrderStatus::Ready);
+            assert_eq!(next_status(&mut resumed).await, OrderStatus::Completed);
+
+            let mut missing = client.subscribe_order_events("order-8", transport).unwrap();
+            assert!(matches!(missing.next_event().await, Err(OmsError::HttpStatus(404, _))));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sse_reconnects_with_last_event_id() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/orders", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let head = "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\nconnection: close\r\n\r\n";
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let request = read_request(&mut stream).await;
+            assert!(request.starts_with("get /orders/order-7/events "));
+            assert!(!request.contains("last-event-id"));
+            // The connection drops after the first event
+            let body = format!(": connected\r\n\r\nid: 1\r\nevent: status\r\ndata: {}\r\n\r\n", event(1, OrderStatus::Confirmed));
+            stream.write_all(format!("{}{}", head, body).as_bytes()).await.unwrap();
+            drop(stream);
+
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let request = read_request(&mut stream).await;
+            assert!(request.contains("last-event-id: 1\r\n"));
+            let body = format!(
+                "id: 1\ndata: {}\n\nid: 2\ndata: {}\n\n",
+                event(1, OrderStatus::Confirmed),
+                event(2, OrderStatus::Cancelled),
+            );
+            stream.write_all(format!("{}{}", head, body).as_bytes()).await.unwrap();
+        });
+
+        let mut subscriber = OrderEventSubscriber::new(&endpoint, "order-7", EventTransport::ServerSentEvents)
+            .unwrap()
+            .with_retry_policy(fast_retries());
+        assert_eq!(next_status(&mut subscriber).await, OrderStatus::Confirmed);
+        // The repeated event is skipped
+        let cancelled = subscriber.next_event().await.unwrap().unwrap();
+        assert_eq!((cancelled.id, cancelled.status), (2, OrderStatus::Cancelled));
+        assert!(subscriber.next_event().await.unwrap().is_none());
+        server.await.unwrap();
+    }
+
+    // The handshake callback's error type is set by tungstenite
+    #[allow(clippy::result_large_err)]
+    #[tokio::test]
+    async fn test_websocket_reconnects_with_last_event_id() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/orders", listener.local_addr().unwrap());
+        let queries = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&queries);
+        let server = tokio::spawn(async move {
+            for message in [event(1, OrderStatus::Ready), event(2, OrderStatus::Completed)] {
+                let (stream, _) = listener.accept().await.unwrap();
+                let seen = Arc::clone(&seen);
+                let mut socket = tokio_tungstenite::accept_hdr_async(stream, move |request: &tungstenite::handshake::server::Request, response| {
+                    seen.lock().unwrap().push(format!("{}", request.uri()));
+                    Ok(response)
+                }).await.unwrap();
+                // The first connection drops without a close handshake
+                socket.send(Message::Text(message.into())).await.unwrap();
+            }
+        });
+
+        let mut subscriber = OrderEventSubscriber::new(&endpoint, "order-7", EventTransport::WebSocket)
+            .unwrap()
+            .with_retry_policy(fast_retries());
+        assert_eq!(next_status(&mut subscriber).await, OrderStatus::Ready);
+        assert_eq!(next_status(&mut subscriber).await, OrderStatus::Completed);
+        assert!(subscriber.next_event().await.unwrap().is_none());
+        server.await.unwrap();
+        assert_eq!(*queries.lock().unwrap(), ["/orders/order-7/ws", "/orders/order-7/ws?last_event_id=1"]);
+    }
+}
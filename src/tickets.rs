@@ -0,0 +1,542 @@
+// src/tickets.rs
+//
+// Kitchen tickets and customer receipts for thermal printers
+//
+// Tickets are laid out as fixed-width plain text and can also be encoded as ESC/POS
+// commands. ESC/POS output is ASCII only: other characters are printed as `?`.
+
+use crate::currency::{format_money, DEFAULT_CURRENCY};
+use crate::document::line_total;
+use crate::labels::format_number;
+use crate::types::*;
+use crate::{OmsError, OmsResult};
+use std::fmt;
+
+/// Characters per line of an 80 mm thermal printer in its default font
+pub const DEFAULT_TICKET_WIDTH: usize = 42;
+
+/// Indentation of customizations and notes under an item
+const DETAIL_INDENT: usize = 4;
+
+const ESC: u8 = 0x1B;
+const GS: u8 = 0x1D;
+
+/// Options for laying out a ticket
+#[derive(Debug, Clone, PartialEq)]
+pub struct TicketOptions {
+    /// Characters per line
+    pub width: usize,
+
+    /// Whether ESC/POS output ends with a paper cut
+    pub cut: bool,
+}
+
+impl Default for TicketOptions {
+    fn default() -> Self {
+        Self {
+            width: DEFAULT_TICKET_WIDTH,
+            cut: true,
+        }
+    }
+}
+
+impl TicketOptions {
+    /// Set the number of characters per line
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Leave the paper uncut after printing
+    pub fn without_cut(mut self) -> Self {
+        self.cut = false;
+        self
+    }
+}
+
+/// A line of a ticket
+#[derive(Debug, Clone, PartialEq)]
+pub enum TicketLine {
+    /// Centered heading, printed double size
+    Heading(String),
+
+    /// Left-aligned text, wrapped to the ticket width
+    Text {
+        text: String,
+        indent: usize,
+        bold: bool,
+    },
+
+    /// Text with a right-aligned amount
+    Row {
+        left: String,
+        right: String,
+        bold: bool,
+    },
+
+    /// Full-width separator
+    Rule,
+}
+
+/// A kitchen ticket or receipt ready for printing
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ticket {
+    options: TicketOptions,
+    lines: Vec<TicketLine>,
+}
+
+impl Ticket {
+    /// Lines of the ticket
+    pub fn lines(&self) -> &[TicketLine] {
+        &self.lines
+    }
+
+    /// Lay the ticket out as fixed-width plain text
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for line in &self.lines {
+            for row in self.layout(line) {
+                text.push_str(row.trim_end());
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    /// Encode the ticket as ESC/POS printer commands
+    pub fn to_escpos(&self) -> Vec<u8> {
+        let mut bytes = vec![ESC, b'@'];
+
+        for line in &self.lines {
+            let (heading, bold) = match line {
+                TicketLine::Heading(_) => (true, true),
+                TicketLine::Text { bold, .. } | TicketLine::Row { bold, .. } => (false, *bold),
+                TicketLine::Rule => (false, false),
+            };
+            if heading {
+                bytes.extend_from_slice(&[ESC, b'a', 1, GS, b'!', 0x11]);
+            }
+            if bold {
+                bytes.extend_from_slice(&[ESC, b'E', 1]);
+            }
+            for row in self.layout(line) {
+                // The printer centers headings itself
+                let row = if heading { row.trim() } else { row.trim_end() };
+                bytes.extend(row.chars().map(|c| if c.is_ascii() { c as u8 } else { b'?' }));
+                bytes.push(b'\n');
+            }
+            if bold {
+                bytes.extend_from_slice(&[ESC, b'E', 0]);
+            }
+            if heading {
+                bytes.extend_from_slice(&[GS, b'!', 0, ESC, b'a', 0]);
+            }
+        }
+
+        if self.options.cut {
+            // Feed past the cutter, then partial cut
+            bytes.extend_from_slice(&[GS, b'V', b'B', 3]);
+        } else {
+            bytes.extend_from_slice(b"\n\n\n");
+        }
+        bytes
+    }
+
+    /// Lay out a single line as rows of at most the ticket width
+    fn layout(&self, line: &TicketLine) -> Vec<String> {
+        let width = self.options.width.max(1);
+        match line {
+            TicketLine::Heading(text) => {
+                // Double-width characters take two columns
+                let columns = (width / 2).max(1);
+                wrap(text, columns, 0).into_iter()
+                    .map(|row| {
+                        let padding = (width.saturating_sub(row.chars().count() * 2)) / 2;
+                        format!("{}{}", " ".repeat(padding), row)
+                    })
+                    .collect()
+            },
+            TicketLine::Text { text, indent, .. } => wrap(text, width, *indent),
+            TicketLine::Row { left, right, .. } => {
+                let right_width = right.chars().count();
+                let mut rows = wrap(left, width.saturating_sub(right_width + 1).max(1), 0);
+                let last = rows.pop().unwrap_or_default();
+                let used = last.chars().count();
+                if used + 1 + right_width <= width {
+                    rows.push(format!("{}{}{}", last, " ".repeat(width - used - right_width), right));
+                } else {
+                    rows.push(last);
+                    rows.push(format!("{:>width$}", right, width = width));
+                }
+                rows
+            },
+            TicketLine::Rule => vec!["-".repeat(width)],
+        }
+    }
+}
+
+impl fmt::Display for Ticket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_text())
+    }
+}
+
+impl OmsDocument {
+    /// Build a kitchen ticket for the document's order
+    ///
+    /// Lists each item with its quantity, selected customizations (by option name)
+    /// and item note, followed by the customer notes.
+    pub fn kitchen_ticket(&self, options: &TicketOptions) -> OmsResult<Ticket> {
+        let order = self.require_order()?;
+
+        let mut lines = vec![TicketLine::Heading("KITCHEN".to_string())];
+        lines.extend(order_header(order));
+        lines.push(TicketLine::Rule);
+        for item in &self.items {
+            lines.push(TicketLine::Text {
+                text: format!("{} x {}", item.quantity.unwrap_or(1), item.name.text()),
+                indent: 0,
+                bold: true,
+            });
+            lines.extend(item_details(item));
+        }
+        lines.extend(customer_notes(order));
+
+        Ok(Ticket { options: options.clone(), lines })
+    }
+
+    /// Build a customer receipt for the document's order
+    ///
+    /// Contains the kitchen ticket's items with their line totals, followed by the
+    /// payment breakdown.
+    pub fn receipt(&self, options: &TicketOptions) -> OmsResult<Ticket> {
+        let order = self.require_order()?;
+        let locale = &self.metadata.locale;
+
+        let mut lines = vec![TicketLine::Heading(self.vendor.name.clone())];
+        if let Some(location) = &self.vendor.location_name {
+            lines.push(text(location, 0));
+        }
+        if let Some(address) = &self.vendor.address {
+            lines.push(text(&address.street, 0));
+            lines.push(text(&format!("{}, {} {}", address.city, address.region, address.postal_code), 0));
+        }
+        lines.extend(order_header(order));
+        lines.push(TicketLine::Rule);
+
+        for item in &self.items {
+            let currency = item.currency.as_deref()
+                .or_else(|| self.currency())
+                .unwrap_or(DEFAULT_CURRENCY);
+            lines.push(TicketLine::Row {
+                left: format!("{} x {}", item.quantity.unwrap_or(1), item.name.text()),
+                right: format_money(line_total(item), currency, locale)?,
+                bold: false,
+            });
+            lines.extend(item_details(item));
+        }
+        lines.extend(customer_notes(order));
+
+        if let Some(payment) = &order.payment {
+            lines.push(TicketLine::Rule);
+            let amounts = [("Subtotal", payment.subtotal), ("Tax", payment.tax), ("Tip", payment.tip)];
+            for (label, amount) in amounts {
+                if let Some(amount) = amount {
+                    lines.push(TicketLine::Row {
+                        left: label.to_string(),
+                        right: format_money(amount, &payment.currency, locale)?,
+                        bold: false,
+                    });
+                }
+            }
+            lines.push(TicketLine::Row {
+                left: "TOTAL".to_string(),
+                right: format_money(payment.total, &payment.currency, locale)?,
+                bold: true,
+            });
+
+            let status = match payment.status {
+                Some(PaymentStatus::Paid) => Some("Paid"),
+                Some(PaymentStatus::Unpaid) => Some("Unpaid"),
+                None => None,
+            };
+            match (status, &payment.method) {
+                (Some(status), Some(method)) => lines.push(text(&format!("{} by {}", status, method), 0)),
+                (Some(status), None) => lines.push(text(status, 0)),
+                (None, Some(method)) => lines.push(text(&format!("Payment: {}", method), 0)),
+                (None, None) => {},
+            }
+            for rate in order.exchange_rates.iter().flatten() {
+                lines.push(text(&format!("1 {} = {} {}", rate.from, format_number(rate.rate), rate.to), 0));
+            }
+        }
+
+        Ok(Ticket { options: options.clone(), lines })
+    }
+
+    fn require_order(&self) -> OmsResult<&Order> {
+        self.order.as_ref().ok_or_else(|| OmsError::MissingRequiredField("order".to_string()))
+    }
+}
+
+/// Order number, fulfilment and customer lines shared by tickets and receipts
+fn order_header(order: &Order) -> Vec<TicketLine> {
+    let mut lines = Vec::new();
+    if let Some(id) = &order.id {
+        lines.push(TicketLine::Text { text: format!("Order {}", id), indent: 0, bold: true });
+    }
+    if let Some(created) = order.created {
+        lines.push(text(&created.format("%Y-%m-%d %H:%M UTC").to_string(), 0));
+    }
+
+    let fulfilment = match order.r#type {
+        Some(OrderType::Pickup) => Some(("Pickup", order.pickup_time)),
+        Some(OrderType::Delivery) => Some(("Delivery", order.delivery_time)),
+        Some(OrderType::DineIn) => Some(("Dine in", None)),
+        None => None,
+    };
+    match fulfilment {
+        Some((kind, Some(time))) => lines.push(text(&format!("{} at {}", kind, time.format("%H:%M UTC")), 0)),
+        Some((kind, None)) => lines.push(text(kind, 0)),
+        None => {},
+    }
+
+    if let Some(customer) = &order.customer {
+        if let Some(name) = customer.name.as_ref().or(customer.id.as_ref()) {
+            lines.push(text(&format!("Customer: {}", name), 0));
+        }
+    }
+    lines
+}
+
+/// Selected customizations and note of an item
+fn item_details(item: &Item) -> Vec<TicketLine> {
+    let mut lines = Vec::new();
+    if let Some(selections) = &item.selected_customizations {
+        push_selections(&mut lines, selections, item.customizations.as_deref().unwrap_or_default(), DETAIL_INDENT);
+    }
+    if let Some(note) = &item.item_note {
+        lines.push(text(&format!("Note: {}", note), DETAIL_INDENT));
+    }
+    lines
+}
+
+/// Customer notes of an order, set apart from the items
+fn customer_notes(order: &Order) -> Vec<TicketLine> {
+    match &order.customer_notes {
+        Some(notes) => vec![TicketLine::Rule, TicketLine::Text { text: format!("Notes: {}", notes), indent: 0, bold: true }],
+        None => Vec::new(),
+    }
+}
+
+/// Append selections with option names resolved, followed by their sub-selections
+fn push_selections(lines: &mut Vec<TicketLine>, selections: &[SelectedCustomization], customizations: &[Customization], indent: usize) {
+    for selection in selections {
+        let customization = customizations.iter().find(|c| c.id == selection.customization_id);
+        let name = customization.map(|c| c.name.text()).unwrap_or(&selection.customization_id);
+        let options = customization.and_then(|c| c.options.as_deref()).unwrap_or_default();
+        let option_name = |id: &str| options.iter()
+            .find(|option| option.id == id)
+            .map(|option| option.name.text().to_string())
+            .unwrap_or_else(|| id.to_string());
+
+        let value = match &selection.selection {
+            CustomizationSelection::String(value) => match customization.map(|c| &c.r#type) {
+                Some(CustomizationType::Text) => format!("\"{}\"", value),
+                _ => option_name(value),
+            },
+            CustomizationSelection::StringArray(ids) => ids.iter()
+                .map(|id| option_name(id))
+                .collect::<Vec<_>>()
+                .join(", "),
+            CustomizationSelection::Number(value) => format_number(*value),
+            CustomizationSelection::Boolean(value) => if *value { "yes" } else { "no" }.to_string(),
+        };
+        lines.push(text(&format!("{}: {}", name, value), indent));
+
+        if let Some(sub_selections) = &selection.sub_selections {
+            let mut option_ids: Vec<&String> = sub_selections.keys().collect();
+            option_ids.sort();
+            for option_id in option_ids {
+                let sub_customizations = options.iter()
+                    .find(|option| &option.id == option_id)
+                    .and_then(|option| option.sub_customizations.as_deref())
+                    .unwrap_or_default();
+                push_selections(lines, &sub_selections[option_id], sub_customizations, indent + 2);
+            }
+        }
+    }
+}
+
+fn text(text: &str, indent: usize) -> TicketLine {
+    TicketLine::Text { text: text.to_string(), indent, bold: false }
+}
+
+/// Wrap text at word boundaries, indenting every row
+fn wrap(text: &str, width: usize, indent: usize) -> Vec<String> {
+    let indent = indent.min(width.saturating_sub(1));
+    let columns = width - indent;
+    let prefix = " ".repeat(indent);
+
+    let mut rows = Vec::new();
+    let mut row = String::new();
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        let row_len = row.chars().count();
+        if row_len > 0 && row_len + 1 + word.len() <= columns {
+            row.push(' ');
+            row.extend(word);
+            continue;
+        }
+        if row_len > 0 {
+            rows.push(format!("{}{}", prefix, row));
+        }
+        // Break words longer than a row
+        while word.len() > columns {
+            let rest = word.split_off(columns);
+            rows.push(format!("{}{}", prefix, word.into_iter().collect::<String>()));
+            word = rest;
+        }
+        row = word.into_iter().collect();
+    }
+    if !row.is_empty() || rows.is_empty() {
+        rows.push(format!("{}{}", prefix, row));
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{create_template, generate_order};
+    use chrono::{TimeZone, Utc};
+    use std::collections::HashMap;
+
+    fn ordered_steak() -> OmsDocument {
+        let mut doc = create_template("restaurant").unwrap();
+        let steak = &mut doc.items[0];
+        let dressing = |id: &str, name: &str| CustomizationOption {
+            id: id.to_string(),
+            name: name.into(),
+            price_adjustment: None,
+            nutrition_adjustments: None,
+            allergens: None,
+            dietary_flags: None,
+            incompatible_with: None,
+            sub_customizations: None,
+            stock: None,
+            ingredients: None,
+        };
+        let side = steak.customizations.as_mut().unwrap().iter_mut().find(|c| c.id == "side").unwrap();
+        let salad = side.options.as_mut().unwrap().iter_mut().find(|o| o.id == "salad").unwrap();
+        salad.sub_customizations = Some(vec![Customization {
+            id: "dressing".to_string(),
+            name: "Dressing".into(),
+            r#type: CustomizationType::SingleSelect,
+            required: true,
+            default: CustomizationDefault::String("vinaigrette".to_string()),
+            min_selections: None,
+            max_selections: None,
+            min: None,
+            max: None,
+            step: None,
+            unit_price_adjustment: None,
+            unit_nutrition_adjustments: None,
+            options: Some(vec![
+                dressing("vinaigrette", "Balsamic Vinaigrette"),
+                dressing("ranch", "Ranch Dressing"),
+            ]),
+            visible_when: None,
+        }]);
+
+        steak.quantity = Some(2);
+        steak.item_note = Some("Please slice the steak before plating it for sharing".to_string());
+        steak.selected_customizations = Some(vec![
+            SelectedCustomization {
+                customization_id: "cooking-pref".to_string(),
+                selection: CustomizationSelection::String("medium-rare".to_string()),
+                sub_selections: None,
+            },
+            SelectedCustomization {
+                customization_id: "side".to_string(),
+                selection: CustomizationSelection::String("salad".to_string()),
+                sub_selections: Some(HashMap::from([("salad".to_string(), vec![SelectedCustomization {
+                    customization_id: "dressing".to_string(),
+                    selection: CustomizationSelection::String("ranch".to_string()),
+                    sub_selections: None,
+                }])])),
+            },
+        ]);
+        generate_order(&mut doc, Some("customer-1")).unwrap();
+
+        let order = doc.order.as_mut().unwrap();
+        order.id = Some("order-42".to_string());
+        order.created = Some(Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap());
+        order.pickup_time = Some(Utc.with_ymd_and_hms(2024, 5, 1, 12, 30, 0).unwrap());
+        order.customer_notes = Some("Nut allergy".to_string());
+        doc
+    }
+
+    #[test]
+    fn test_kitchen_ticket_text() {
+        let doc = ordered_steak();
+        let customizations = doc.items[0].customizations.as_ref().unwrap();
+        assert!(customizations.iter().any(|c| c.id == "cooking-pref"), "template ids changed");
+
+        let ticket = doc.kitchen_ticket(&TicketOptions::default().with_width(32)).unwrap();
+        let expected = concat!("         KITCHEN\n", "\
+Order order-42
+2024-05-01 12:00 UTC
+Pickup at 12:30 UTC
+Customer: customer-1
+--------------------------------
+2 x New York Strip Steak
+    Cooking Preference: Medium
+    Rare
+    Side: House Salad
+      Dressing: Ranch Dressing
+    Note: Please slice the steak
+    before plating it for
+    sharing
+--------------------------------
+Notes: Nut allergy
+");
+        assert_eq!(ticket.to_text(), expected);
+        assert!(ticket.to_text().lines().all(|line| line.chars().count() <= 32));
+    }
+
+    #[test]
+    fn test_receipt_adds_payment_breakdown() {
+        let mut doc = ordered_steak();
+        doc.order.as_mut().unwrap().customer_notes = None;
+
+        let receipt = doc.receipt(&TicketOptions::default()).unwrap().to_text();
+        let row = |left: &str, right: &str| format!("\n{}{}{}\n", left, " ".repeat(DEFAULT_TICKET_WIDTH - left.len() - right.len()), right);
+        assert!(receipt.starts_with("  Restaurant Template\nOrder order-42\n"));
+        assert!(receipt.contains(&row("2 x New York Strip Steak", "$59.98")));
+        assert!(receipt.contains("\n    Cooking Preference: Medium Rare\n"));
+        assert!(receipt.contains("\n      Dressing: Ranch Dressing\n"));
+        assert!(receipt.contains(&row("Subtotal", "$59.98")));
+        assert!(receipt.contains(&row("Tax", "$4.80")));
+        assert!(receipt.ends_with(&format!("{}Unpaid\n", row("TOTAL", "$64.78"))));
+        assert!(!receipt.contains("Notes:"));
+    }
+
+    #[test]
+    fn test_escpos_encoding() {
+        let mut doc = ordered_steak();
+        doc.items[0].name = "Café Steak".into();
+        let ticket = doc.kitchen_ticket(&TicketOptions::default()).unwrap();
+
+        let bytes = ticket.to_escpos();
+        assert!(bytes.starts_with(&[ESC, b'@', ESC, b'a', 1, GS, b'!', 0x11, ESC, b'E', 1, b'K']));
+        assert!(bytes.ends_with(&[GS, b'V', b'B', 3]));
+        assert!(bytes.is_ascii());
+        let printed = String::from_utf8(bytes).unwrap();
+        assert!(printed.contains("\x1bE\x012 x Caf? Steak\n\x1bE\x00"));
+
+        let uncut = doc.kitchen_ticket(&TicketOptions::default().without_cut()).unwrap().to_escpos();
+        assert!(uncut.ends_with(b"\n\n\n"));
+        assert!(matches!(create_template("cafe").unwrap().receipt(&TicketOptions::default()), Err(OmsError::MissingRequiredField(_))));
+    }
+}
@@ -3,13 +3,14 @@
 // Core data structures for the OpenMenuStandard
 
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt;
 use std::str::FromStr;
 
 /// Main OmsDocument struct representing a complete OpenMenuStandard document
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct OmsDocument {
     /// Version of the OpenMenuStandard specification
     pub oms_version: String,
@@ -20,6 +21,12 @@ pub struct OmsDocument {
     /// Information about the vendor
     pub vendor: Vendor,
     
+    /// Optional catalog of ingredients referenced by items and options
+    ///
+    /// Declared before `items` so streaming readers see the catalog first.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ingredients: Option<Vec<Ingredient>>,
+    
     /// Menu items
     pub items: Vec<Item>,
     
@@ -33,7 +40,7 @@ pub struct OmsDocument {
 }
 
 /// Metadata about the OMS document
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct Metadata {
     /// When the document was created
     pub created: DateTime<Utc>,
@@ -46,7 +53,7 @@ pub struct Metadata {
 }
 
 /// Information about the food service provider
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct Vendor {
     /// Unique identifier for the vendor
     pub id: String,
@@ -87,7 +94,7 @@ pub struct Vendor {
 }
 
 /// Physical address information
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct Address {
     /// Street address
     pub street: String,
@@ -106,7 +113,7 @@ pub struct Address {
 }
 
 /// Contact information
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct Contact {
     /// Phone number
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -118,7 +125,7 @@ pub struct Contact {
 }
 
 /// Business hours for a particular day
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct BusinessHours {
     /// Day of the week
     pub day: DayOfWeek,
@@ -128,7 +135,7 @@ pub struct BusinessHours {
 }
 
 /// Days of the week
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum DayOfWeek {
     Monday,
@@ -141,7 +148,7 @@ pub enum DayOfWeek {
 }
 
 /// Time range with open and close times
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct TimeRange {
     /// Opening time in 24-hour format (HH:MM)
     pub open: String,
@@ -151,13 +158,13 @@ pub struct TimeRange {
 }
 
 /// Representation of a food or beverage item
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct Item {
     /// Unique identifier for the item
     pub id: String,
     
-    /// Name of the item
-    pub name: String,
+    /// Name of the item (plain or localized)
+    pub name: LocalizedText,
     
     /// Category of the item
     pub category: String,
@@ -166,9 +173,9 @@ pub struct Item {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub vendor_id: Option<String>,
     
-    /// Optional detailed description
+    /// Optional detailed description (plain or localized)
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub description: Option<String>,
+    pub description: Option<LocalizedText>,
     
     /// Optional subcategory
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -202,6 +209,10 @@ pub struct Item {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub quantity: Option<u32>,
     
+    /// Optional ID of the order line this item was ordered on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_id: Option<String>,
+    
     /// Optional note specific to this item
     #[serde(skip_serializing_if = "Option::is_none")]
     pub item_note: Option<String>,
@@ -221,10 +232,14 @@ pub struct Item {
     /// Optional popularity metrics
     #[serde(skip_serializing_if = "Option::is_none")]
     pub popularity: Option<Popularity>,
+    
+    /// Optional stock information
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stock: Option<StockInfo>,
 }
 
 /// Nutritional information about an item
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct Nutrition {
     /// Serving size information
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -284,7 +299,7 @@ pub struct Nutrition {
 }
 
 /// Measurement value with unit
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct MeasurementValue {
     /// Numeric value
     pub value: f64,
@@ -294,7 +309,7 @@ pub struct MeasurementValue {
 }
 
 /// Nutrient with detailed breakdown
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct NutrientWithDetails {
     /// Numeric value
     pub value: f64,
@@ -308,7 +323,7 @@ pub struct NutrientWithDetails {
 }
 
 /// Nutrient value, which can be either a simple measurement or another detailed breakdown
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 #[serde(untagged)]
 pub enum NutrientValue {
     Simple(MeasurementValue),
@@ -316,7 +331,7 @@ pub enum NutrientValue {
 }
 
 /// Vitamin or mineral nutrient information
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct VitaminMineral {
     /// Name of the vitamin or mineral
     pub name: String,
@@ -333,17 +348,81 @@ pub struct VitaminMineral {
 }
 
 /// Ingredient grouping
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct IngredientGroup {
     /// Name of the ingredient group
     pub name: String,
     
     /// List of ingredients in this group
-    pub ingredients: Vec<String>,
+    pub ingredients: Vec<IngredientEntry>,
+}
+
+/// Ingredient entry, which can be either a plain name or a reference to the ingredient catalog
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(untagged)]
+pub enum IngredientEntry {
+    Name(String),
+    Usage(IngredientUsage),
+}
+
+/// Reference to a catalog ingredient with the quantity used
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct IngredientUsage {
+    /// ID of the ingredient in the document's ingredient catalog
+    pub ingredient_id: String,
+    
+    /// Quantity of the ingredient used (mass)
+    pub quantity: MeasurementValue,
+}
+
+/// Structured ingredient definition
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct Ingredient {
+    /// Unique identifier for the ingredient
+    pub id: String,
+    
+    /// Name of the ingredient
+    pub name: String,
+    
+    /// Optional allergens contained in the ingredient
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allergens: Option<Vec<String>>,
+    
+    /// Optional dietary flags for the ingredient
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dietary_flags: Option<Vec<String>>,
+    
+    /// Optional basic nutrition per 100 grams
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nutrition_per_100g: Option<BasicNutrition>,
+}
+
+/// Basic nutrition values used for ingredient-level calculations
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct BasicNutrition {
+    /// Calories (kcal)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub calories: Option<f64>,
+    
+    /// Protein in grams
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protein: Option<f64>,
+    
+    /// Fat in grams
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fat: Option<f64>,
+    
+    /// Carbohydrates in grams
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub carbohydrates: Option<f64>,
+    
+    /// Sodium in milligrams
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sodium: Option<f64>,
 }
 
 /// Nutrition standards compliance information
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct NutritionStandards {
     /// US FDA compliance information
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -355,7 +434,7 @@ pub struct NutritionStandards {
 }
 
 /// US FDA nutrition information
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct UsFdaInfo {
     /// Serving size description
     pub serving_size_description: String,
@@ -365,20 +444,20 @@ pub struct UsFdaInfo {
 }
 
 /// EU regulation nutrition information
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct EuRegulationInfo {
     /// Reference intake description
     pub reference_intake_description: String,
 }
 
 /// Customization options for an item
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct Customization {
     /// Unique identifier for the customization
     pub id: String,
     
-    /// Name of the customization
-    pub name: String,
+    /// Name of the customization (plain or localized)
+    pub name: LocalizedText,
     
     /// Type of customization
     pub r#type: CustomizationType,
@@ -421,10 +500,24 @@ pub struct Customization {
     /// Available options for selection
     #[serde(skip_serializing_if = "Option::is_none")]
     pub options: Option<Vec<CustomizationOption>>,
+    
+    /// Conditions that must all hold for this customization to be shown
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visible_when: Option<Vec<CustomizationCondition>>,
+}
+
+/// Condition on another customization's selection
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct CustomizationCondition {
+    /// ID of the customization the condition depends on
+    pub customization_id: String,
+    
+    /// Option IDs, one of which must be selected (`true`/`false` for boolean customizations)
+    pub option_ids: Vec<String>,
 }
 
 /// Types of customizations
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum CustomizationType {
     SingleSelect,
@@ -465,7 +558,7 @@ impl fmt::Display for CustomizationType {
 }
 
 /// Default value for a customization, which varies by type
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 #[serde(untagged)]
 pub enum CustomizationDefault {
     String(String),
@@ -475,13 +568,13 @@ pub enum CustomizationDefault {
 }
 
 /// Individual option for a customization
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct CustomizationOption {
     /// Unique identifier for the option
     pub id: String,
     
-    /// Name of the option
-    pub name: String,
+    /// Name of the option (plain or localized)
+    pub name: LocalizedText,
     
     /// Optional price adjustment for selecting this option
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -498,20 +591,50 @@ pub struct CustomizationOption {
     /// Optional dietary flags for this option
     #[serde(skip_serializing_if = "Option::is_none")]
     pub dietary_flags: Option<Vec<String>>,
+    
+    /// Optional options that cannot be selected together with this one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub incompatible_with: Option<Vec<OptionReference>>,
+    
+    /// Optional customizations offered once this option is selected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub_customizations: Option<Vec<Customization>>,
+    
+    /// Optional stock information
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stock: Option<StockInfo>,
+    
+    /// Optional catalog ingredients added by this option
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ingredients: Option<Vec<IngredientUsage>>,
+}
+
+/// Reference to an option of a sibling customization
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct OptionReference {
+    /// ID of the customization the option belongs to
+    pub customization_id: String,
+    
+    /// ID of the option
+    pub option_id: String,
 }
 
 /// Selected customization for an item
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct SelectedCustomization {
     /// ID of the customization being selected
     pub customization_id: String,
     
     /// Selection value, which varies by customization type
     pub selection: CustomizationSelection,
+    
+    /// Optional selections for sub-customizations, keyed by selected option ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub_selections: Option<HashMap<String, Vec<SelectedCustomization>>>,
 }
 
 /// Value of a selected customization, which varies by type
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 #[serde(untagged)]
 pub enum CustomizationSelection {
     String(String),
@@ -521,7 +644,7 @@ pub enum CustomizationSelection {
 }
 
 /// Calculated values based on customizations
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct CalculatedValues {
     /// Calculated item price after customizations
     pub item_price: f64,
@@ -532,7 +655,7 @@ pub struct CalculatedValues {
 }
 
 /// Item availability information
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct Availability {
     /// Optional start date for seasonal items
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -552,7 +675,7 @@ pub struct Availability {
 }
 
 /// Item popularity metrics
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct Popularity {
     /// Optional ranking among menu items
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -563,8 +686,32 @@ pub struct Popularity {
     pub tags: Option<Vec<String>>,
 }
 
+/// Stock information for an item or option
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct StockInfo {
+    /// Current stock status
+    pub status: StockStatus,
+    
+    /// Optional number of units remaining
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quantity: Option<u32>,
+    
+    /// Optional time when a sold out entry is expected back in stock
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restock_time: Option<DateTime<Utc>>,
+}
+
+/// Stock status
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StockStatus {
+    InStock,
+    Low,
+    SoldOut,
+}
+
 /// Order information
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct Order {
     /// Unique identifier for the order
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -605,10 +752,38 @@ pub struct Order {
     /// Delivery information
     #[serde(skip_serializing_if = "Option::is_none")]
     pub delivery: Option<Delivery>,
+    
+    /// Exchange rates applied to convert item prices into the payment currency
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exchange_rates: Option<Vec<ExchangeRateRecord>>,
+}
+
+/// Record of an exchange rate applied to an order
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct ExchangeRateRecord {
+    /// Currency converted from (ISO 4217)
+    pub from: String,
+    
+    /// Currency converted to (ISO 4217)
+    pub to: String,
+    
+    /// Units of `to` per unit of `from`
+    pub rate: f64,
+    
+    /// Where the rate came from (e.g. a provider name)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    
+    /// When the rate was quoted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub as_of: Option<DateTime<Utc>>,
+    
+    /// When the rate was applied to the order
+    pub applied_at: DateTime<Utc>,
 }
 
 /// Order status
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum OrderStatus {
     Draft,
@@ -621,7 +796,7 @@ pub enum OrderStatus {
 }
 
 /// Order type
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum OrderType {
     Pickup,
@@ -630,7 +805,7 @@ pub enum OrderType {
 }
 
 /// Payment information
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct Payment {
     /// Payment status
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -660,7 +835,7 @@ pub struct Payment {
 }
 
 /// Payment status
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum PaymentStatus {
     Unpaid,
@@ -668,7 +843,7 @@ pub enum PaymentStatus {
 }
 
 /// Customer information
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct Customer {
     /// Unique identifier for the customer
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -688,7 +863,7 @@ pub struct Customer {
 }
 
 /// Delivery information
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct Delivery {
     /// Delivery address
     pub address: Address,
@@ -701,6 +876,16 @@ pub struct Delivery {
 /// Extensions for vendor-specific or future extensions
 pub type Extensions = HashMap<String, serde_json::Value>;
 
+/// Text that is either a plain string or translations keyed by language tag
+///
+/// A plain string is in the document's `metadata.locale`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(untagged)]
+pub enum LocalizedText {
+    Plain(String),
+    Translations(HashMap<String, String>),
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
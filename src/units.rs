@@ -0,0 +1,495 @@
+// src/units.rs
+//
+// Unit system for measurement values: parsing, conversion and arithmetic
+
+use crate::types::*;
+use crate::{OmsError, OmsResult};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Kilojoules per kilocalorie
+pub const KJ_PER_KCAL: f64 = 4.184;
+
+/// Physical dimension of a unit; only units of the same dimension can be converted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dimension {
+    Mass,
+    Volume,
+    Energy,
+    InternationalUnit,
+}
+
+/// Units supported for measurement values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Unit {
+    Microgram,
+    Milligram,
+    Gram,
+    Kilogram,
+    Ounce,
+    Pound,
+    Milliliter,
+    Centiliter,
+    Deciliter,
+    Liter,
+    Teaspoon,
+    Tablespoon,
+    FluidOunce,
+    Cup,
+    Kilocalorie,
+    Kilojoule,
+    InternationalUnit,
+}
+
+impl Unit {
+    /// Dimension of the unit
+    pub fn dimension(&self) -> Dimension {
+        match self {
+            Unit::Microgram | Unit::Milligram | Unit::Gram | Unit::Kilogram | Unit::Ounce | Unit::Pound => {
+                Dimension::Mass
+            },
+            Unit::Milliliter | Unit::Centiliter | Unit::Deciliter | Unit::Liter | Unit::Teaspoon
+            | Unit::Tablespoon | Unit::FluidOunce | Unit::Cup => Dimension::Volume,
+            Unit::Kilocalorie | Unit::Kilojoule => Dimension::Energy,
+            Unit::InternationalUnit => Dimension::InternationalUnit,
+        }
+    }
+
+    /// Size of the unit in the base unit of its dimension (g, ml, kcal or IU)
+    fn base_factor(&self) -> f64 {
+        match self {
+            Unit::Microgram => 0.000_001,
+            Unit::Milligram => 0.001,
+            Unit::Gram => 1.0,
+            Unit::Kilogram => 1000.0,
+            Unit::Ounce => 28.349_523_125,
+            Unit::Pound => 453.592_37,
+            Unit::Milliliter => 1.0,
+            Unit::Centiliter => 10.0,
+            Unit::Deciliter => 100.0,
+            Unit::Liter => 1000.0,
+            Unit::Teaspoon => 4.928_921_593_75,
+            Unit::Tablespoon => 14.786_764_781_25,
+            Unit::FluidOunce => 29.573_529_562_5,
+            Unit::Cup => 236.588_236_5,
+            Unit::Kilocalorie => 1.0,
+            Unit::Kilojoule => 1.0 / KJ_PER_KCAL,
+            Unit::InternationalUnit => 1.0,
+        }
+    }
+
+    /// Convert a value in this unit to another unit of the same dimension
+    pub fn convert(&self, value: f64, to: Unit) -> OmsResult<f64> {
+        if self.dimension() != to.dimension() {
+            return Err(OmsError::IncompatibleUnits(format!("cannot convert {} to {}", self, to)));
+        }
+
+        if *self == to {
+            return Ok(value);
+        }
+
+        Ok(value * self.base_factor() / to.base_factor())
+    }
+}
+
+impl FromStr for Unit {
+    type Err = OmsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "mcg" | "µg" | "μg" | "ug" | "microgram" | "micrograms" => Ok(Unit::Microgram),
+            "mg" | "milligram" | "milligrams" => Ok(Unit::Milligram),
+            "g" | "gram" | "grams" => Ok(Unit::Gram),
+            "kg" | "kilogram" | "kilograms" => Ok(Unit::Kilogram),
+            "oz" | "ounce" | "ounces" => Ok(Unit::Ounce),
+            "lb" | "lbs" | "pound" | "pounds" => Ok(Unit::Pound),
+            "ml" | "milliliter" | "milliliters" | "millilitre" | "millilitres" => Ok(Unit::Milliliter),
+            "cl" | "centiliter" | "centiliters" | "centilitre" | "centilitres" => Ok(Unit::Centiliter),
+            "dl" | "deciliter" | "deciliters" | "decilitre" | "decilitres" => Ok(Unit::Deciliter),
+            "l" | "liter" | "liters" | "litre" | "litres" => Ok(Unit::Liter),
+            "tsp" | "teaspoon" | "teaspoons" => Ok(Unit::Teaspoon),
+            "tbsp" | "tablespoon" | "tablespoons" => Ok(Unit::Tablespoon),
+            "fl oz" | "fl_oz" | "floz" | "fluid ounce" | "fluid ounces" => Ok(Unit::FluidOunce),
+            "cup" | "cups" => Ok(Unit::Cup),
+            "kcal" | "cal" | "calorie" | "calories" | "kilocalorie" | "kilocalories" => Ok(Unit::Kilocalorie),
+            "kj" | "kilojoule" | "kilojoules" => Ok(Unit::Kilojoule),
+            "iu" => Ok(Unit::InternationalUnit),
+            _ => Err(OmsError::InvalidUnit(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Unit::Microgram => write!(f, "mcg"),
+            Unit::Milligram => write!(f, "mg"),
+            Unit::Gram => write!(f, "g"),
+            Unit::Kilogram => write!(f, "kg"),
+            Unit::Ounce => write!(f, "oz"),
+            Unit::Pound => write!(f, "lb"),
+            Unit::Milliliter => write!(f, "ml"),
+            Unit::Centiliter => write!(f, "cl"),
+            Unit::Deciliter => write!(f, "dl"),
+            Unit::Liter => write!(f, "l"),
+            Unit::Teaspoon => write!(f, "tsp"),
+            Unit::Tablespoon => write!(f, "tbsp"),
+            Unit::FluidOunce => write!(f, "fl oz"),
+            Unit::Cup => write!(f, "cup"),
+            Unit::Kilocalorie => write!(f, "kcal"),
+            Unit::Kilojoule => write!(f, "kJ"),
+            Unit::InternationalUnit => write!(f, "IU"),
+        }
+    }
+}
+
+impl MeasurementValue {
+    /// Create a measurement value from a value and a unit
+    pub fn new(value: f64, unit: Unit) -> Self {
+        Self {
+            value,
+            unit: unit.to_string(),
+        }
+    }
+
+    /// Parse a measurement such as `"500 mg"` or `"1.2g"`
+    pub fn parse(s: &str) -> OmsResult<Self> {
+        let s = s.trim();
+        let split = s
+            .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+'))
+            .unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+
+        let value = number.parse::<f64>()
+            .map_err(|_| OmsError::InvalidFieldValue(format!("invalid measurement value '{}'", s)))?;
+        let unit = Unit::from_str(unit)?;
+
+        Ok(Self::new(value, unit))
+    }
+
+    /// Get the parsed unit of the measurement
+    pub fn parsed_unit(&self) -> OmsResult<Unit> {
+        Unit::from_str(&self.unit)
+    }
+
+    /// Convert the measurement to another unit of the same dimension
+    pub fn convert_to(&self, unit: Unit) -> OmsResult<Self> {
+        let value = self.parsed_unit()?.convert(self.value, unit)?;
+        Ok(Self::new(value, unit))
+    }
+
+    /// Add another measurement, expressing the result in this measurement's unit
+    pub fn try_add(&self, other: &MeasurementValue) -> OmsResult<Self> {
+        let unit = self.parsed_unit()?;
+        let other_value = other.parsed_unit()?.convert(other.value, unit)?;
+        Ok(Self {
+            value: self.value + other_value,
+            unit: self.unit.clone(),
+        })
+    }
+
+    /// Multiply the measurement by a factor
+    pub fn scale(&self, factor: f64) -> Self {
+        Self {
+            value: self.value * factor,
+            unit: self.unit.clone(),
+        }
+    }
+}
+
+impl NutrientWithDetails {
+    /// Get the top-level value as a measurement
+    pub fn measurement(&self) -> MeasurementValue {
+        MeasurementValue {
+            value: self.value,
+            unit: self.unit.clone(),
+        }
+    }
+
+    /// Convert the nutrient and its breakdown to another unit of the same dimension
+    pub fn convert_to(&self, unit: Unit) -> OmsResult<Self> {
+        let details = match &self.details {
+            Some(details) => Some(details.iter()
+                .map(|(name, value)| Ok((name.clone(), value.convert_to(unit)?)))
+                .collect::<OmsResult<HashMap<_, _>>>()?),
+            None => None,
+        };
+
+        Ok(Self {
+            value: self.measurement().convert_to(unit)?.value,
+            unit: unit.to_string(),
+            details,
+        })
+    }
+
+    /// Add another nutrient, combining breakdowns entry by entry
+    pub fn try_add(&self, other: &NutrientWithDetails) -> OmsResult<Self> {
+        let value = self.measurement().try_add(&other.measurement())?.value;
+
+        let details = match (&self.details, &other.details) {
+            (Some(ours), Some(theirs)) => {
+                let mut combined = ours.clone();
+                for (name, value) in theirs {
+                    let sum = match combined.get(name) {
+                        Some(existing) => existing.try_add(value)?,
+                        None => value.clone(),
+                    };
+                    combined.insert(name.clone(), sum);
+                }
+                Some(combined)
+            },
+            (Some(details), None) | (None, Some(details)) => Some(details.clone()),
+            (None, None) => None,
+        };
+
+        Ok(Self {
+            value,
+            unit: self.unit.clone(),
+            details,
+        })
+    }
+
+    /// Multiply the nutrient and its breakdown by a factor
+    pub fn scale(&self, factor: f64) -> Self {
+        Self {
+            value: self.value * factor,
+            unit: self.unit.clone(),
+            details: self.details.as_ref().map(|details| {
+                details.iter()
+                    .map(|(name, value)| (name.clone(), value.scale(factor)))
+                    .collect()
+            }),
+        }
+    }
+}
+
+impl NutrientValue {
+    /// Get the top-level value as a measurement
+    pub fn measurement(&self) -> MeasurementValue {
+        match self {
+            NutrientValue::Simple(value) => value.clone(),
+            NutrientValue::Detailed(value) => value.measurement(),
+        }
+    }
+
+    /// Convert the value to another unit of the same dimension
+    pub fn convert_to(&self, unit: Unit) -> OmsResult<Self> {
+        match self {
+            NutrientValue::Simple(value) => Ok(NutrientValue::Simple(value.convert_to(unit)?)),
+            NutrientValue::Detailed(value) => Ok(NutrientValue::Detailed(value.convert_to(unit)?)),
+        }
+    }
+
+    /// Add another value, expressing the result in this value's unit
+    pub fn try_add(&self, other: &NutrientValue) -> OmsResult<Self> {
+        match (self, other) {
+            (NutrientValue::Detailed(ours), NutrientValue::Detailed(theirs)) => {
+                Ok(NutrientValue::Detailed(ours.try_add(theirs)?))
+            },
+            (NutrientValue::Detailed(ours), NutrientValue::Simple(theirs)) => {
+                Ok(NutrientValue::Detailed(NutrientWithDetails {
+                    value: ours.measurement().try_add(theirs)?.value,
+                    unit: ours.unit.clone(),
+                    details: ours.details.clone(),
+                }))
+            },
+            (NutrientValue::Simple(ours), _) => {
+                Ok(NutrientValue::Simple(ours.try_add(&other.measurement())?))
+            },
+        }
+    }
+
+    /// Multiply the value by a factor
+    pub fn scale(&self, factor: f64) -> Self {
+        match self {
+            NutrientValue::Simple(value) => NutrientValue::Simple(value.scale(factor)),
+            NutrientValue::Detailed(value) => NutrientValue::Detailed(value.scale(factor)),
+        }
+    }
+}
+
+impl Nutrition {
+    /// Get the energy per serving as a measurement in kilocalories
+    pub fn energy(&self) -> Option<MeasurementValue> {
+        self.calories.map(|calories| MeasurementValue::new(calories, Unit::Kilocalorie))
+    }
+
+    /// Multiply every amount (including the serving size) by a factor
+    pub fn scale(&self, factor: f64) -> Self {
+        let scale_vitamins = |list: &Option<Vec<VitaminMineral>>| {
+            list.as_ref().map(|list| {
+                list.iter()
+                    .map(|entry| VitaminMineral {
+                        name: entry.name.clone(),
+                        value: entry.value * factor,
+                        unit: entry.unit.clone(),
+                        daily_value_percent: entry.daily_value_percent.map(|percent| percent * factor),
+                    })
+                    .collect()
+            })
+        };
+
+        Self {
+            serving_size: self.serving_size.as_ref().map(|size| size.scale(factor)),
+            calories: self.calories.map(|calories| calories * factor),
+            protein: self.protein.as_ref().map(|value| value.scale(factor)),
+            fat: self.fat.as_ref().map(|value| value.scale(factor)),
+            carbohydrates: self.carbohydrates.as_ref().map(|value| value.scale(factor)),
+            sodium: self.sodium.as_ref().map(|value| value.scale(factor)),
+            cholesterol: self.cholesterol.as_ref().map(|value| value.scale(factor)),
+            vitamins: scale_vitamins(&self.vitamins),
+            minerals: scale_vitamins(&self.minerals),
+            allergens: self.allergens.clone(),
+            dietary_flags: self.dietary_flags.clone(),
+            health_claims: self.health_claims.clone(),
+            ingredients: self.ingredients.clone(),
+            nutrition_standards: self.nutrition_standards.clone(),
+        }
+    }
+
+    /// Rescale the nutrition to a different serving size
+    pub fn scale_to_serving(&self, serving_size: &MeasurementValue) -> OmsResult<Self> {
+        let current = self.serving_size.as_ref()
+            .ok_or_else(|| OmsError::MissingRequiredField("nutrition.serving_size".to_string()))?;
+
+        if current.value <= 0.0 {
+            return Err(OmsError::InvalidFieldValue("serving size must be greater than zero".to_string()));
+        }
+
+        let target = serving_size.convert_to(current.parsed_unit()?)?;
+        let mut scaled = self.scale(target.value / current.value);
+        scaled.serving_size = Some(serving_size.clone());
+        Ok(scaled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn test_parse_units() {
+        assert_eq!(Unit::from_str("mg").unwrap(), Unit::Milligram);
+        assert_eq!(Unit::from_str("kJ").unwrap(), Unit::Kilojoule);
+        assert_eq!(Unit::from_str("fl oz").unwrap(), Unit::FluidOunce);
+        assert_eq!(Unit::from_str("IU").unwrap(), Unit::InternationalUnit);
+        assert!(Unit::from_str("smidgen").is_err());
+
+        let value = MeasurementValue::parse("500 mg").unwrap();
+        assert_eq!(value, MeasurementValue { value: 500.0, unit: "mg".to_string() });
+
+        let value = MeasurementValue::parse("1.2g").unwrap();
+        assert_eq!(value.parsed_unit().unwrap(), Unit::Gram);
+        assert_close(value.value, 1.2);
+
+        assert!(MeasurementValue::parse("mg").is_err());
+        assert!(MeasurementValue::parse("12 parsecs").is_err());
+    }
+
+    #[test]
+    fn test_convert_and_add() {
+        let sodium = MeasurementValue::parse("500 mg").unwrap();
+        let more_sodium = MeasurementValue::parse("1.2 g").unwrap();
+
+        let total = sodium.try_add(&more_sodium).unwrap();
+        assert_eq!(total.unit, "mg");
+        assert_close(total.value, 1700.0);
+        assert_close(total.convert_to(Unit::Gram).unwrap().value, 1.7);
+
+        let energy = MeasurementValue::new(250.0, Unit::Kilocalorie);
+        assert_close(energy.convert_to(Unit::Kilojoule).unwrap().value, 1046.0);
+
+        assert_close(MeasurementValue::parse("1 cup").unwrap().convert_to(Unit::Milliliter).unwrap().value, 236.5882365);
+
+        // Different dimensions cannot be combined
+        assert!(sodium.try_add(&MeasurementValue::parse("5 ml").unwrap()).is_err());
+        assert!(MeasurementValue::parse("400 IU").unwrap().convert_to(Unit::Microgram).is_err());
+    }
+
+    #[test]
+    fn test_nutrient_details_arithmetic() {
+        let fat = NutrientWithDetails {
+            value: 10.0,
+            unit: "g".to_string(),
+            details: Some(HashMap::from([
+                ("saturated".to_string(), NutrientValue::Simple(MeasurementValue::new(4.0, Unit::Gram))),
+            ])),
+        };
+        let more_fat = NutrientWithDetails {
+            value: 500.0,
+            unit: "mg".to_string(),
+            details: Some(HashMap::from([
+                ("saturated".to_string(), NutrientValue::Simple(MeasurementValue::new(250.0, Unit::Milligram))),
+                ("trans".to_string(), NutrientValue::Simple(MeasurementValue::new(100.0, Unit::Milligram))),
+            ])),
+        };
+
+        let total = fat.try_add(&more_fat).unwrap();
+        assert_close(total.value, 10.5);
+        let details = total.details.as_ref().unwrap();
+        assert_close(details["saturated"].measurement().value, 4.25);
+        assert_eq!(details["trans"].measurement().unit, "mg");
+
+        let doubled = total.scale(2.0).convert_to(Unit::Milligram).unwrap();
+        assert_close(doubled.value, 21000.0);
+        assert_close(doubled.details.as_ref().unwrap()["saturated"].measurement().value, 8500.0);
+    }
+
+    #[test]
+    fn test_scale_to_serving() {
+        let nutrition = Nutrition {
+            serving_size: Some(MeasurementValue::new(100.0, Unit::Gram)),
+            calories: Some(250.0),
+            protein: Some(MeasurementValue::new(10.0, Unit::Gram)),
+            fat: None,
+            carbohydrates: None,
+            sodium: Some(MeasurementValue::new(400.0, Unit::Milligram)),
+            cholesterol: None,
+            vitamins: Some(vec![VitaminMineral {
+                name: "Vitamin D".to_string(),
+                value: 40.0,
+                unit: "IU".to_string(),
+                daily_value_percent: Some(5.0),
+            }]),
+            minerals: None,
+            allergens: None,
+            dietary_flags: None,
+            health_claims: None,
+            ingredients: None,
+            nutrition_standards: None,
+        };
+
+        let scaled = nutrition.scale_to_serving(&MeasurementValue::new(8.0, Unit::Ounce)).unwrap();
+        let factor = 8.0 * 28.349523125 / 100.0;
+        assert_eq!(scaled.serving_size.as_ref().unwrap().unit, "oz");
+        assert_close(scaled.calories.unwrap(), 250.0 * factor);
+        assert_close(scaled.sodium.as_ref().unwrap().value, 400.0 * factor);
+        assert_close(scaled.vitamins.as_ref().unwrap()[0].value, 40.0 * factor);
+
+        assert_close(scaled.energy().unwrap().convert_to(Unit::Kilojoule).unwrap().value, 250.0 * factor * KJ_PER_KCAL);
+        assert!(nutrition.scale_to_serving(&MeasurementValue::new(1.0, Unit::Cup)).is_err());
+    }
+
+    #[test]
+    fn test_validation_rejects_unknown_units() {
+        let mut doc = crate::utils::create_template("coffee-shop").unwrap();
+        let set_calcium = |doc: &mut OmsDocument, unit: &str| {
+            let milk = &mut doc.items[0].customizations.as_mut().unwrap()[1];
+            milk.options.as_mut().unwrap()[0].nutrition_adjustments = Some(HashMap::from([(
+                "calcium".to_string(),
+                NutrientValue::Simple(MeasurementValue { value: 120.0, unit: unit.to_string() }),
+            )]));
+        };
+
+        set_calcium(&mut doc, "mg");
+        assert!(doc.validate().is_ok());
+
+        set_calcium(&mut doc, "mgs");
+        assert!(matches!(doc.validate(), Err(OmsError::InvalidUnit(unit)) if unit == "mgs"));
+    }
+}
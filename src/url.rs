@@ -6,6 +6,8 @@ use crate::{OMS_URL_SCHEME, OmsError, OmsResult};
 use crate::types::OmsDocument;
 use std::collections::HashMap;
 use url::Url;
+#[cfg(feature = "network")]
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 
 /// Parse an OMS URL and extract the parameters
 pub fn parse_oms_url(url: &str) -> OmsResult<HashMap<String, String>> {
@@ -131,14 +133,14 @@ pub fn add_custom_params(url: &str, params: &HashMap<String, String>) -> OmsResu
 #[cfg(feature = "network")]
 pub fn encode_document_as_param(document: &OmsDocument) -> OmsResult<String> {
     let json = document.to_compact_json()?;
-    let encoded = base64::encode(json);
+    let encoded = BASE64.encode(json);
     Ok(encoded)
 }
 
 /// Decode a base64-encoded OMS document from a URL parameter
 #[cfg(feature = "network")]
 pub fn decode_document_from_param(encoded: &str) -> OmsResult<OmsDocument> {
-    let json = base64::decode(encoded)
+    let json = BASE64.decode(encoded)
         .map_err(|_| OmsError::InvalidFieldValue("Invalid base64 encoding".to_string()))?;
     
     let json_str = String::from_utf8(json)
@@ -260,7 +262,7 @@ mod tests {
             vec![
                 Item {
                     id: "test-item".to_string(),
-                    name: "Test Item".to_string(),
+                    name: "Test Item".into(),
                     category: "test".to_string(),
                     vendor_id: None,
                     description: None,
@@ -272,11 +274,13 @@ mod tests {
                     customizations: None,
                     selected_customizations: None,
                     quantity: None,
+                    line_id: None,
                     item_note: None,
                     calculated: None,
                     components: None,
                     availability: None,
                     popularity: None,
+                    stock: None,
                 },
             ],
         );
@@ -2,14 +2,16 @@
 //
 // Utility functions for working with OMS documents
 
-use crate::document::parse_oms_document;
+use crate::currency::{round_to_currency, ExchangeRates, DEFAULT_CURRENCY};
+use crate::formats::DocumentFormat;
 use crate::types::*;
 use crate::url::parse_oms_url;
-use crate::{OmsError, OmsResult, OMS_VERSION};
+use crate::validation::is_customization_visible;
+use crate::{OmsError, OmsResult};
 use chrono::Utc;
 use std::collections::HashMap;
 use std::fs;
-use std::io::{Read, Write};
+use std::io::Read;
 use std::path::Path;
 
 /// Create a minimal OMS document with basic fields
@@ -42,7 +44,7 @@ pub fn create_minimal_document(
 
     let item = Item {
         id: item_id.to_string(),
-        name: item_name.to_string(),
+        name: item_name.into(),
         category: item_category.to_string(),
         vendor_id: None,
         description: None,
@@ -54,11 +56,13 @@ pub fn create_minimal_document(
         customizations: None,
         selected_customizations: None,
         quantity: None,
+        line_id: None,
         item_note: None,
         calculated: None,
         components: None,
         availability: None,
         popularity: None,
+        stock: None,
     };
 
     let document = OmsDocument::new(metadata, vendor, vec![item]);
@@ -102,7 +106,7 @@ fn create_restaurant_template() -> OmsResult<OmsDocument> {
     // Create a customization for cooking preference
     let cooking_pref = Customization {
         id: "cooking-pref".to_string(),
-        name: "Cooking Preference".to_string(),
+        name: "Cooking Preference".into(),
         r#type: CustomizationType::SingleSelect,
         required: true,
         default: CustomizationDefault::String("medium".to_string()),
@@ -116,51 +120,72 @@ fn create_restaurant_template() -> OmsResult<OmsDocument> {
         options: Some(vec![
             CustomizationOption {
                 id: "rare".to_string(),
-                name: "Rare".to_string(),
+                name: "Rare".into(),
                 price_adjustment: None,
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "medium-rare".to_string(),
-                name: "Medium Rare".to_string(),
+                name: "Medium Rare".into(),
                 price_adjustment: None,
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "medium".to_string(),
-                name: "Medium".to_string(),
+                name: "Medium".into(),
                 price_adjustment: None,
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "medium-well".to_string(),
-                name: "Medium Well".to_string(),
+                name: "Medium Well".into(),
                 price_adjustment: None,
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "well-done".to_string(),
-                name: "Well Done".to_string(),
+                name: "Well Done".into(),
                 price_adjustment: None,
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
         ]),
+        visible_when: None,
     };
 
     // Create a customization for sides
     let sides = Customization {
         id: "side".to_string(),
-        name: "Side".to_string(),
+        name: "Side".into(),
         r#type: CustomizationType::SingleSelect,
         required: true,
         default: CustomizationDefault::String("fries".to_string()),
@@ -174,38 +199,51 @@ fn create_restaurant_template() -> OmsResult<OmsDocument> {
         options: Some(vec![
             CustomizationOption {
                 id: "fries".to_string(),
-                name: "French Fries".to_string(),
+                name: "French Fries".into(),
                 price_adjustment: None,
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "salad".to_string(),
-                name: "House Salad".to_string(),
+                name: "House Salad".into(),
                 price_adjustment: None,
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "soup".to_string(),
-                name: "Soup of the Day".to_string(),
+                name: "Soup of the Day".into(),
                 price_adjustment: None,
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
         ]),
+        visible_when: None,
     };
 
     // Create an item
     let steak = Item {
         id: "steak".to_string(),
-        name: "New York Strip Steak".to_string(),
+        name: "New York Strip Steak".into(),
         category: "entree".to_string(),
         vendor_id: None,
-        description: Some("12oz New York Strip steak with choice of side".to_string()),
+        description: Some("12oz New York Strip steak with choice of side".into()),
         subcategory: None,
         image_url: None,
         base_price: Some(29.99),
@@ -214,11 +252,13 @@ fn create_restaurant_template() -> OmsResult<OmsDocument> {
         customizations: Some(vec![cooking_pref, sides]),
         selected_customizations: None,
         quantity: None,
+        line_id: None,
         item_note: None,
         calculated: None,
         components: None,
         availability: None,
         popularity: None,
+        stock: None,
     };
 
     Ok(OmsDocument::new(metadata, vendor, vec![steak]))
@@ -248,7 +288,7 @@ fn create_cafe_template() -> OmsResult<OmsDocument> {
     // Create a customization for bread type
     let bread = Customization {
         id: "bread".to_string(),
-        name: "Bread".to_string(),
+        name: "Bread".into(),
         r#type: CustomizationType::SingleSelect,
         required: true,
         default: CustomizationDefault::String("wheat".to_string()),
@@ -262,35 +302,48 @@ fn create_cafe_template() -> OmsResult<OmsDocument> {
         options: Some(vec![
             CustomizationOption {
                 id: "wheat".to_string(),
-                name: "Wheat".to_string(),
+                name: "Wheat".into(),
                 price_adjustment: None,
                 nutrition_adjustments: None,
                 allergens: Some(vec!["wheat".to_string()]),
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "white".to_string(),
-                name: "White".to_string(),
+                name: "White".into(),
                 price_adjustment: None,
                 nutrition_adjustments: None,
                 allergens: Some(vec!["wheat".to_string()]),
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "rye".to_string(),
-                name: "Rye".to_string(),
+                name: "Rye".into(),
                 price_adjustment: None,
                 nutrition_adjustments: None,
                 allergens: Some(vec!["wheat".to_string()]),
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
         ]),
+        visible_when: None,
     };
 
     // Create a customization for cheese
     let cheese = Customization {
         id: "cheese".to_string(),
-        name: "Cheese".to_string(),
+        name: "Cheese".into(),
         r#type: CustomizationType::SingleSelect,
         required: false,
         default: CustomizationDefault::String("cheddar".to_string()),
@@ -304,40 +357,53 @@ fn create_cafe_template() -> OmsResult<OmsDocument> {
         options: Some(vec![
             CustomizationOption {
                 id: "cheddar".to_string(),
-                name: "Cheddar".to_string(),
+                name: "Cheddar".into(),
                 price_adjustment: None,
                 nutrition_adjustments: None,
                 allergens: Some(vec!["dairy".to_string()]),
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "swiss".to_string(),
-                name: "Swiss".to_string(),
+                name: "Swiss".into(),
                 price_adjustment: None,
                 nutrition_adjustments: None,
                 allergens: Some(vec!["dairy".to_string()]),
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "none".to_string(),
-                name: "No Cheese".to_string(),
+                name: "No Cheese".into(),
                 price_adjustment: None,
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: Some(vec!["dairy_free".to_string()]),
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
         ]),
+        visible_when: None,
     };
 
     // Create an item
     let sandwich = Item {
         id: "turkey-sandwich".to_string(),
-        name: "Turkey Sandwich".to_string(),
+        name: "Turkey Sandwich".into(),
         category: "sandwich".to_string(),
         vendor_id: None,
         description: Some(
             "Roasted turkey breast with lettuce, tomato, and choice of cheese and bread"
-                .to_string(),
+                .into(),
         ),
         subcategory: None,
         image_url: None,
@@ -347,11 +413,13 @@ fn create_cafe_template() -> OmsResult<OmsDocument> {
         customizations: Some(vec![bread, cheese]),
         selected_customizations: None,
         quantity: None,
+        line_id: None,
         item_note: None,
         calculated: None,
         components: None,
         availability: None,
         popularity: None,
+        stock: None,
     };
 
     Ok(OmsDocument::new(metadata, vendor, vec![sandwich]))
@@ -381,11 +449,11 @@ fn create_fast_food_template() -> OmsResult<OmsDocument> {
     // Create a combo meal with components
     let burger = Item {
         id: "burger".to_string(),
-        name: "Cheeseburger".to_string(),
+        name: "Cheeseburger".into(),
         category: "burger".to_string(),
         vendor_id: None,
         description: Some(
-            "Quarter-pound beef patty with cheese, lettuce, tomato, and special sauce".to_string(),
+            "Quarter-pound beef patty with cheese, lettuce, tomato, and special sauce".into(),
         ),
         subcategory: None,
         image_url: None,
@@ -395,17 +463,19 @@ fn create_fast_food_template() -> OmsResult<OmsDocument> {
         customizations: None,
         selected_customizations: None,
         quantity: None,
+        line_id: None,
         item_note: None,
         calculated: None,
         components: None,
         availability: None,
         popularity: None,
+        stock: None,
     };
 
     // Create drink customization
     let drink = Customization {
         id: "drink".to_string(),
-        name: "Drink".to_string(),
+        name: "Drink".into(),
         r#type: CustomizationType::SingleSelect,
         required: true,
         default: CustomizationDefault::String("cola".to_string()),
@@ -419,35 +489,48 @@ fn create_fast_food_template() -> OmsResult<OmsDocument> {
         options: Some(vec![
             CustomizationOption {
                 id: "cola".to_string(),
-                name: "Cola".to_string(),
+                name: "Cola".into(),
                 price_adjustment: None,
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "diet-cola".to_string(),
-                name: "Diet Cola".to_string(),
+                name: "Diet Cola".into(),
                 price_adjustment: None,
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "lemon-lime".to_string(),
-                name: "Lemon-Lime Soda".to_string(),
+                name: "Lemon-Lime Soda".into(),
                 price_adjustment: None,
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
         ]),
+        visible_when: None,
     };
 
     // Create side customization
     let side = Customization {
         id: "side".to_string(),
-        name: "Side".to_string(),
+        name: "Side".into(),
         r#type: CustomizationType::SingleSelect,
         required: true,
         default: CustomizationDefault::String("fries".to_string()),
@@ -461,30 +544,39 @@ fn create_fast_food_template() -> OmsResult<OmsDocument> {
         options: Some(vec![
             CustomizationOption {
                 id: "fries".to_string(),
-                name: "French Fries".to_string(),
+                name: "French Fries".into(),
                 price_adjustment: None,
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "onion-rings".to_string(),
-                name: "Onion Rings".to_string(),
+                name: "Onion Rings".into(),
                 price_adjustment: Some(1.00),
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
         ]),
+        visible_when: None,
     };
 
     // Create an item with components
     let combo = Item {
         id: "combo".to_string(),
-        name: "Cheeseburger Combo".to_string(),
+        name: "Cheeseburger Combo".into(),
         category: "combo".to_string(),
         vendor_id: None,
-        description: Some("Cheeseburger with fries and a drink".to_string()),
+        description: Some("Cheeseburger with fries and a drink".into()),
         subcategory: None,
         image_url: None,
         base_price: Some(7.99),
@@ -493,11 +585,13 @@ fn create_fast_food_template() -> OmsResult<OmsDocument> {
         customizations: Some(vec![drink, side]),
         selected_customizations: None,
         quantity: None,
+        line_id: None,
         item_note: None,
         calculated: None,
         components: Some(vec![burger]),
         availability: None,
         popularity: None,
+        stock: None,
     };
 
     Ok(OmsDocument::new(metadata, vendor, vec![combo]))
@@ -527,7 +621,7 @@ fn create_coffee_shop_template() -> OmsResult<OmsDocument> {
     // Create size customization
     let size = Customization {
         id: "size".to_string(),
-        name: "Size".to_string(),
+        name: "Size".into(),
         r#type: CustomizationType::SingleSelect,
         required: true,
         default: CustomizationDefault::String("medium".to_string()),
@@ -541,35 +635,48 @@ fn create_coffee_shop_template() -> OmsResult<OmsDocument> {
         options: Some(vec![
             CustomizationOption {
                 id: "small".to_string(),
-                name: "Small (12oz)".to_string(),
+                name: "Small (12oz)".into(),
                 price_adjustment: Some(-0.50),
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "medium".to_string(),
-                name: "Medium (16oz)".to_string(),
+                name: "Medium (16oz)".into(),
                 price_adjustment: Some(0.0),
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "large".to_string(),
-                name: "Large (20oz)".to_string(),
+                name: "Large (20oz)".into(),
                 price_adjustment: Some(0.50),
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
         ]),
+        visible_when: None,
     };
 
     // Create milk customization
     let milk = Customization {
         id: "milk".to_string(),
-        name: "Milk".to_string(),
+        name: "Milk".into(),
         r#type: CustomizationType::SingleSelect,
         required: true,
         default: CustomizationDefault::String("whole".to_string()),
@@ -583,43 +690,60 @@ fn create_coffee_shop_template() -> OmsResult<OmsDocument> {
         options: Some(vec![
             CustomizationOption {
                 id: "whole".to_string(),
-                name: "Whole Milk".to_string(),
+                name: "Whole Milk".into(),
                 price_adjustment: Some(0.0),
                 nutrition_adjustments: None,
                 allergens: Some(vec!["dairy".to_string()]),
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "skim".to_string(),
-                name: "Skim Milk".to_string(),
+                name: "Skim Milk".into(),
                 price_adjustment: Some(0.0),
                 nutrition_adjustments: None,
                 allergens: Some(vec!["dairy".to_string()]),
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "almond".to_string(),
-                name: "Almond Milk".to_string(),
+                name: "Almond Milk".into(),
                 price_adjustment: Some(0.75),
                 nutrition_adjustments: None,
                 allergens: Some(vec!["tree-nuts".to_string()]),
                 dietary_flags: Some(vec!["dairy_free".to_string(), "vegan".to_string()]),
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "oat".to_string(),
-                name: "Oat Milk".to_string(),
+                name: "Oat Milk".into(),
                 price_adjustment: Some(0.75),
                 nutrition_adjustments: None,
                 allergens: Some(vec!["gluten".to_string()]),
                 dietary_flags: Some(vec!["dairy_free".to_string(), "vegan".to_string()]),
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
         ]),
+        visible_when: None,
     };
 
     // Create espresso shots customization
     let shots = Customization {
         id: "shots".to_string(),
-        name: "Espresso Shots".to_string(),
+        name: "Espresso Shots".into(),
         r#type: CustomizationType::Quantity,
         required: true,
         default: CustomizationDefault::Number(2.0),
@@ -631,12 +755,13 @@ fn create_coffee_shop_template() -> OmsResult<OmsDocument> {
         unit_price_adjustment: Some(0.75),
         unit_nutrition_adjustments: None,
         options: None,
+        visible_when: None,
     };
 
     // Create flavor customization
     let flavor = Customization {
         id: "flavor".to_string(),
-        name: "Flavor Syrup".to_string(),
+        name: "Flavor Syrup".into(),
         r#type: CustomizationType::MultiSelect,
         required: false,
         default: CustomizationDefault::StringArray(vec![]),
@@ -650,38 +775,51 @@ fn create_coffee_shop_template() -> OmsResult<OmsDocument> {
         options: Some(vec![
             CustomizationOption {
                 id: "vanilla".to_string(),
-                name: "Vanilla".to_string(),
+                name: "Vanilla".into(),
                 price_adjustment: Some(0.50),
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "caramel".to_string(),
-                name: "Caramel".to_string(),
+                name: "Caramel".into(),
                 price_adjustment: Some(0.50),
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "hazelnut".to_string(),
-                name: "Hazelnut".to_string(),
+                name: "Hazelnut".into(),
                 price_adjustment: Some(0.50),
                 nutrition_adjustments: None,
                 allergens: Some(vec!["tree-nuts".to_string()]),
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
         ]),
+        visible_when: None,
     };
 
     // Create latte item
     let latte = Item {
         id: "latte".to_string(),
-        name: "Latte".to_string(),
+        name: "Latte".into(),
         category: "coffee".to_string(),
         vendor_id: None,
-        description: Some("Espresso with steamed milk".to_string()),
+        description: Some("Espresso with steamed milk".into()),
         subcategory: None,
         image_url: None,
         base_price: Some(4.50),
@@ -695,20 +833,22 @@ fn create_coffee_shop_template() -> OmsResult<OmsDocument> {
         ]),
         selected_customizations: None,
         quantity: None,
+        line_id: None,
         item_note: None,
         calculated: None,
         components: None,
         availability: None,
         popularity: None,
+        stock: None,
     };
 
     // Create cappuccino item
     let cappuccino = Item {
         id: "cappuccino".to_string(),
-        name: "Cappuccino".to_string(),
+        name: "Cappuccino".into(),
         category: "coffee".to_string(),
         vendor_id: None,
-        description: Some("Espresso with equal parts steamed milk and foamed milk".to_string()),
+        description: Some("Espresso with equal parts steamed milk and foamed milk".into()),
         subcategory: None,
         image_url: None,
         base_price: Some(4.25),
@@ -717,11 +857,13 @@ fn create_coffee_shop_template() -> OmsResult<OmsDocument> {
         customizations: Some(vec![size, milk, shots, flavor]),
         selected_customizations: None,
         quantity: None,
+        line_id: None,
         item_note: None,
         calculated: None,
         components: None,
         availability: None,
         popularity: None,
+        stock: None,
     };
 
     Ok(OmsDocument::new(metadata, vendor, vec![latte, cappuccino]))
@@ -751,7 +893,7 @@ fn create_pizzeria_template() -> OmsResult<OmsDocument> {
     // Create size customization
     let size = Customization {
         id: "size".to_string(),
-        name: "Size".to_string(),
+        name: "Size".into(),
         r#type: CustomizationType::SingleSelect,
         required: true,
         default: CustomizationDefault::String("medium".to_string()),
@@ -765,43 +907,60 @@ fn create_pizzeria_template() -> OmsResult<OmsDocument> {
         options: Some(vec![
             CustomizationOption {
                 id: "small".to_string(),
-                name: "Small (10\")".to_string(),
+                name: "Small (10\")".into(),
                 price_adjustment: Some(-2.00),
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "medium".to_string(),
-                name: "Medium (12\")".to_string(),
+                name: "Medium (12\")".into(),
                 price_adjustment: Some(0.0),
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "large".to_string(),
-                name: "Large (14\")".to_string(),
+                name: "Large (14\")".into(),
                 price_adjustment: Some(2.00),
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "x-large".to_string(),
-                name: "X-Large (16\")".to_string(),
+                name: "X-Large (16\")".into(),
                 price_adjustment: Some(4.00),
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
         ]),
+        visible_when: None,
     };
 
     // Create crust customization
     let crust = Customization {
         id: "crust".to_string(),
-        name: "Crust".to_string(),
+        name: "Crust".into(),
         r#type: CustomizationType::SingleSelect,
         required: true,
         default: CustomizationDefault::String("regular".to_string()),
@@ -815,43 +974,60 @@ fn create_pizzeria_template() -> OmsResult<OmsDocument> {
         options: Some(vec![
             CustomizationOption {
                 id: "regular".to_string(),
-                name: "Regular".to_string(),
+                name: "Regular".into(),
                 price_adjustment: Some(0.0),
                 nutrition_adjustments: None,
                 allergens: Some(vec!["wheat".to_string()]),
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "thin".to_string(),
-                name: "Thin".to_string(),
+                name: "Thin".into(),
                 price_adjustment: Some(0.0),
                 nutrition_adjustments: None,
                 allergens: Some(vec!["wheat".to_string()]),
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "stuffed".to_string(),
-                name: "Cheese-Stuffed".to_string(),
+                name: "Cheese-Stuffed".into(),
                 price_adjustment: Some(2.50),
                 nutrition_adjustments: None,
                 allergens: Some(vec!["wheat".to_string(), "dairy".to_string()]),
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "gluten-free".to_string(),
-                name: "Gluten-Free".to_string(),
+                name: "Gluten-Free".into(),
                 price_adjustment: Some(3.00),
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: Some(vec!["gluten_free".to_string()]),
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
         ]),
+        visible_when: None,
     };
 
     // Create toppings customization
     let toppings = Customization {
         id: "toppings".to_string(),
-        name: "Toppings".to_string(),
+        name: "Toppings".into(),
         r#type: CustomizationType::MultiSelect,
         required: false,
         default: CustomizationDefault::StringArray(vec![]),
@@ -865,62 +1041,87 @@ fn create_pizzeria_template() -> OmsResult<OmsDocument> {
         options: Some(vec![
             CustomizationOption {
                 id: "pepperoni".to_string(),
-                name: "Pepperoni".to_string(),
+                name: "Pepperoni".into(),
                 price_adjustment: Some(1.50),
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "sausage".to_string(),
-                name: "Sausage".to_string(),
+                name: "Sausage".into(),
                 price_adjustment: Some(1.50),
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: None,
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "mushrooms".to_string(),
-                name: "Mushrooms".to_string(),
+                name: "Mushrooms".into(),
                 price_adjustment: Some(1.00),
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: Some(vec!["vegetarian".to_string()]),
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "onions".to_string(),
-                name: "Onions".to_string(),
+                name: "Onions".into(),
                 price_adjustment: Some(1.00),
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: Some(vec!["vegetarian".to_string()]),
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "peppers".to_string(),
-                name: "Bell Peppers".to_string(),
+                name: "Bell Peppers".into(),
                 price_adjustment: Some(1.00),
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: Some(vec!["vegetarian".to_string()]),
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
             CustomizationOption {
                 id: "olives".to_string(),
-                name: "Black Olives".to_string(),
+                name: "Black Olives".into(),
                 price_adjustment: Some(1.00),
                 nutrition_adjustments: None,
                 allergens: None,
                 dietary_flags: Some(vec!["vegetarian".to_string()]),
+                incompatible_with: None,
+                sub_customizations: None,
+                stock: None,
+                ingredients: None,
             },
         ]),
+        visible_when: None,
     };
 
     // Create pizza item
     let pizza = Item {
         id: "cheese-pizza".to_string(),
-        name: "Cheese Pizza".to_string(),
+        name: "Cheese Pizza".into(),
         category: "pizza".to_string(),
         vendor_id: None,
-        description: Some("Classic cheese pizza with tomato sauce and mozzarella".to_string()),
+        description: Some("Classic cheese pizza with tomato sauce and mozzarella".into()),
         subcategory: None,
         image_url: None,
         base_price: Some(12.99),
@@ -929,29 +1130,45 @@ fn create_pizzeria_template() -> OmsResult<OmsDocument> {
         customizations: Some(vec![size, crust, toppings]),
         selected_customizations: None,
         quantity: None,
+        line_id: None,
         item_note: None,
         calculated: None,
         components: None,
         availability: None,
         popularity: None,
+        stock: None,
     };
 
     Ok(OmsDocument::new(metadata, vendor, vec![pizza]))
 }
 
-/// Save an OMS document to a file
+/// Save an OMS document to a file, choosing the format from the file extension
 pub fn save_document_to_file(document: &OmsDocument, path: &Path) -> OmsResult<()> {
-    let json = document.to_json()?;
-    fs::write(path, json)?;
+    let contents = document.to_format(DocumentFormat::from_path(path))?;
+    fs::write(path, contents)?;
     Ok(())
 }
 
-/// Load an OMS document from a file
+/// Load an OMS document from a file, choosing the format from the file extension
 pub fn load_document_from_file(path: &Path) -> OmsResult<OmsDocument> {
     let mut file = fs::File::open(path)?;
     let mut contents = String::new();
     file.read_to_string(&mut contents)?;
-    parse_oms_document(&contents)
+    OmsDocument::from_format(&contents, DocumentFormat::from_path(path))
+}
+
+/// Turn a name into a lowercase, hyphen-separated identifier
+pub(crate) fn slugify(name: &str, fallback: &str) -> String {
+    let slug = name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if slug.is_empty() {
+        fallback.to_string()
+    } else {
+        slug
+    }
 }
 
 /// Calculate price adjustments for selected customizations
@@ -959,73 +1176,99 @@ pub fn calculate_price_adjustments(
     item: &Item,
     selected: &[SelectedCustomization],
 ) -> OmsResult<f64> {
+    match &item.customizations {
+        Some(customizations) => Ok(calculate_customization_adjustments(customizations, selected)),
+        None => Ok(0.0),
+    }
+}
+
+/// Calculate price adjustments for selections against a set of customizations
+///
+/// Customizations hidden by their conditions are ignored, and the adjustments of
+/// sub-customizations are added for each selected option that offers them.
+fn calculate_customization_adjustments(
+    customizations: &[Customization],
+    selected: &[SelectedCustomization],
+) -> f64 {
     let mut total_adjustment = 0.0;
 
-    if let Some(customizations) = &item.customizations {
-        // Create a map of customizations for easy lookup
-        let customization_map: HashMap<&str, &Customization> =
-            customizations.iter().map(|c| (c.id.as_str(), c)).collect();
-
-        // Process each selected customization
-        for selection in selected {
-            let customization = match customization_map.get(selection.customization_id.as_str()) {
-                Some(c) => c,
-                None => continue, // Skip unknown customizations
-            };
-
-            match &customization.r#type {
-                CustomizationType::SingleSelect => {
-                    if let CustomizationSelection::String(selected_id) = &selection.selection {
-                        if let Some(options) = &customization.options {
-                            for option in options {
-                                if option.id == *selected_id {
-                                    if let Some(price_adj) = option.price_adjustment {
-                                        total_adjustment += price_adj;
-                                    }
-                                    break;
-                                }
-                            }
-                        }
+    // Create a map of customizations for easy lookup
+    let customization_map: HashMap<&str, &Customization> =
+        customizations.iter().map(|c| (c.id.as_str(), c)).collect();
+
+    // Process each selected customization
+    for selection in selected {
+        let customization = match customization_map.get(selection.customization_id.as_str()) {
+            Some(c) => c,
+            None => continue, // Skip unknown customizations
+        };
+
+        // Skip customizations that are not shown for these selections
+        if !is_customization_visible(customization, selected) {
+            continue;
+        }
+
+        match &customization.r#type {
+            CustomizationType::SingleSelect | CustomizationType::MultiSelect => {
+                let selected_ids = match &selection.selection {
+                    CustomizationSelection::String(selected_id)
+                        if customization.r#type == CustomizationType::SingleSelect =>
+                    {
+                        std::slice::from_ref(selected_id)
                     }
-                }
-                CustomizationType::MultiSelect => {
-                    if let CustomizationSelection::StringArray(selected_ids) = &selection.selection
+                    CustomizationSelection::StringArray(selected_ids)
+                        if customization.r#type == CustomizationType::MultiSelect =>
                     {
-                        if let Some(options) = &customization.options {
-                            for selected_id in selected_ids {
-                                for option in options {
-                                    if option.id == *selected_id {
-                                        if let Some(price_adj) = option.price_adjustment {
-                                            total_adjustment += price_adj;
-                                        }
-                                        break;
+                        selected_ids.as_slice()
+                    }
+                    _ => continue,
+                };
+
+                if let Some(options) = &customization.options {
+                    for selected_id in selected_ids {
+                        for option in options {
+                            if option.id == *selected_id {
+                                if let Some(price_adj) = option.price_adjustment {
+                                    total_adjustment += price_adj;
+                                }
+
+                                // Add adjustments from the option's own customizations
+                                if let (Some(sub_customizations), Some(sub_selections)) =
+                                    (&option.sub_customizations, &selection.sub_selections)
+                                {
+                                    if let Some(sub_selected) = sub_selections.get(&option.id) {
+                                        total_adjustment += calculate_customization_adjustments(
+                                            sub_customizations,
+                                            sub_selected,
+                                        );
                                     }
                                 }
+                                break;
                             }
                         }
                     }
                 }
-                CustomizationType::Quantity => {
-                    if let CustomizationSelection::Number(quantity) = selection.selection {
-                        if let Some(unit_price_adj) = customization.unit_price_adjustment {
-                            // Get the default quantity
-                            let default_quantity = match &customization.default {
-                                CustomizationDefault::Number(def) => *def,
-                                _ => 0.0, // Fallback if default is not a number
-                            };
-
-                            // Only apply adjustment to the difference from default
-                            total_adjustment += unit_price_adj * (quantity - default_quantity);
-                        }
+            }
+            CustomizationType::Quantity => {
+                if let CustomizationSelection::Number(quantity) = selection.selection {
+                    if let Some(unit_price_adj) = customization.unit_price_adjustment {
+                        // Get the default quantity
+                        let default_quantity = match &customization.default {
+                            CustomizationDefault::Number(def) => *def,
+                            _ => 0.0, // Fallback if default is not a number
+                        };
+
+                        // Only apply adjustment to the difference from default
+                        total_adjustment += unit_price_adj * (quantity - default_quantity);
                     }
                 }
-                // Boolean, Text, and Range don't have price adjustments in this implementation
-                _ => {}
             }
+            // Boolean, Text, and Range don't have price adjustments in this implementation
+            _ => {}
         }
     }
 
-    Ok(total_adjustment)
+    total_adjustment
 }
 
 /// Extract and update only the customization selections from an OMS URL
@@ -1095,6 +1338,7 @@ pub fn extract_and_update_selections(url: &str, document: &mut OmsDocument) -> O
                         selected.push(SelectedCustomization {
                             customization_id: first_customization_id,
                             selection,
+                            sub_selections: None,
                         });
                     }
                 }
@@ -1108,9 +1352,34 @@ pub fn extract_and_update_selections(url: &str, document: &mut OmsDocument) -> O
 /// Generate a complete order from a document
 pub fn generate_order(document: &mut OmsDocument, customer_id: Option<&str>) -> OmsResult<()> {
     // Calculate total price
-    let subtotal = document.calculate_total_price().unwrap_or(0.0);
+    let currency = document.currency().unwrap_or(DEFAULT_CURRENCY).to_string();
+    let subtotal = document.calculate_total_price()?.unwrap_or(0.0);
+    set_generated_order(document, customer_id, subtotal, currency, None)
+}
+
+/// Generate a complete order, converting item prices into one payment currency
+///
+/// The applied exchange rates are stored in the order for auditing.
+pub fn generate_order_in_currency(
+    document: &mut OmsDocument,
+    customer_id: Option<&str>,
+    currency: &str,
+    rates: &ExchangeRates,
+) -> OmsResult<()> {
+    let converted = document.calculate_total_price_in(currency, rates)?;
+    set_generated_order(document, customer_id, converted.total, converted.currency, Some(converted.exchange_rates))
+}
+
+/// Build a draft pickup order with tax for a subtotal and set it on the document
+fn set_generated_order(
+    document: &mut OmsDocument,
+    customer_id: Option<&str>,
+    subtotal: f64,
+    currency: String,
+    exchange_rates: Option<Vec<ExchangeRateRecord>>,
+) -> OmsResult<()> {
     let tax_rate = 0.08; // 8% tax rate
-    let tax = (subtotal * tax_rate * 100.0).round() / 100.0; // Round to 2 decimal places
+    let tax = round_to_currency(subtotal * tax_rate, &currency)?; // Round to the currency's minor unit
     let total = subtotal + tax;
 
     // Create an order
@@ -1129,7 +1398,7 @@ pub fn generate_order(document: &mut OmsDocument, customer_id: Option<&str>) ->
             tax: Some(tax),
             tip: None,
             total,
-            currency: "USD".to_string(),
+            currency,
         }),
         customer: customer_id.map(|id| Customer {
             id: Some(id.to_string()),
@@ -1138,6 +1407,7 @@ pub fn generate_order(document: &mut OmsDocument, customer_id: Option<&str>) ->
             email: None,
         }),
         delivery: None,
+        exchange_rates: exchange_rates.filter(|rates| !rates.is_empty()),
     };
 
     document.set_order(order);
@@ -1239,7 +1509,7 @@ mod tests {
     #[test]
     fn test_calculate_price_adjustments() {
         // Create an item with customizations
-        let mut doc = create_template("coffee-shop").unwrap();
+        let doc = create_template("coffee-shop").unwrap();
         let item = &doc.items[0]; // Latte
 
         // Create some selections
@@ -1247,14 +1517,17 @@ mod tests {
             SelectedCustomization {
                 customization_id: "size".to_string(),
                 selection: CustomizationSelection::String("large".to_string()),
+                sub_selections: None,
             },
             SelectedCustomization {
                 customization_id: "milk".to_string(),
                 selection: CustomizationSelection::String("almond".to_string()),
+                sub_selections: None,
             },
             SelectedCustomization {
                 customization_id: "shots".to_string(),
                 selection: CustomizationSelection::Number(3.0),
+                sub_selections: None,
             },
             SelectedCustomization {
                 customization_id: "flavor".to_string(),
@@ -1262,6 +1535,7 @@ mod tests {
                     "vanilla".to_string(),
                     "caramel".to_string(),
                 ]),
+                sub_selections: None,
             },
         ];
 
@@ -1278,6 +1552,96 @@ mod tests {
         assert_eq!(adjustment, 3.00);
     }
 
+    #[test]
+    fn test_calculate_conditional_price_adjustments() {
+        let mut doc = create_template("fast-food").unwrap();
+        let combo = &mut doc.items[0];
+
+        // Onion rings come with a dip choice; the dip upsell is only offered with a cola
+        let onion_rings = &mut combo.customizations.as_mut().unwrap()[1].options.as_mut().unwrap()[1];
+        onion_rings.sub_customizations = Some(vec![Customization {
+            id: "dip".to_string(),
+            name: "Dip".into(),
+            r#type: CustomizationType::SingleSelect,
+            required: true,
+            default: CustomizationDefault::String("ketchup".to_string()),
+            min_selections: None,
+            max_selections: None,
+            min: None,
+            max: None,
+            step: None,
+            unit_price_adjustment: None,
+            unit_nutrition_adjustments: None,
+            options: Some(vec![
+                CustomizationOption {
+                    id: "ketchup".to_string(),
+                    name: "Ketchup".into(),
+                    price_adjustment: None,
+                    nutrition_adjustments: None,
+                    allergens: None,
+                    dietary_flags: None,
+                    incompatible_with: None,
+                    sub_customizations: None,
+                    stock: None,
+                    ingredients: None,
+                },
+                CustomizationOption {
+                    id: "chipotle-mayo".to_string(),
+                    name: "Chipotle Mayo".into(),
+                    price_adjustment: Some(0.50),
+                    nutrition_adjustments: None,
+                    allergens: Some(vec!["eggs".to_string()]),
+                    dietary_flags: None,
+                    incompatible_with: None,
+                    sub_customizations: None,
+                    stock: None,
+                    ingredients: None,
+                },
+            ]),
+            visible_when: None,
+        }]);
+
+        let mut upsize = combo.customizations.as_ref().unwrap()[0].clone();
+        upsize.id = "upsize".to_string();
+        upsize.required = false;
+        upsize.options.as_mut().unwrap()[0].price_adjustment = Some(0.80);
+        upsize.visible_when = Some(vec![CustomizationCondition {
+            customization_id: "drink".to_string(),
+            option_ids: vec!["cola".to_string()],
+        }]);
+        combo.customizations.as_mut().unwrap().push(upsize);
+
+        let item = &doc.items[0];
+        let selections = vec![
+            SelectedCustomization {
+                customization_id: "drink".to_string(),
+                selection: CustomizationSelection::String("diet-cola".to_string()),
+                sub_selections: None,
+            },
+            SelectedCustomization {
+                customization_id: "side".to_string(),
+                selection: CustomizationSelection::String("onion-rings".to_string()),
+                sub_selections: Some(HashMap::from([(
+                    "onion-rings".to_string(),
+                    vec![SelectedCustomization {
+                        customization_id: "dip".to_string(),
+                        selection: CustomizationSelection::String("chipotle-mayo".to_string()),
+                        sub_selections: None,
+                    }],
+                )])),
+            },
+            SelectedCustomization {
+                customization_id: "upsize".to_string(),
+                selection: CustomizationSelection::String("cola".to_string()),
+                sub_selections: None,
+            },
+        ];
+
+        // Onion rings (+1.00) and chipotle mayo (+0.50); the upsize is hidden for diet cola
+        let adjustment = calculate_price_adjustments(item, &selections).unwrap();
+        assert_eq!(adjustment, 1.50);
+    }
+
     #[test]
     fn test_extract_and_update_selections() {
         let mut doc = create_template("coffee-shop").unwrap();
@@ -4,6 +4,9 @@
 
 use crate::{OmsError, OmsResult};
 use crate::types::*;
+use crate::units::Unit;
+use crate::currency::is_iso_currency;
+use crate::localization::{validate_localized_text, visit_item};
 use validator::ValidationError;
 
 /// Validates a complete OmsDocument
@@ -13,22 +16,15 @@ pub fn validate_document(document: &OmsDocument) -> OmsResult<()> {
         return Err(OmsError::ValidationError(validator::ValidationErrors::new()));
     }
     
-    // Validate each item's customizations
+    // Validate each item on its own, with ingredient references checked against the catalog
+    let catalog = document.ingredients.as_deref().unwrap_or(&[]);
     for item in &document.items {
-        if let Some(customizations) = &item.customizations {
-            validate_customizations(customizations)?;
-        }
-        
-        // Validate selected customizations against available customizations
-        if let Some(selected) = &item.selected_customizations {
-            if let Some(available) = &item.customizations {
-                validate_selected_customizations(selected, available)?;
-            } else {
-                return Err(OmsError::ValidationError(validator::ValidationErrors::new()));
-            }
-        }
+        validate_item(item, Some(catalog))?;
     }
     
+    // Check that currencies are real ISO 4217 codes and agree with each other
+    validate_currencies(document)?;
+    
     // If order exists, validate it
     if let Some(order) = &document.order {
         validate_order(order, &document.items)?;
@@ -37,6 +33,244 @@ pub fn validate_document(document: &OmsDocument) -> OmsResult<()> {
     Ok(())
 }
 
+/// Validates a single item without looking at the rest of the document
+///
+/// Ingredient references are only checked when a catalog is given.
+pub fn validate_item(item: &Item, catalog: Option<&[Ingredient]>) -> OmsResult<()> {
+    if let Some(customizations) = &item.customizations {
+        validate_customizations(customizations)?;
+    }
+    
+    // Validate selected customizations against available customizations
+    if let Some(selected) = &item.selected_customizations {
+        if let Some(available) = &item.customizations {
+            validate_selected_customizations(selected, available)?;
+        } else {
+            return Err(OmsError::ValidationError(validator::ValidationErrors::new()));
+        }
+    }
+    
+    if let Some(catalog) = catalog {
+        validate_item_ingredients(item, catalog)?;
+    }
+    validate_item_units(item)?;
+    
+    let mut currencies = Vec::new();
+    collect_item_currencies(item, &mut currencies);
+    for (path, currency) in currencies {
+        if !is_iso_currency(currency) {
+            return Err(OmsError::InvalidCurrency(format!("{} in {}", currency, path)));
+        }
+    }
+    
+    // Check that translations are keyed by valid language tags
+    let mut localization_result = Ok(());
+    visit_item(item, &format!("items[{}]", item.id), &mut |path, text| {
+        if localization_result.is_ok() {
+            localization_result = validate_localized_text(text, path);
+        }
+    });
+    localization_result
+}
+
+/// Validates that an item's ingredient references exist in the ingredient catalog
+fn validate_item_ingredients(item: &Item, catalog: &[Ingredient]) -> OmsResult<()> {
+    let usages = item.nutrition.iter()
+        .flat_map(|nutrition| nutrition.ingredients.iter().flatten())
+        .flat_map(|group| group.ingredients.iter())
+        .filter_map(|entry| match entry {
+            IngredientEntry::Usage(usage) => Some(usage),
+            IngredientEntry::Name(_) => None,
+        });
+    
+    for usage in usages {
+        validate_ingredient_reference(usage, catalog)?;
+    }
+    
+    if let Some(customizations) = &item.customizations {
+        validate_option_ingredients(customizations, catalog)?;
+    }
+    
+    for component in item.components.iter().flatten() {
+        validate_item_ingredients(component, catalog)?;
+    }
+    
+    Ok(())
+}
+
+/// Validates that option ingredient references exist in the ingredient catalog
+fn validate_option_ingredients(customizations: &[Customization], catalog: &[Ingredient]) -> OmsResult<()> {
+    for option in customizations.iter().flat_map(|c| c.options.iter().flatten()) {
+        for usage in option.ingredients.iter().flatten() {
+            validate_ingredient_reference(usage, catalog)?;
+        }
+        
+        if let Some(sub_customizations) = &option.sub_customizations {
+            validate_option_ingredients(sub_customizations, catalog)?;
+        }
+    }
+    
+    Ok(())
+}
+
+/// Validates a single ingredient reference
+fn validate_ingredient_reference(usage: &IngredientUsage, catalog: &[Ingredient]) -> OmsResult<()> {
+    if !catalog.iter().any(|ingredient| ingredient.id == usage.ingredient_id) {
+        return Err(OmsError::InvalidFieldValue(format!(
+            "ingredient {} not found in ingredient catalog",
+            usage.ingredient_id
+        )));
+    }
+    
+    Ok(())
+}
+
+/// Validates that item and payment currencies are ISO 4217 codes and consistent
+fn validate_currencies(document: &OmsDocument) -> OmsResult<()> {
+    let mut currencies: Vec<(String, &str)> = Vec::new();
+    for item in &document.items {
+        collect_item_currencies(item, &mut currencies);
+    }
+    if let Some(payment) = document.order.as_ref().and_then(|order| order.payment.as_ref()) {
+        currencies.push(("order.payment".to_string(), &payment.currency));
+    }
+    
+    for (path, currency) in &currencies {
+        if !is_iso_currency(currency) {
+            return Err(OmsError::InvalidCurrency(format!("{} in {}", currency, path)));
+        }
+    }
+    
+    // Prices in other currencies are allowed when the order records how they were converted
+    let exchange_rates = document.order.as_ref()
+        .and_then(|order| order.exchange_rates.as_deref())
+        .unwrap_or(&[]);
+    for record in exchange_rates {
+        if !is_iso_currency(&record.from) || !is_iso_currency(&record.to) {
+            return Err(OmsError::InvalidCurrency(format!("{} to {} in order.exchange_rates", record.from, record.to)));
+        }
+        if !(record.rate.is_finite() && record.rate > 0.0) {
+            return Err(OmsError::InvalidFieldValue(format!(
+                "exchange rate from {} to {} must be positive",
+                record.from, record.to
+            )));
+        }
+    }
+    
+    // The payment currency wins, otherwise the first currency seen
+    if let Some((target_path, target)) = currencies.last().filter(|(path, _)| path == "order.payment").or(currencies.first()) {
+        let mismatch = currencies.iter().find(|(_, currency)| {
+            currency != target
+                && !exchange_rates.iter().any(|record| record.from == *currency && record.to == *target)
+        });
+        if let Some((path, currency)) = mismatch {
+            return Err(OmsError::CurrencyMismatch(format!(
+                "{} uses {} but {} uses {}",
+                path, currency, target_path, target
+            )));
+        }
+    }
+    
+    Ok(())
+}
+
+/// Collects the currencies of an item and its components with their paths
+fn collect_item_currencies<'a>(item: &'a Item, currencies: &mut Vec<(String, &'a str)>) {
+    if let Some(currency) = &item.currency {
+        currencies.push((format!("items[{}]", item.id), currency));
+    }
+    
+    for component in item.components.iter().flatten() {
+        collect_item_currencies(component, currencies);
+    }
+}
+
+/// Validates that every measurement on an item uses a known unit
+fn validate_item_units(item: &Item) -> OmsResult<()> {
+    if let Some(nutrition) = &item.nutrition {
+        let measurements = [&nutrition.serving_size, &nutrition.protein, &nutrition.sodium, &nutrition.cholesterol];
+        for measurement in measurements.into_iter().flatten() {
+            validate_unit(&measurement.unit)?;
+        }
+        
+        for nutrient in [&nutrition.fat, &nutrition.carbohydrates].into_iter().flatten() {
+            validate_detailed_nutrient_units(nutrient)?;
+        }
+        
+        let vitamins_minerals = nutrition.vitamins.iter().chain(&nutrition.minerals).flatten();
+        for entry in vitamins_minerals {
+            validate_unit(&entry.unit)?;
+        }
+        
+        let usages = nutrition.ingredients.iter().flatten()
+            .flat_map(|group| group.ingredients.iter())
+            .filter_map(|entry| match entry {
+                IngredientEntry::Usage(usage) => Some(usage),
+                IngredientEntry::Name(_) => None,
+            });
+        for usage in usages {
+            validate_unit(&usage.quantity.unit)?;
+        }
+    }
+    
+    if let Some(customizations) = &item.customizations {
+        validate_customization_units(customizations)?;
+    }
+    
+    for component in item.components.iter().flatten() {
+        validate_item_units(component)?;
+    }
+    
+    Ok(())
+}
+
+/// Validates the units of customization and option nutrition adjustments
+fn validate_customization_units(customizations: &[Customization]) -> OmsResult<()> {
+    for customization in customizations {
+        for adjustment in customization.unit_nutrition_adjustments.iter().flat_map(|a| a.values()) {
+            validate_nutrient_units(adjustment)?;
+        }
+        
+        for option in customization.options.iter().flatten() {
+            for adjustment in option.nutrition_adjustments.iter().flat_map(|a| a.values()) {
+                validate_nutrient_units(adjustment)?;
+            }
+            
+            for usage in option.ingredients.iter().flatten() {
+                validate_unit(&usage.quantity.unit)?;
+            }
+            
+            if let Some(sub_customizations) = &option.sub_customizations {
+                validate_customization_units(sub_customizations)?;
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Validates the units of a nutrient value and its breakdown
+fn validate_nutrient_units(value: &NutrientValue) -> OmsResult<()> {
+    match value {
+        NutrientValue::Simple(measurement) => validate_unit(&measurement.unit),
+        NutrientValue::Detailed(nutrient) => validate_detailed_nutrient_units(nutrient),
+    }
+}
+
+/// Validates the units of a nutrient with a breakdown
+fn validate_detailed_nutrient_units(nutrient: &NutrientWithDetails) -> OmsResult<()> {
+    validate_unit(&nutrient.unit)?;
+    for detail in nutrient.details.iter().flat_map(|d| d.values()) {
+        validate_nutrient_units(detail)?;
+    }
+    Ok(())
+}
+
+/// Validates that a unit string names a known unit
+fn validate_unit(unit: &str) -> OmsResult<()> {
+    unit.parse::<Unit>().map(|_| ())
+}
+
 /// Validates customization definitions
 fn validate_customizations(customizations: &[Customization]) -> OmsResult<()> {
     for customization in customizations {
@@ -190,11 +424,89 @@ fn validate_customizations(customizations: &[Customization]) -> OmsResult<()> {
         }
     }
     
+    validate_customization_dependencies(customizations)?;
+    
+    Ok(())
+}
+
+/// Validates conditions, incompatibilities and sub-customizations of customization definitions
+fn validate_customization_dependencies(customizations: &[Customization]) -> OmsResult<()> {
+    let find_option = |customization_id: &str, option_id: &str| {
+        customizations.iter()
+            .find(|c| c.id == customization_id)
+            .map(|c| match c.r#type {
+                CustomizationType::Boolean => option_id == "true" || option_id == "false",
+                _ => c.options.iter().flatten().any(|opt| opt.id == option_id),
+            })
+            .unwrap_or(false)
+    };
+    
+    for customization in customizations {
+        // Check that conditions refer to existing sibling options
+        for condition in customization.visible_when.iter().flatten() {
+            if condition.customization_id == customization.id {
+                return Err(OmsError::InvalidFieldValue(format!(
+                    "customization {} cannot depend on itself",
+                    customization.id
+                )));
+            }
+            
+            for option_id in &condition.option_ids {
+                if !find_option(&condition.customization_id, option_id) {
+                    return Err(OmsError::InvalidFieldValue(format!(
+                        "condition on '{}' of customization {} not found in customization {}",
+                        option_id, condition.customization_id, customization.id
+                    )));
+                }
+            }
+        }
+        
+        for option in customization.options.iter().flatten() {
+            // Check that incompatible options exist
+            for reference in option.incompatible_with.iter().flatten() {
+                if !find_option(&reference.customization_id, &reference.option_id) {
+                    return Err(OmsError::InvalidFieldValue(format!(
+                        "incompatible option '{}' of customization {} not found for option '{}'",
+                        reference.option_id, reference.customization_id, option.id
+                    )));
+                }
+            }
+            
+            // Validate nested customizations recursively
+            if let Some(sub_customizations) = &option.sub_customizations {
+                validate_customizations(sub_customizations)?;
+            }
+        }
+    }
+    
     Ok(())
 }
 
+/// Get the option IDs chosen by a selection (`true`/`false` for booleans)
+pub(crate) fn selected_option_ids(selection: &CustomizationSelection) -> Vec<String> {
+    match selection {
+        CustomizationSelection::String(id) => vec![id.clone()],
+        CustomizationSelection::StringArray(ids) => ids.clone(),
+        CustomizationSelection::Boolean(value) => vec![value.to_string()],
+        CustomizationSelection::Number(_) => Vec::new(),
+    }
+}
+
+/// Whether a customization is shown given the current selections
+pub fn is_customization_visible(customization: &Customization, selected: &[SelectedCustomization]) -> bool {
+    customization.visible_when.iter().flatten().all(|condition| {
+        selected.iter()
+            .filter(|sel| sel.customization_id == condition.customization_id)
+            .any(|sel| {
+                selected_option_ids(&sel.selection)
+                    .iter()
+                    .any(|id| condition.option_ids.contains(id))
+            })
+    })
+}
+
 /// Validates selected customizations against available customizations
-fn validate_selected_customizations(
+pub(crate) fn validate_selected_customizations(
     selected: &[SelectedCustomization],
     available: &[Customization]
 ) -> OmsResult<()> {
@@ -206,13 +518,14 @@ fn validate_selected_customizations(
     
     // Check that all required customizations are selected
     for customization in available {
-        if customization.required {
-            if !selected.iter().any(|sel| sel.customization_id == customization.id) {
-                return Err(OmsError::MissingRequiredField(format!(
-                    "required customization {} not selected", 
-                    customization.id
-                )));
-            }
+        if customization.required
+            && is_customization_visible(customization, selected)
+            && !selected.iter().any(|sel| sel.customization_id == customization.id)
+        {
+            return Err(OmsError::MissingRequiredField(format!(
+                "required customization {} not selected", 
+                customization.id
+            )));
         }
     }
     
@@ -227,6 +540,14 @@ fn validate_selected_customizations(
             ))),
         };
         
+        // Check that the customization is shown for the current selections
+        if !is_customization_visible(customization, selected) {
+            return Err(OmsError::InvalidFieldValue(format!(
+                "selected customization {} is not available for the current selections",
+                customization.id
+            )));
+        }
+        
         // Validate the selection based on customization type
         match customization.r#type {
             CustomizationType::SingleSelect => {
@@ -361,6 +682,67 @@ fn validate_selected_customizations(
                 }
             },
         }
+        
+        // Validate incompatibilities and nested selections of the chosen options
+        let chosen = selected_option_ids(&selection.selection);
+        let chosen_options: Vec<&CustomizationOption> = customization.options.iter()
+            .flatten()
+            .filter(|opt| chosen.contains(&opt.id))
+            .collect();
+        
+        for option in &chosen_options {
+            validate_selected_option(option, selection, selected)?;
+        }
+        
+        for option_id in selection.sub_selections.iter().flat_map(|subs| subs.keys()) {
+            let has_sub_customizations = chosen_options.iter()
+                .any(|opt| &opt.id == option_id && opt.sub_customizations.is_some());
+            
+            if !has_sub_customizations {
+                return Err(OmsError::InvalidFieldValue(format!(
+                    "sub-selections for '{}' do not match a selected option with sub-customizations in customization {}",
+                    option_id, customization.id
+                )));
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Validates a chosen option against incompatible options and its sub-customizations
+fn validate_selected_option(
+    option: &CustomizationOption,
+    selection: &SelectedCustomization,
+    selected: &[SelectedCustomization],
+) -> OmsResult<()> {
+    if is_sold_out(option.stock.as_ref()) {
+        return Err(OmsError::InvalidFieldValue(format!(
+            "selected option '{}' is sold out",
+            option.id
+        )));
+    }
+    
+    for reference in option.incompatible_with.iter().flatten() {
+        let conflicting = selected.iter()
+            .filter(|sel| sel.customization_id == reference.customization_id)
+            .any(|sel| selected_option_ids(&sel.selection).contains(&reference.option_id));
+        
+        if conflicting {
+            return Err(OmsError::InvalidFieldValue(format!(
+                "option '{}' cannot be combined with option '{}' of customization {}",
+                option.id, reference.option_id, reference.customization_id
+            )));
+        }
+    }
+    
+    if let Some(sub_customizations) = &option.sub_customizations {
+        let sub_selected = selection.sub_selections.as_ref()
+            .and_then(|subs| subs.get(&option.id))
+            .map(Vec::as_slice)
+            .unwrap_or(&[]);
+        
+        validate_selected_customizations(sub_selected, sub_customizations)?;
     }
     
     Ok(())
@@ -373,6 +755,11 @@ fn validate_order(order: &Order, items: &[Item]) -> OmsResult<()> {
         return Err(OmsError::ValidationError(validator::ValidationErrors::new()));
     }
     
+    // Check that ordered items are in stock
+    for item in items {
+        validate_item_in_stock(item, item.quantity.unwrap_or(1))?;
+    }
+    
     // Validate payment information
     if let Some(payment) = &order.payment {
         // Check that total is greater than zero
@@ -395,7 +782,7 @@ fn validate_order(order: &Order, items: &[Item]) -> OmsResult<()> {
     }
     
     // Validate delivery information
-    if let Some(delivery) = &order.delivery {
+    if order.delivery.is_some() {
         // If delivery type is specified, it should be "delivery"
         if let Some(order_type) = &order.r#type {
             if *order_type != OrderType::Delivery {
@@ -418,6 +805,29 @@ fn validate_order(order: &Order, items: &[Item]) -> OmsResult<()> {
     Ok(())
 }
 
+/// Validates that an item can be ordered in the given quantity
+pub(crate) fn validate_item_in_stock(item: &Item, quantity: u32) -> OmsResult<()> {
+    if is_sold_out(item.stock.as_ref()) {
+        return Err(OmsError::InvalidFieldValue(format!("item {} is sold out", item.id)));
+    }
+    
+    if let Some(remaining) = item.stock.as_ref().and_then(|stock| stock.quantity) {
+        if quantity > remaining {
+            return Err(OmsError::InvalidFieldValue(format!(
+                "ordered quantity {} exceeds remaining stock {} for item {}",
+                quantity, remaining, item.id
+            )));
+        }
+    }
+    
+    Ok(())
+}
+
+/// Whether stock information marks an entry as sold out
+fn is_sold_out(stock: Option<&StockInfo>) -> bool {
+    matches!(stock, Some(StockInfo { status: StockStatus::SoldOut, .. }))
+}
+
 /// Validation function for customization type
 pub fn validate_customization_type(type_str: &str) -> Result<(), ValidationError> {
     let valid_types = [
@@ -454,7 +864,6 @@ pub fn validate_vendor_type(type_str: &str) -> Result<(), ValidationError> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::*;
     
     #[test]
     fn test_validate_empty_document() {
@@ -478,6 +887,7 @@ mod tests {
                 cuisine: None,
                 services: None,
             },
+            ingredients: None,
             items: vec![],
             order: None,
             extensions: None,
@@ -493,7 +903,7 @@ mod tests {
         // Valid single_select customization
         let single_select = Customization {
             id: "test-single".to_string(),
-            name: "Test Single".to_string(),
+            name: "Test Single".into(),
             r#type: CustomizationType::SingleSelect,
             required: true,
             default: CustomizationDefault::String("option1".to_string()),
@@ -507,25 +917,34 @@ mod tests {
             options: Some(vec![
                 CustomizationOption {
                     id: "option1".to_string(),
-                    name: "Option 1".to_string(),
+                    name: "Option 1".into(),
                     price_adjustment: None,
                     nutrition_adjustments: None,
                     allergens: None,
                     dietary_flags: None,
+                    incompatible_with: None,
+                    sub_customizations: None,
+                    stock: None,
+                    ingredients: None,
                 },
                 CustomizationOption {
                     id: "option2".to_string(),
-                    name: "Option 2".to_string(),
+                    name: "Option 2".into(),
                     price_adjustment: None,
                     nutrition_adjustments: None,
                     allergens: None,
                     dietary_flags: None,
+                    incompatible_with: None,
+                    sub_customizations: None,
+                    stock: None,
+                    ingredients: None,
                 },
             ]),
+            visible_when: None,
         };
         
         // Test valid customization
-        let result = validate_customizations(&[single_select.clone()]);
+        let result = validate_customizations(std::slice::from_ref(&single_select));
         assert!(result.is_ok());
         
         // Test invalid default value
@@ -553,7 +972,7 @@ mod tests {
         let customizations = vec![
             Customization {
                 id: "test-single".to_string(),
-                name: "Test Single".to_string(),
+                name: "Test Single".into(),
                 r#type: CustomizationType::SingleSelect,
                 required: true,
                 default: CustomizationDefault::String("option1".to_string()),
@@ -567,25 +986,34 @@ mod tests {
                 options: Some(vec![
                     CustomizationOption {
                         id: "option1".to_string(),
-                        name: "Option 1".to_string(),
+                        name: "Option 1".into(),
                         price_adjustment: None,
                         nutrition_adjustments: None,
                         allergens: None,
                         dietary_flags: None,
+                        incompatible_with: None,
+                        sub_customizations: None,
+                        stock: None,
+                        ingredients: None,
                     },
                     CustomizationOption {
                         id: "option2".to_string(),
-                        name: "Option 2".to_string(),
+                        name: "Option 2".into(),
                         price_adjustment: None,
                         nutrition_adjustments: None,
                         allergens: None,
                         dietary_flags: None,
+                        incompatible_with: None,
+                        sub_customizations: None,
+                        stock: None,
+                        ingredients: None,
                     },
                 ]),
+                visible_when: None,
             },
             Customization {
                 id: "test-multi".to_string(),
-                name: "Test Multi".to_string(),
+                name: "Test Multi".into(),
                 r#type: CustomizationType::MultiSelect,
                 required: false,
                 default: CustomizationDefault::StringArray(vec!["option1".to_string()]),
@@ -599,21 +1027,30 @@ mod tests {
                 options: Some(vec![
                     CustomizationOption {
                         id: "option1".to_string(),
-                        name: "Option 1".to_string(),
+                        name: "Option 1".into(),
                         price_adjustment: None,
                         nutrition_adjustments: None,
                         allergens: None,
                         dietary_flags: None,
+                        incompatible_with: None,
+                        sub_customizations: None,
+                        stock: None,
+                        ingredients: None,
                     },
                     CustomizationOption {
                         id: "option2".to_string(),
-                        name: "Option 2".to_string(),
+                        name: "Option 2".into(),
                         price_adjustment: None,
                         nutrition_adjustments: None,
                         allergens: None,
                         dietary_flags: None,
+                        incompatible_with: None,
+                        sub_customizations: None,
+                        stock: None,
+                        ingredients: None,
                     },
                 ]),
+                visible_when: None,
             },
         ];
         
@@ -622,10 +1059,12 @@ mod tests {
             SelectedCustomization {
                 customization_id: "test-single".to_string(),
                 selection: CustomizationSelection::String("option2".to_string()),
+                sub_selections: None,
             },
             SelectedCustomization {
                 customization_id: "test-multi".to_string(),
                 selection: CustomizationSelection::StringArray(vec!["option1".to_string(), "option2".to_string()]),
+                sub_selections: None,
             },
         ];
         
@@ -638,6 +1077,7 @@ mod tests {
             SelectedCustomization {
                 customization_id: "test-multi".to_string(),
                 selection: CustomizationSelection::StringArray(vec!["option1".to_string()]),
+                sub_selections: None,
             },
         ];
         let result = validate_selected_customizations(&missing_required, &customizations);
@@ -648,6 +1088,7 @@ mod tests {
             SelectedCustomization {
                 customization_id: "test-single".to_string(),
                 selection: CustomizationSelection::String("nonexistent".to_string()),
+                sub_selections: None,
             },
         ];
         let result = validate_selected_customizations(&invalid_selection, &customizations);
@@ -658,6 +1099,7 @@ mod tests {
             SelectedCustomization {
                 customization_id: "test-single".to_string(),
                 selection: CustomizationSelection::Number(1.0),
+                sub_selections: None,
             },
         ];
         let result = validate_selected_customizations(&invalid_type, &customizations);
@@ -668,19 +1110,154 @@ mod tests {
             SelectedCustomization {
                 customization_id: "nonexistent".to_string(),
                 selection: CustomizationSelection::String("option1".to_string()),
+                sub_selections: None,
             },
         ];
         let result = validate_selected_customizations(&nonexistent, &customizations);
         assert!(result.is_err());
     }
     
+    fn option(id: &str, price_adjustment: Option<f64>) -> CustomizationOption {
+        CustomizationOption {
+            id: id.to_string(),
+            name: id.into(),
+            price_adjustment,
+            nutrition_adjustments: None,
+            allergens: None,
+            dietary_flags: None,
+            incompatible_with: None,
+            sub_customizations: None,
+            stock: None,
+            ingredients: None,
+        }
+    }
+    
+    fn select(id: &str, r#type: CustomizationType, required: bool, options: Vec<CustomizationOption>) -> Customization {
+        let default = match r#type {
+            CustomizationType::MultiSelect => CustomizationDefault::StringArray(vec![]),
+            _ => CustomizationDefault::String(options[0].id.clone()),
+        };
+        
+        Customization {
+            id: id.to_string(),
+            name: id.into(),
+            r#type,
+            required,
+            default,
+            min_selections: None,
+            max_selections: None,
+            min: None,
+            max: None,
+            step: None,
+            unit_price_adjustment: None,
+            unit_nutrition_adjustments: None,
+            options: Some(options),
+            visible_when: None,
+        }
+    }
+    
+    fn selection(customization_id: &str, selection: CustomizationSelection) -> SelectedCustomization {
+        SelectedCustomization {
+            customization_id: customization_id.to_string(),
+            selection,
+            sub_selections: None,
+        }
+    }
+    
+    fn conditional_customizations() -> Vec<Customization> {
+        let style = select("style", CustomizationType::SingleSelect, true, vec![
+            option("wings", None),
+            option("tenders", None),
+        ]);
+        
+        // Sauce is only offered for wings
+        let mut sauce = select("sauce", CustomizationType::SingleSelect, true, vec![
+            option("buffalo", None),
+            option("bbq", None),
+        ]);
+        sauce.visible_when = Some(vec![CustomizationCondition {
+            customization_id: "style".to_string(),
+            option_ids: vec!["wings".to_string()],
+        }]);
+        
+        // Salad comes with its own dressing choice
+        let mut salad = option("salad", Some(1.00));
+        salad.sub_customizations = Some(vec![
+            select("dressing", CustomizationType::SingleSelect, true, vec![
+                option("ranch", None),
+                option("blue-cheese", Some(0.50)),
+            ]),
+        ]);
+        let side = select("side", CustomizationType::SingleSelect, true, vec![
+            option("fries", None),
+            salad,
+        ]);
+        
+        // Vegan cheese excludes regular cheese
+        let mut vegan_cheese = option("vegan-cheese", Some(1.00));
+        vegan_cheese.incompatible_with = Some(vec![OptionReference {
+            customization_id: "extras".to_string(),
+            option_id: "cheese".to_string(),
+        }]);
+        let extras = select("extras", CustomizationType::MultiSelect, false, vec![
+            option("cheese", Some(0.75)),
+            vegan_cheese,
+        ]);
+        
+        vec![style, sauce, side, extras]
+    }
+    
+    #[test]
+    fn test_validate_conditional_customizations() {
+        let customizations = conditional_customizations();
+        assert!(validate_customizations(&customizations).is_ok());
+        
+        let tenders = selection("style", CustomizationSelection::String("tenders".to_string()));
+        let wings = selection("style", CustomizationSelection::String("wings".to_string()));
+        let sauce = selection("sauce", CustomizationSelection::String("bbq".to_string()));
+        let fries = selection("side", CustomizationSelection::String("fries".to_string()));
+        
+        // Sauce is required for wings but hidden for tenders
+        assert!(validate_selected_customizations(&[tenders.clone(), fries.clone()], &customizations).is_ok());
+        assert!(validate_selected_customizations(&[wings.clone(), fries.clone()], &customizations).is_err());
+        assert!(validate_selected_customizations(&[wings.clone(), sauce.clone(), fries.clone()], &customizations).is_ok());
+        assert!(validate_selected_customizations(&[tenders.clone(), sauce, fries.clone()], &customizations).is_err());
+        
+        // Incompatible options cannot be selected together
+        let both_cheeses = selection("extras", CustomizationSelection::StringArray(vec![
+            "cheese".to_string(),
+            "vegan-cheese".to_string(),
+        ]));
+        assert!(validate_selected_customizations(&[tenders.clone(), fries.clone(), both_cheeses], &customizations).is_err());
+        
+        // Salad requires a dressing from its sub-customizations
+        let mut salad = selection("side", CustomizationSelection::String("salad".to_string()));
+        assert!(validate_selected_customizations(&[tenders.clone(), salad.clone()], &customizations).is_err());
+        
+        salad.sub_selections = Some(std::collections::HashMap::from([(
+            "salad".to_string(),
+            vec![selection("dressing", CustomizationSelection::String("ranch".to_string()))],
+        )]));
+        assert!(validate_selected_customizations(&[tenders.clone(), salad.clone()], &customizations).is_ok());
+        
+        // Sub-selections must belong to a selected option
+        let mut fries_with_dressing = salad;
+        fries_with_dressing.selection = CustomizationSelection::String("fries".to_string());
+        assert!(validate_selected_customizations(&[tenders, fries_with_dressing], &customizations).is_err());
+        
+        // Conditions must refer to existing options
+        let mut invalid = customizations;
+        invalid[1].visible_when.as_mut().unwrap()[0].option_ids = vec!["drumsticks".to_string()];
+        assert!(validate_customizations(&invalid).is_err());
+    }
+    
     #[test]
     fn test_validate_order() {
         // Create items for the order
         let items = vec![
             Item {
                 id: "item1".to_string(),
-                name: "Item 1".to_string(),
+                name: "Item 1".into(),
                 category: "test".to_string(),
                 vendor_id: None,
                 description: None,
@@ -692,11 +1269,13 @@ mod tests {
                 customizations: None,
                 selected_customizations: None,
                 quantity: Some(1),
+                line_id: None,
                 item_note: None,
                 calculated: None,
                 components: None,
                 availability: None,
                 popularity: None,
+                stock: None,
             },
         ];
         
@@ -720,6 +1299,7 @@ mod tests {
             }),
             customer: None,
             delivery: None,
+            exchange_rates: None,
         };
         
         // Test valid order
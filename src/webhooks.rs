@@ -0,0 +1,230 @@
+// src/webhooks.rs
+//
+// HMAC-SHA256 signing and verification of order webhooks
+//
+// A webhook carries an order document's canonical JSON as its body and a signature header of the form
+// `t=<unix seconds>,v1=<hex HMAC-SHA256 of "<t>.<body>">`. Receivers check the
+// signature against the raw body and reject timestamps outside the replay window.
+
+use crate::types::OmsDocument;
+use crate::{OmsError, OmsResult};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::time::Duration;
+
+/// Header carrying the signature of a webhook
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "OMS-Signature";
+
+/// How far a webhook's timestamp may be from the receiver's clock by default
+pub const DEFAULT_REPLAY_WINDOW: Duration = Duration::from_secs(300);
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// A signed webhook ready to send
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignedWebhook {
+    /// Body of the webhook
+    pub payload: String,
+
+    /// When the webhook was signed, to whole seconds
+    pub timestamp: DateTime<Utc>,
+
+    /// Value of the [`WEBHOOK_SIGNATURE_HEADER`] header
+    pub signature: String,
+}
+
+/// Signs webhook payloads with a shared secret
+#[derive(Clone)]
+pub struct WebhookSigner {
+    secret: Vec<u8>,
+}
+
+impl std::fmt::Debug for WebhookSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebhookSigner").finish_non_exhaustive()
+    }
+}
+
+impl WebhookSigner {
+    /// Create a signer from a shared secret
+    pub fn new(secret: impl AsRef<[u8]>) -> Self {
+        Self {
+            secret: secret.as_ref().to_vec(),
+        }
+    }
+
+    /// Signature header value of a payload sent at `timestamp`
+    pub fn sign(&self, payload: &str, timestamp: DateTime<Utc>) -> String {
+        let timestamp = timestamp.timestamp();
+        format!("t={},v1={}", timestamp, hex::encode(signature(&self.secret, timestamp, payload.as_bytes())))
+    }
+
+    /// Sign an order document as a webhook sent now
+    pub fn sign_document(&self, document: &OmsDocument) -> OmsResult<SignedWebhook> {
+        self.sign_document_at(document, Utc::now())
+    }
+
+    /// Sign an order document as a webhook sent at `timestamp`
+    pub fn sign_document_at(&self, document: &OmsDocument, timestamp: DateTime<Utc>) -> OmsResult<SignedWebhook> {
+        let payload = document.to_canonical_json()?;
+        let signature = self.sign(&payload, timestamp);
+        Ok(SignedWebhook {
+            payload,
+            timestamp: DateTime::from_timestamp(timestamp.timestamp(), 0).unwrap_or(timestamp),
+            signature,
+        })
+    }
+}
+
+/// Verifies signed webhooks on the receiving side
+///
+/// Several secrets can be accepted at once while a secret is being rotated.
+#[derive(Clone)]
+pub struct WebhookVerifier {
+    secrets: Vec<Vec<u8>>,
+    replay_window: Duration,
+}
+
+impl std::fmt::Debug for WebhookVerifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebhookVerifier")
+            .field("replay_window", &self.replay_window)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WebhookVerifier {
+    /// Create a verifier from a shared secret
+    pub fn new(secret: impl AsRef<[u8]>) -> Self {
+        Self {
+            secrets: vec![secret.as_ref().to_vec()],
+            replay_window: DEFAULT_REPLAY_WINDOW,
+        }
+    }
+
+    /// Also accept signatures made with another secret
+    pub fn with_secret(mut self, secret: impl AsRef<[u8]>) -> Self {
+        self.secrets.push(secret.as_ref().to_vec());
+        self
+    }
+
+    /// Set how far a webhook's timestamp may be from the receiver's clock
+    pub fn with_replay_window(mut self, replay_window: Duration) -> Self {
+        self.replay_window = replay_window;
+        self
+    }
+
+    /// Verify a raw webhook body against its signature header
+    pub fn verify(&self, payload: &[u8], signature_header: &str) -> OmsResult<()> {
+        self.verify_at(payload, signature_header, Utc::now())
+    }
+
+    /// Verify a raw webhook body against its signature header as of `now`
+    pub fn verify_at(&self, payload: &[u8], signature_header: &str, now: DateTime<Utc>) -> OmsResult<()> {
+        let mut timestamp = None;
+        let mut signatures = Vec::new();
+        for part in signature_header.split(',') {
+            match part.trim().split_once('=') {
+                Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+                // Unparsable signatures can never match, so they are skipped
+                Some(("v1", value)) => signatures.extend(hex::decode(value).ok()),
+                _ => {},
+            }
+        }
+        let timestamp = timestamp
+            .ok_or_else(|| OmsError::InvalidSignature("missing timestamp".to_string()))?;
+        if signatures.is_empty() {
+            return Err(OmsError::InvalidSignature("missing v1 signature".to_string()));
+        }
+
+        let age = now.timestamp().abs_diff(timestamp);
+        if age > self.replay_window.as_secs() {
+            return Err(OmsError::InvalidSignature(format!("timestamp {} is outside the replay window", timestamp)));
+        }
+
+        let valid = self.secrets.iter().any(|secret| {
+            signatures.iter().any(|expected| mac(secret, timestamp, payload).verify_slice(expected).is_ok())
+        });
+        if !valid {
+            return Err(OmsError::InvalidSignature("signature does not match".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Verify a webhook and read the order document it carries
+    pub fn verify_document(&self, payload: &[u8], signature_header: &str) -> OmsResult<OmsDocument> {
+        self.verify(payload, signature_header)?;
+        let payload = std::str::from_utf8(payload)
+            .map_err(|_| OmsError::InvalidFieldValue("webhook payload is not valid UTF-8".to_string()))?;
+        OmsDocument::from_json(payload)
+    }
+}
+
+fn mac(secret: &[u8], timestamp: i64, payload: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(payload);
+    mac
+}
+
+fn signature(secret: &[u8], timestamp: i64, payload: &[u8]) -> Vec<u8> {
+    mac(secret, timestamp, payload).finalize().into_bytes().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::OrderStatus;
+    use crate::utils::{create_template, generate_order};
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(seconds, 0).unwrap()
+    }
+
+    #[test]
+    fn test_signature_format() {
+        let signer = WebhookSigner::new("whsec_test");
+        assert_eq!(
+            signer.sign("{\"status\":\"ready\"}", at(1714564800)),
+            "t=1714564800,v1=4af71227d777280d65cad20dd934db66e27bb190488788d95c846b0e0fd03974",
+        );
+    }
+
+    #[test]
+    fn test_sign_and_verify_document() {
+        let mut doc = create_template("cafe").unwrap();
+        generate_order(&mut doc, Some("customer-1")).unwrap();
+        doc.update_order_status(OrderStatus::Confirmed).unwrap();
+        let extensions = doc.extensions.get_or_insert_with(Default::default);
+        extensions.insert("x-pos".to_string(), serde_json::json!({ "lane": 2, "drive_thru": true }));
+        extensions.insert("x-aggregator".to_string(), serde_json::json!("delivery"));
+
+        let signer = WebhookSigner::new("whsec_test");
+        let webhook = signer.sign_document_at(&doc, at(1714564800)).unwrap();
+        // A copy of the document built separately signs identically
+        let copy: OmsDocument = serde_json::from_str(&doc.to_compact_json().unwrap()).unwrap();
+        assert_eq!(signer.sign_document_at(&copy, at(1714564800)).unwrap(), webhook);
+
+        let verifier = WebhookVerifier::new("whsec_old").with_secret("whsec_test");
+        let body = webhook.payload.as_bytes();
+        verifier.verify_at(body, &webhook.signature, at(1714564800 + 299)).unwrap();
+        assert!(matches!(
+            verifier.verify_at(body, &webhook.signature, at(1714564800 + 301)),
+            Err(OmsError::InvalidSignature(_)),
+        ));
+        assert!(matches!(
+            WebhookVerifier::new("whsec_other").verify_at(body, &webhook.signature, at(1714564800)),
+            Err(OmsError::InvalidSignature(_)),
+        ));
+
+        let tampered = webhook.payload.replace("confirmed", "cancelled");
+        assert!(verifier.verify_at(tampered.as_bytes(), &webhook.signature, at(1714564800)).is_err());
+        assert!(verifier.verify_at(body, "v1=00", at(1714564800)).is_err());
+
+        let fresh = signer.sign_document(&doc).unwrap();
+        let received = verifier.verify_document(fresh.payload.as_bytes(), &fresh.signature).unwrap();
+        assert_eq!(received, doc);
+    }
+}